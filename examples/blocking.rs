@@ -0,0 +1,40 @@
+//! Calling the functions from synchronous code, through both blocking runtimes.
+
+use surrealdb::{engine::local::Mem, Surreal};
+use surrealdb_functions::include_fn;
+
+include_fn!{
+    blocking as sync_$;
+    "$CARGO_MANIFEST_DIR/tests/main.surql"
+}
+
+mod on_tokio {
+    surrealdb_functions::include_fn!{
+        blocking as sync_$ tokio;
+        stored as tokio_functions;
+        "$CARGO_MANIFEST_DIR/tests/main.surql"
+    }
+}
+
+fn main() -> surrealdb::Result<()> {
+    // The embedded database still needs a runtime for its own tasks
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let db = rt.block_on(async {
+        let db = Surreal::new::<Mem>(()).await?;
+        db.use_ns("test").use_db("test").await?;
+        surrealdb::Result::Ok(db)
+    })?;
+
+    // Waiting with `futures::executor::block_on`
+    sync_define_functions(&db)?.check()?;
+    let got = nested::sync_greet_value(&db, "blocking".to_string())?;
+    assert_eq!(got.as_deref(), Some("Hello, blocking!"));
+
+    // Waiting on the runtime
+    on_tokio::sync_define_functions(rt.handle(), &db)?.check()?;
+    let mut res = on_tokio::nested::sync_greet(rt.handle(), &db, "tokio".to_string())?.check()?;
+    let got: Option<String> = res.take(0)?;
+    assert_eq!(got.as_deref(), Some("Hello, tokio!"));
+
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use surrealdb::{engine::local::{Db, Mem}, Surreal};
+use surrealdb_functions::SurqlFunctions;
+
+#[derive(SurqlFunctions)]
+#[surql(driver as is; "$CARGO_MANIFEST_DIR/tests/main.surql")]
+struct Client {
+    db: Surreal<Db>,
+}
+
+#[tokio::main]
+async fn main() -> surrealdb::Result<()> {
+    // In-memory database for testing
+    let db = Surreal::new::<Mem>(()).await?;
+    db.use_ns("test").use_db("test").await?;
+
+    // Store the connection once, then call the functions as methods
+    let client = Client { db };
+    client.define_functions().await?.check()?;
+
+    println!("{:?}", client.greet_but_with_number("client", 10).await?.check()?);
+    println!("{:?}", client.nested_greet("client").await?.check()?);
+
+    Ok(())
+}
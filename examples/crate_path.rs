@@ -0,0 +1,34 @@
+//! Generated code going through a re-export of surrealdb instead of `::surrealdb`.
+
+mod db {
+    pub use ::surrealdb;
+}
+
+mod generated {
+    surrealdb_functions::include_fn! {
+        crate as crate::db::surrealdb;
+        driver as is;
+        datastore as ds_$;
+        dispatch;
+        "$CARGO_MANIFEST_DIR/tests/main.surql"
+    }
+}
+
+#[tokio::main]
+async fn main() -> surrealdb::Result<()> {
+    use db::surrealdb::{engine::local::Mem, sql::Value, Surreal};
+
+    let db = Surreal::new::<Mem>(()).await?;
+    db.use_ns("test").use_db("test").await?;
+    generated::define_functions(&db).await?.check()?;
+
+    let mut res = generated::nested::greet(&db, "re-export").await?.check()?;
+    let got: Option<String> = res.take(0)?;
+    assert_eq!(got.as_deref(), Some("Hello, re-export!"));
+
+    let mut res = generated::dispatch(&db, "fn::nested::greet", vec![Value::from("dispatch")]).await?.check()?;
+    let got: Option<String> = res.take(0)?;
+    assert_eq!(got.as_deref(), Some("Hello, dispatch!"));
+
+    Ok(())
+}
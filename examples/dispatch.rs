@@ -0,0 +1,31 @@
+use surrealdb::{engine::local::Mem, sql::Value, Surreal};
+use surrealdb_functions::include_fn;
+
+include_fn!{
+    driver as is;
+    dispatch;
+    "$CARGO_MANIFEST_DIR/tests/main.surql"
+}
+
+#[tokio::main]
+async fn main() -> surrealdb::Result<()> {
+    // In-memory database for testing
+    let db = Surreal::new::<Mem>(()).await?;
+    db.use_ns("test").use_db("test").await?;
+    define_functions(&db).await?.check()?;
+
+    // Names could come from a script at runtime
+    let mut res = dispatch(&db, "fn::nested::greet", vec![Value::from("dispatch")]).await?.check()?;
+    let got: Option<String> = res.take(0)?;
+    assert_eq!(got.as_deref(), Some("Hello, dispatch!"));
+
+    let mut res = dispatch(&db, "greet_but_with_number", vec![Value::from("dispatch"), Value::from(10)]).await?.check()?;
+    let got: Option<String> = res.take(0)?;
+    println!("{got:?}");
+
+    // Mistakes are caught before reaching the database
+    assert!(dispatch(&db, "nested::greet", vec![]).await.is_err());
+    assert!(dispatch(&db, "fn::unknown", vec![]).await.is_err());
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+//! Functions generated for `Surreal<Any>` only, so they can sit behind a `dyn` interface.
+
+use std::{future::Future, pin::Pin};
+
+use surrealdb::engine::any::{connect, Any};
+use surrealdb::Surreal;
+use surrealdb_functions::include_fn;
+
+include_fn!{
+    driver as is;
+    engine as any;
+    "$CARGO_MANIFEST_DIR/tests/main.surql"
+}
+
+/// What the rest of the application depends on, without knowing about surrealdb.
+trait Greeter {
+    fn greet<'a>(&'a self, name: &'a str) -> Pin<Box<dyn Future<Output = surrealdb::Result<Option<String>>> + Send + 'a>>;
+}
+
+impl Greeter for Surreal<Any> {
+    fn greet<'a>(&'a self, name: &'a str) -> Pin<Box<dyn Future<Output = surrealdb::Result<Option<String>>> + Send + 'a>> {
+        Box::pin(nested::greet_value(self, name.to_string()))
+    }
+}
+
+#[tokio::main]
+async fn main() -> surrealdb::Result<()> {
+    let db = connect("mem://").await?;
+    db.use_ns("test").use_db("test").await?;
+    define_functions(&db).await?.check()?;
+
+    let greeter: Box<dyn Greeter> = Box::new(db);
+    let got = greeter.greet("any").await?;
+    assert_eq!(got.as_deref(), Some("Hello, any!"));
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use surrealdb::{dbs::Session, kvs::Datastore};
+use surrealdb_functions::include_fn;
+
+// The driver flavor only exists when the `remote` feature is enabled,
+// like a crate only enabling surrealdb's protocols behind a feature of its own
+include_fn!{
+    driver as is if "remote";
+    datastore as ds_$;
+    dispatch;
+    "$CARGO_MANIFEST_DIR/tests/main.surql"
+}
+
+#[tokio::main]
+async fn main() -> surrealdb::Result<()> {
+    let ds = Datastore::new("memory").await?;
+    let ses = Session::for_kv().with_ns("test").with_db("test");
+    for response in ds_define_functions(&ds, &ses).await? {
+        response.result?;
+    }
+    let msg = nested::ds_greet(&ds, &ses, "datastore").await;
+    println!("{msg:?}");
+
+    #[cfg(feature = "remote")]
+    {
+        use surrealdb::{engine::local::Mem, Surreal};
+
+        let db = Surreal::new::<Mem>(()).await?;
+        db.use_ns("test").use_db("test").await?;
+        define_functions(&db).await?.check()?;
+        let res = dispatch(&db, "nested::greet", vec!["driver".into()]).await?.check()?;
+        println!("{res:?}");
+    }
+
+    Ok(())
+}
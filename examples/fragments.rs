@@ -0,0 +1,38 @@
+use surrealdb::{engine::local::Mem, Surreal};
+use surrealdb_functions::include_fn;
+
+include_fn!{
+    driver as is;
+    batch;
+    "$CARGO_MANIFEST_DIR/tests/main.surql"
+}
+
+#[tokio::main]
+async fn main() -> surrealdb::Result<()> {
+    let db = Surreal::new::<Mem>(()).await?;
+    db.use_ns("test").use_db("test").await?;
+    define_functions(&db).await?.check()?;
+
+    // Both calls run in the same transaction, in a single round trip
+    let query = db.query("BEGIN TRANSACTION");
+    let query = greet_but_with_number_fragment("first", 1).append_to(query);
+    let query = nested::greet_fragment("second").append_to(query);
+    let mut res = query.query("COMMIT TRANSACTION").await?.check()?;
+
+    let first: Option<String> = res.take(0)?;
+    let second: Option<String> = res.take(1)?;
+    println!("{first:?} {second:?}");
+    assert_eq!(second.as_deref(), Some("Hello, second!"));
+
+    // Or queue calls and run them together, each result is at the index returned by `push`
+    let mut calls = batch(&db);
+    let first = calls.push(nested::greet_fragment("first"));
+    let second = calls.push(nested::greet_fragment("second"));
+    let mut res = calls.run().await?.check()?;
+    let second: Option<String> = res.take(second)?;
+    let first: Option<String> = res.take(first)?;
+    assert_eq!(first.as_deref(), Some("Hello, first!"));
+    assert_eq!(second.as_deref(), Some("Hello, second!"));
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+//! Everything the macros generate has to compile without the prelude.
+
+#[no_implicit_prelude]
+mod generated {
+    ::surrealdb_functions::include_fn!{
+        driver as is;
+        datastore as ds_$;
+        blocking as sync_$;
+        trait as StoredFunctions;
+        dispatch;
+        tower;
+        retry(times = 2, backoff = "50ms");
+        timeout "5s";
+        instrument;
+        metrics;
+        migrations;
+        verify;
+        metadata;
+        batch;
+        "$CARGO_MANIFEST_DIR/tests/main.surql";
+        "$CARGO_MANIFEST_DIR/tests/bindings.surql"
+    }
+
+    ::surrealdb_functions::include_fn!{
+        mod as flat;
+        datastore as is raw;
+        modules as flat;
+        "$CARGO_MANIFEST_DIR/tests/main.surql"
+    }
+
+    ::surrealdb_functions::include_fn!{
+        mod as any;
+        driver as is;
+        engine as any;
+        blocking as sync_$ tokio;
+        trait as StoredFunctions;
+        tower;
+        migrations;
+        verify;
+        batch;
+        "$CARGO_MANIFEST_DIR/tests/main.surql"
+    }
+
+    #[allow(dead_code)]
+    #[derive(::surrealdb_functions::SurqlFunctions)]
+    #[surql(driver as is; instrument; "$CARGO_MANIFEST_DIR/tests/main.surql")]
+    pub struct Client<C: ::surrealdb::Connection> {
+        pub db: ::surrealdb::Surreal<C>,
+    }
+}
+
+fn main() {
+    println!("{}", generated::stored_functions().len());
+}
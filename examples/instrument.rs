@@ -0,0 +1,102 @@
+//! Calls wrapped in `tracing` spans, recorded by a minimal subscriber.
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use surrealdb::{dbs::Session, engine::local::Mem, kvs::Datastore, Surreal};
+use surrealdb_functions::include_fn;
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Event, Metadata, Subscriber,
+};
+
+include_fn!{
+    driver as is;
+    datastore as ds_$;
+    instrument;
+    "$CARGO_MANIFEST_DIR/tests/main.surql";
+    "$CARGO_MANIFEST_DIR/tests/auth.surql"
+}
+
+/// Records the spans and events of this crate as lines of text.
+#[derive(Default)]
+struct Recorder {
+    lines: Arc<Mutex<Vec<String>>>,
+    next_id: AtomicU64,
+}
+
+struct Fields(String);
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.push_str(&format!(" {}={:?}", field.name(), value));
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        // Leave out what surrealdb itself traces
+        metadata.target().starts_with("instrument")
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut fields = Fields(String::new());
+        span.record(&mut fields);
+        self.lines.lock().unwrap().push(format!("span {}{}", span.metadata().name(), fields.0));
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields(String::new());
+        event.record(&mut fields);
+        self.lines.lock().unwrap().push(format!("event {}{}", event.metadata().level(), fields.0));
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+#[tokio::main]
+async fn main() -> surrealdb::Result<()> {
+    let recorder = Recorder::default();
+    let lines = recorder.lines.clone();
+    tracing::subscriber::set_global_default(recorder).unwrap();
+
+    let db = Surreal::new::<Mem>(()).await?;
+    db.use_ns("test").use_db("test").await?;
+
+    // Not defined yet, so the call fails
+    assert!(nested::greet_value(&db, "world".to_string()).await.is_err());
+
+    define_functions(&db).await?.check()?;
+    let got = nested::greet_value(&db, "world".to_string()).await?;
+    assert_eq!(got.as_deref(), Some("Hello, world!"));
+    auth::login(&db, "admin".to_string(), "hunter2".to_string()).await?.check()?;
+
+    let ds = Datastore::new("memory").await.unwrap();
+    let ses = Session::for_kv().with_ns("test").with_db("test");
+    ds_define_functions(&ds, &ses).await.unwrap();
+    ds_greet_but_with_number(&ds, &ses, "world".to_string(), 10).await.unwrap();
+
+    let lines = lines.lock().unwrap();
+    for line in lines.iter() {
+        println!("{line}");
+    }
+    let spans = lines.iter().filter(|line| line.starts_with("span fn::nested::greet ")).count();
+    assert_eq!(spans, 2);
+    assert!(lines.contains(&r#"span fn::nested::greet args="name" query="RETURN fn::nested::greet($__fnarg_name)""#.to_string()));
+    assert!(lines.iter().any(|line| line.starts_with("span fn::greet_but_with_number args=\"name, number\"")));
+    assert_eq!(lines.iter().filter(|line| line.starts_with("event ERROR")).count(), 1);
+    // Only names are recorded, never values
+    assert!(lines.iter().all(|line| !line.contains("hunter2")));
+
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use surrealdb::{engine::local::Mem, Surreal, kvs::Datastore, dbs::Session};
 use surrealdb_functions::include_fn;
 
@@ -8,6 +10,13 @@ include_fn!{
     "$CARGO_MANIFEST_DIR/tests/main.surql"
 }
 
+// A second invocation in the same module needs its own module
+include_fn!{
+    mod as bindings;
+    driver as is;
+    "$CARGO_MANIFEST_DIR/tests/bindings.surql"
+}
+
 #[tokio::main]
 async fn main() -> surrealdb::Result<()> {
     // In-memory database for testing
@@ -18,9 +27,28 @@ async fn main() -> surrealdb::Result<()> {
     
     // Define the functions using the include_fn! macro defined functions
     define_functions(&db).await?.check()?;
+    bindings::define_functions(&db).await?.check()?;
+
+    // The hash of the defined functions is stored alongside them
+    store_functions_hash(&db).await?.check()?;
+    bindings::store_functions_hash(&db).await?.check()?;
+    assert_eq!(functions_version(&db).await?.as_deref(), Some(FUNCTIONS_HASH));
+    assert_eq!(bindings::functions_version(&db).await?.as_deref(), Some(bindings::FUNCTIONS_HASH));
 
     // Call the example functions
-    dbg!(greet_but_with_number(&db, "driver", 10).await?.check()?);
+    println!("{:?}", greet_but_with_number(&db, "driver", 10).await?.check()?);
+
+    println!("{:?}", bindings::string_value(&db, "bindings").await?);
+
+    // Old names from `-- @alias` still work, but are deprecated
+    #[allow(deprecated)]
+    let res = greet_nested(&db, "alias").await?.check()?;
+    println!("{:?}", res);
+
+    // `_value` wrappers check the response and take the first result
+    println!("{:?}", greet_but_with_number_value(&db, "value", 10).await?);
+    let greeting: Option<String> = nested::greet_value(&db, "value").await?;
+    println!("{:?}", greeting);
 
     // Direct datastore access
     let ds = Datastore::new("memory").await?;
@@ -34,13 +62,14 @@ async fn main() -> surrealdb::Result<()> {
         Err(e) => panic!("ERR: {e}"),
     }
 
-    // Wow using datastore sure is very verbose huh...
-    let mut res = ds_greet_but_with_number(&ds, &ses, "datastore", 10).await?;
-    let tmp = res.remove(0).result;
-	match tmp {
-        Ok(msg) => println!("OK {msg}"),
-        Err(e) => println!("ERR: {e}"),
-    }
+    // Datastore functions return the result of the call
+    let msg = ds_greet_but_with_number(&ds, &ses, "datastore", 10).await?;
+    println!("OK {msg}");
+
+    // Extra variables are bound too, but never override the parameters
+    let vars = BTreeMap::from([("name".to_string(), "ignored".into()), ("ctx".to_string(), "context".into())]);
+    let msg = ds_greet_but_with_number_with_vars(&ds, &ses, "datastore", 10, vars).await?;
+    println!("OK {msg}");
 
     Ok(())
 }
@@ -0,0 +1,66 @@
+//! Counting calls and their latencies with a `FunctionCallObserver`.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use surrealdb::{dbs::Session, engine::local::Mem, kvs::Datastore, Surreal};
+use surrealdb_functions::include_fn;
+
+include_fn!{
+    driver as is;
+    datastore as ds_$;
+    trait as StoredFunctions;
+    metrics;
+    "$CARGO_MANIFEST_DIR/tests/main.surql"
+}
+
+/// Calls and failures of every function, along with the total time spent in them.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Stats {
+    calls: u32,
+    failures: u32,
+    total: Duration,
+}
+
+#[tokio::main]
+async fn main() -> surrealdb::Result<()> {
+    let stats = Arc::new(Mutex::new(BTreeMap::<String, Stats>::new()));
+    let recorded = stats.clone();
+    assert!(set_function_observer(move |function: &str, elapsed: Duration, succeeded: bool| {
+        let mut stats = recorded.lock().unwrap();
+        let stats = stats.entry(function.to_string()).or_default();
+        stats.calls += 1;
+        stats.failures += u32::from(!succeeded);
+        stats.total += elapsed;
+    }));
+    // Only the first observer is kept
+    assert!(!set_function_observer(|_: &str, _: Duration, _: bool| {}));
+
+    let db = Surreal::new::<Mem>(()).await?;
+    db.use_ns("test").use_db("test").await?;
+
+    // Not defined yet, so the call fails
+    assert!(nested::greet_value(&db, "world".to_string()).await.is_err());
+
+    define_functions(&db).await?.check()?;
+    nested::greet(&db, "world".to_string()).await?.check()?;
+    db.nested_greet("trait".to_string()).await?.check()?;
+
+    let ds = Datastore::new("memory").await.unwrap();
+    let ses = Session::for_kv().with_ns("test").with_db("test");
+    ds_define_functions(&ds, &ses).await.unwrap();
+    ds_greet_but_with_number(&ds, &ses, "world".to_string(), 10).await.unwrap();
+
+    let stats = stats.lock().unwrap();
+    println!("{stats:#?}");
+    assert_eq!(stats["nested::greet"].calls, 3);
+    assert_eq!(stats["nested::greet"].failures, 1);
+    assert_eq!(stats["greet_but_with_number"].calls, 1);
+    assert_eq!(stats["greet_but_with_number"].failures, 0);
+    assert!(stats["nested::greet"].total > Duration::ZERO);
+
+    Ok(())
+}
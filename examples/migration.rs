@@ -0,0 +1,43 @@
+use surrealdb::{engine::local::Mem, Surreal};
+use surrealdb_functions::include_fn;
+
+include_fn!{
+    driver as is;
+    migrations;
+    verify;
+    "$CARGO_MANIFEST_DIR/tests/main.surql"
+}
+
+#[tokio::main]
+async fn main() -> surrealdb::Result<()> {
+    // In-memory database for testing
+    let db = Surreal::new::<Mem>(()).await?;
+    db.use_ns("test").use_db("test").await?;
+
+    // Nothing is defined yet, so every function has to be created
+    assert!(!verify_functions(&db).await?.is_ok());
+    let plan = plan_migration(&db).await?;
+    assert!(plan.steps.iter().all(|step| step.action == MigrationAction::Create));
+
+    // Leftovers from a previous deployment, and a function edited since
+    db.query("DEFINE FUNCTION fn::removed() { RETURN 1; }").await?.check()?;
+    plan.apply(&db).await?.check()?;
+    db.query("DEFINE FUNCTION fn::nested::greet($name: string) { RETURN $name; }").await?.check()?;
+
+    // The edited function can still be called, but it's outdated
+    let report = verify_functions(&db).await?;
+    assert!(report.is_ok() && !report.is_current());
+    assert_eq!(report.stale, vec!["nested::greet"]);
+
+    let plan = plan_migration(&db).await?;
+    let steps: Vec<_> = plan.steps.iter().map(|step| (step.action, step.name.as_str())).collect();
+    assert_eq!(steps, vec![(MigrationAction::Update, "nested::greet"), (MigrationAction::Remove, "removed")]);
+
+    plan.apply(&db).await?.check()?;
+    assert!(plan_migration(&db).await?.is_empty());
+    assert!(verify_functions(&db).await?.is_current());
+    store_functions_hash(&db).await?.check()?;
+    assert_eq!(functions_version(&db).await?.as_deref(), Some(FUNCTIONS_HASH));
+
+    Ok(())
+}
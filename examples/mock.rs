@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use surrealdb::{engine::local::Mem, error::Api, Response, Surreal};
+use surrealdb_functions::include_fn;
+
+include_fn!{
+    driver as is;
+    trait as StoredFunctions;
+    "$CARGO_MANIFEST_DIR/tests/main.surql"
+}
+
+// Application code only depends on the trait
+async fn greet(functions: &impl StoredFunctions, name: &str) -> surrealdb::Result<()> {
+    functions.nested_greet(name).await?.check()?;
+    Ok(())
+}
+
+// Mock that counts the calls and never touches a database
+#[derive(Default)]
+struct Mock {
+    greeted: AtomicUsize,
+}
+
+impl StoredFunctions for Mock {
+    async fn define_functions(&self) -> surrealdb::Result<Response> {
+        Err(Api::Query("not available in the mock".to_owned()).into())
+    }
+
+    async fn greet_but_with_number(
+        &self,
+        _name: impl Into<surrealdb::sql::Strand>,
+        _number: impl Into<surrealdb::sql::Number>,
+    ) -> surrealdb::Result<Response> {
+        Err(Api::Query("not available in the mock".to_owned()).into())
+    }
+
+    async fn greet_nested(&self, name: impl Into<surrealdb::sql::Strand>) -> surrealdb::Result<Response> {
+        self.nested_greet(name).await
+    }
+
+    async fn nested_greet(&self, _name: impl Into<surrealdb::sql::Strand>) -> surrealdb::Result<Response> {
+        self.greeted.fetch_add(1, Ordering::SeqCst);
+        Err(Api::Query("mocked".to_owned()).into())
+    }
+}
+
+#[tokio::main]
+async fn main() -> surrealdb::Result<()> {
+    // The mock
+    let mock = Mock::default();
+    assert!(greet(&mock, "mock").await.is_err());
+    assert_eq!(mock.greeted.load(Ordering::SeqCst), 1);
+
+    // The real thing
+    let db = Surreal::new::<Mem>(()).await?;
+    db.use_ns("test").use_db("test").await?;
+    StoredFunctions::define_functions(&db).await?.check()?;
+    greet(&db, "driver").await?;
+
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use surrealdb::{engine::local::Mem, Surreal};
+use surrealdb_functions::include_fn;
+
+// Public functions
+include_fn!{
+    driver as is;
+    "$CARGO_MANIFEST_DIR/tests/main.surql"
+}
+
+// Admin functions in the same module, with their own names for everything
+include_fn!{
+    driver as admin_$;
+    stored as admin_stored_functions;
+    // `pub mod nested` is already taken by the public functions
+    modules as flat;
+    "$CARGO_MANIFEST_DIR/tests/main.surql";
+    "$CARGO_MANIFEST_DIR/tests/bindings.surql"
+}
+
+#[tokio::main]
+async fn main() -> surrealdb::Result<()> {
+    let db = Surreal::new::<Mem>(()).await?;
+    db.use_ns("test").use_db("test").await?;
+
+    assert!(admin_stored_functions().len() > stored_functions().len());
+    admin_define_functions(&db).await?.check()?;
+
+    println!("{:?}", greet_but_with_number(&db, "public", 1).await?.check()?);
+    println!("{:?}", admin_string(&db, "admin").await?.check()?);
+    println!("{:?}", admin_nested_greet(&db, "admin").await?.check()?);
+
+    Ok(())
+}
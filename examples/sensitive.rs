@@ -0,0 +1,42 @@
+use surrealdb::{engine::local::Mem, sql::Value, Surreal};
+use surrealdb_functions::include_fn;
+
+include_fn!{
+    driver as is;
+    fragments;
+    tower;
+    metadata;
+    "$CARGO_MANIFEST_DIR/tests/auth.surql"
+}
+
+#[tokio::main]
+async fn main() -> surrealdb::Result<()> {
+    // In-memory database for testing
+    let db = Surreal::new::<Mem>(()).await?;
+    db.use_ns("test").use_db("test").await?;
+    define_functions(&db).await?.check()?;
+
+    // UIs can tell which parameters not to show
+    let login = FUNCTIONS.iter().find(|function| function.name == "auth::login").unwrap();
+    let sensitive: Vec<_> = login.params.iter().map(|param| (param.name, param.sensitive)).collect();
+    assert_eq!(sensitive, vec![("user", false), ("password", true)]);
+    assert!(FUNCTIONS.iter().any(|function| function.name == "check_login" && function.deprecated));
+
+    // Logging a call never shows the password
+    let fragment = auth::login_fragment("admin", "hunter2");
+    let logged = format!("{fragment:?}");
+    assert!(logged.contains("admin") && !logged.contains("hunter2"), "{logged}");
+
+    let request = FnRequest::new("fn::auth::login", vec![Value::from("admin"), Value::from("hunter2")]);
+    let logged = format!("{request:?}");
+    assert!(logged.contains("admin") && !logged.contains("hunter2"), "{logged}");
+
+    // The calls themselves are unaffected
+    let query = fragment.append_to(db.query("BEGIN TRANSACTION"));
+    let mut res = query.query("COMMIT TRANSACTION").await?.check()?;
+    let ok: Option<bool> = res.take(0)?;
+    assert_eq!(ok, Some(true));
+    assert_eq!(auth::login_value(&db, "admin", "wrong").await?, Some(false));
+
+    Ok(())
+}
@@ -0,0 +1,33 @@
+//! Calls taking longer than the timeout fail with `QueryTimedout`.
+
+use std::time::Duration;
+
+use surrealdb::{engine::local::Mem, error::Db, Surreal};
+use surrealdb_functions::include_fn;
+
+include_fn!{
+    driver as is;
+    retry;
+    timeout "200ms";
+    "$CARGO_MANIFEST_DIR/tests/slow.surql"
+}
+
+#[tokio::main]
+async fn main() -> surrealdb::Result<()> {
+    let db = Surreal::new::<Mem>(()).await?;
+    db.use_ns("test").use_db("test").await?;
+    define_functions(&db).await?.check()?;
+
+    assert_eq!(slow_value(&db, Duration::from_millis(10)).await?, Some(true));
+
+    let err = slow_value(&db, Duration::from_millis(400)).await.unwrap_err();
+    assert!(matches!(err, surrealdb::Error::Db(Db::QueryTimedout)), "{err}");
+    let err = slow(&db, Duration::from_millis(400)).await.unwrap_err();
+    assert!(matches!(err, surrealdb::Error::Db(Db::QueryTimedout)), "{err}");
+
+    // The database keeps running the calls that timed out, only the callers stopped waiting
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(slow_value(&db, Duration::ZERO).await?, Some(true));
+
+    Ok(())
+}
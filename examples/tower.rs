@@ -0,0 +1,34 @@
+use std::time::Duration;
+
+use surrealdb::{engine::local::Mem, sql::Value, Surreal};
+use surrealdb_functions::include_fn;
+use tower::{ServiceBuilder, ServiceExt};
+
+include_fn!{
+    driver as is;
+    tower;
+    "$CARGO_MANIFEST_DIR/tests/main.surql"
+}
+
+#[tokio::main]
+async fn main() -> Result<(), tower::BoxError> {
+    // In-memory database for testing
+    let db = Surreal::new::<Mem>(()).await?;
+    db.use_ns("test").use_db("test").await?;
+    define_functions(&db).await?.check()?;
+
+    // The functions behave like any other service, so middleware can be stacked on top
+    let service = ServiceBuilder::new()
+        .timeout(Duration::from_secs(5))
+        .service(service(db));
+
+    let request = FnRequest::new("fn::nested::greet", vec![Value::from("tower")]);
+    let mut res = service.clone().oneshot(request).await?.check()?;
+    let got: Option<String> = res.take(0)?;
+    assert_eq!(got.as_deref(), Some("Hello, tower!"));
+
+    // Dispatch errors come back through the stack too
+    assert!(service.oneshot(FnRequest::new("fn::unknown", vec![])).await.is_err());
+
+    Ok(())
+}
@@ -0,0 +1,32 @@
+//! The code generated for the WASM SDK, run natively: nothing relies on `Send` and everything works with `engine::any`.
+
+use surrealdb::{engine::any::connect, sql::Value};
+use surrealdb_functions::include_fn;
+use tower::ServiceExt;
+
+include_fn!{
+    driver as is;
+    trait as StoredFunctions;
+    tower;
+    wasm;
+    "$CARGO_MANIFEST_DIR/tests/main.surql"
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<(), tower::BoxError> {
+    // In a browser this would be `connect("indxdb://...")`
+    let db = connect("mem://").await?;
+    db.use_ns("test").use_db("test").await?;
+    define_functions(&db).await?.check()?;
+
+    let mut res = db.nested_greet("wasm").await?.check()?;
+    let got: Option<String> = res.take(0)?;
+    assert_eq!(got.as_deref(), Some("Hello, wasm!"));
+
+    let request = FnRequest::new("fn::nested::greet", vec![Value::from("tower")]);
+    let mut res = service(db).oneshot(request).await?.check()?;
+    let got: Option<String> = res.take(0)?;
+    assert_eq!(got.as_deref(), Some("Hello, tower!"));
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::{collections::BTreeMap, error::Error, fs, path::Path};
+
+use crate::parser::DefineFunctionStatement;
+
+/// Set to rewrite the snapshot with the current API, like when cutting a release.
+const UPDATE_VAR: &str = "SURREALDB_FUNCTIONS_UPDATE_SNAPSHOT";
+
+/// One line of the API summary, like `fn::greet($name: string) -> string`.
+fn signature_line(function: &DefineFunctionStatement) -> String {
+    let mut out = function.signature();
+    if function.alias_of.is_some() {
+        out.push_str(" (deprecated alias)");
+    }
+    out
+}
+
+/// Name of the function a summary line is about.
+fn line_name(line: &str) -> &str {
+    line.split('(').next().unwrap_or(line)
+}
+
+/// Differences between two summaries, one `added`, `removed` or `changed` line per function,
+/// with the fields separated by tabs.
+fn diff(old: &str, new: &str) -> Vec<String> {
+    let old: BTreeMap<_, _> = old.lines().map(|line| (line_name(line), line)).collect();
+    let new: BTreeMap<_, _> = new.lines().map(|line| (line_name(line), line)).collect();
+
+    let mut out = vec![];
+    for (name, line) in &new {
+        match old.get(name) {
+            None => out.push(format!("added\t{line}")),
+            Some(previous) if previous != line => out.push(format!("changed\t{previous}\t{line}")),
+            Some(_) => {}
+        }
+    }
+    for (name, line) in &old {
+        if !new.contains_key(name) {
+            out.push(format!("removed\t{line}"));
+        }
+    }
+    out
+}
+
+/// Compares the generated API against the snapshot at `path`, returning a note for every change.
+///
+/// The changes are also written next to the snapshot with a `.changes` extension.
+/// A missing snapshot is created, an existing one is only rewritten when `SURREALDB_FUNCTIONS_UPDATE_SNAPSHOT` is set.
+pub(crate) fn compare(
+    path: &Path,
+    functions: &[DefineFunctionStatement],
+) -> Result<Vec<String>, Box<dyn Error>> {
+    crate::file::track_file(path);
+    let mut current: Vec<_> = functions.iter().map(signature_line).collect();
+    current.sort();
+    let current = current.iter().map(|line| format!("{line}\n")).collect::<String>();
+
+    let previous = match fs::read_to_string(path) {
+        Ok(previous) => previous,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            fs::write(path, &current).map_err(|e| format!("{}: {e}", path.display()))?;
+            return Ok(vec![]);
+        }
+        Err(e) => return Err(format!("{}: {e}", path.display()).into()),
+    };
+
+    let changes = diff(&previous, &current);
+    let summary = changes.iter().map(|line| format!("{line}\n")).collect::<String>();
+    let output = path.with_extension("changes");
+    fs::write(&output, summary).map_err(|e| format!("{}: {e}", output.display()))?;
+    // Only rewritten when it changes, the snapshot is tracked so writing it would trigger another rebuild
+    if crate::file::get_env(UPDATE_VAR).is_some() && previous != current {
+        fs::write(path, &current).map_err(|e| format!("{}: {e}", path.display()))?;
+    }
+
+    Ok(changes
+        .iter()
+        .map(|change| format!("API {}", change.replacen('\t', ": ", 1).replace('\t', " => ")))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_functions() {
+        let old = "fn::a($x: int)\nfn::b()\nfn::c()\n";
+        let new = "fn::a($x: string)\nfn::c()\nfn::d() -> bool\n";
+        assert_eq!(
+            diff(old, new),
+            vec![
+                "changed\tfn::a($x: int)\tfn::a($x: string)".to_string(),
+                "added\tfn::d() -> bool".to_string(),
+                "removed\tfn::b()".to_string(),
+            ]
+        );
+        assert!(diff(new, new).is_empty());
+    }
+
+    #[test]
+    fn compare_snapshot() {
+        let dir = std::env::temp_dir().join("surrealdb_functions_changelog");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("api.snapshot");
+        std::fs::write(&path, "fn::greet($name: string)\nfn::old()\n").unwrap();
+
+        let functions = crate::parser::statements(
+            "DEFINE FUNCTION fn::greet($name: string, $loud: bool) { RETURN $name; };",
+        )
+        .unwrap();
+        let functions: Vec<_> = functions.into_iter().map(|(_, v)| v).collect();
+        let notes = compare(&path, &functions).unwrap();
+        assert_eq!(
+            notes,
+            vec![
+                "API changed: fn::greet($name: string) => fn::greet($name: string, $loud: bool)",
+                "API removed: fn::old()",
+            ]
+        );
+        let changes = std::fs::read_to_string(dir.join("api.changes")).unwrap();
+        assert_eq!(changes.lines().count(), 2);
+    }
+}
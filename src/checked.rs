@@ -0,0 +1,154 @@
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use quote::quote;
+
+use crate::parser::kind::Kind;
+
+/// A conversion trait taken by the parameters of a kind with `checked_args`, instead of `impl Into<T>`.
+///
+/// It's implemented for the Rust types of the kind only, so passing anything else fails on the trait itself,
+/// with a message naming the kind instead of pages about `Into<Strand>`.
+struct Checked {
+    kind: &'static str,
+    name: &'static str,
+    method: &'static str,
+    target: TokenStream2,
+    /// The types taking the kind and how `self` of them converts into `target`.
+    impls: Vec<(TokenStream2, TokenStream2)>,
+    note: &'static str,
+}
+
+/// The checked trait taken by parameters of `kind` and its method, if it has one.
+pub(crate) fn checked_trait(kind: &Kind) -> Option<(Ident, Ident)> {
+    let name = match kind {
+        Kind::Bool => "bool",
+        Kind::String => "string",
+        Kind::Int => "int",
+        Kind::Float => "float",
+        Kind::Decimal => "decimal",
+        Kind::Number => "number",
+        Kind::Duration => "duration",
+        _ => return None,
+    };
+    let checked = traits().into_iter().find(|checked| checked.kind == name)?;
+    Some((Ident::new(checked.name, Span::call_site()), Ident::new(checked.method, Span::call_site())))
+}
+
+/// Imports of the traits, for the modules of nested functions.
+pub(crate) fn imports() -> TokenStream2 {
+    let names = traits().into_iter().map(|checked| Ident::new(checked.name, Span::call_site()));
+    quote! {
+        #[allow(unused_imports)]
+        use super::{#(#names),*};
+    }
+}
+
+/// Generates the traits, next to `stored_functions()`.
+pub(crate) fn checked_traits() -> TokenStream2 {
+    traits()
+        .into_iter()
+        .map(|checked| {
+            let Checked { kind, name, method, target, impls, note } = checked;
+            let name = Ident::new(name, Span::call_site());
+            let method = Ident::new(method, Span::call_site());
+            let doc = format!("Anything a `{kind}` parameter takes, checked when the function is called.");
+            let message = format!("`{{Self}}` can't be passed to a `{kind}` parameter");
+            let label = format!("not a `{kind}`");
+            let impls = impls.into_iter().map(|(ty, conversion)| {
+                quote! {
+                    impl #name for #ty {
+                        fn #method(self) -> #target {
+                            #conversion
+                        }
+                    }
+                }
+            });
+            quote! {
+                #[doc = #doc]
+                #[diagnostic::on_unimplemented(message = #message, label = #label, note = #note)]
+                pub trait #name {
+                    #[doc = "Converts this into what the parameter is bound as."]
+                    fn #method(self) -> #target;
+                }
+
+                #(#impls)*
+            }
+        })
+        .collect()
+}
+
+fn traits() -> Vec<Checked> {
+    let krate = crate::krate();
+    let number = quote! { #krate::sql::Number };
+    let from = |ty: TokenStream2| (ty, quote! { ::std::convert::From::from(self) });
+    let ints = || [quote! { i8 }, quote! { i16 }, quote! { i32 }, quote! { i64 }, quote! { isize }, quote! { u8 }, quote! { u16 }, quote! { u32 }, quote! { u64 }, quote! { usize }];
+    let floats = || [quote! { f32 }, quote! { f64 }];
+    let numbers = |types: Vec<TokenStream2>| {
+        let mut impls: Vec<_> = types.into_iter().map(from).collect();
+        impls.push((number.clone(), quote! { self }));
+        impls
+    };
+    vec![
+        Checked {
+            kind: "bool",
+            name: "IntoBool",
+            method: "into_bool",
+            target: quote! { bool },
+            impls: vec![(quote! { bool }, quote! { self })],
+            note: "`bool` parameters take `true` or `false`",
+        },
+        Checked {
+            kind: "string",
+            name: "IntoString",
+            method: "into_strand",
+            target: quote! { #krate::sql::Strand },
+            impls: vec![
+                from(quote! { &str }),
+                from(quote! { ::std::string::String }),
+                (quote! { &::std::string::String }, quote! { ::std::convert::From::from(self.as_str()) }),
+                (quote! { ::std::borrow::Cow<'_, str> }, quote! { ::std::convert::From::from(self.into_owned()) }),
+                (quote! { #krate::sql::Strand }, quote! { self }),
+            ],
+            note: "`string` parameters take a `&str`, `String`, `Cow<str>` or `Strand`, other values have to be formatted first",
+        },
+        Checked {
+            kind: "int",
+            name: "IntoInt",
+            method: "into_int",
+            target: number.clone(),
+            impls: numbers(ints().into()),
+            note: "`int` parameters take Rust integers or a `Number`, floats have to be rounded first",
+        },
+        Checked {
+            kind: "float",
+            name: "IntoFloat",
+            method: "into_float",
+            target: number.clone(),
+            impls: numbers(floats().into()),
+            note: "`float` parameters take an `f32`, `f64` or `Number`, write whole numbers like `5.0`",
+        },
+        Checked {
+            kind: "decimal",
+            name: "IntoDecimal",
+            method: "into_decimal",
+            target: number.clone(),
+            impls: numbers(ints().into_iter().chain(floats()).collect()),
+            note: "`decimal` parameters take Rust numbers or a `Number`",
+        },
+        Checked {
+            kind: "number",
+            name: "IntoNumber",
+            method: "into_number",
+            target: number.clone(),
+            impls: numbers(ints().into_iter().chain(floats()).collect()),
+            note: "`number` parameters take Rust numbers or a `Number`",
+        },
+        Checked {
+            kind: "duration",
+            name: "IntoDuration",
+            method: "into_duration",
+            target: quote! { #krate::sql::Duration },
+            impls: vec![from(quote! { ::std::time::Duration }), (quote! { #krate::sql::Duration }, quote! { self })],
+            note: "`duration` parameters take a `std::time::Duration`, like `Duration::from_secs(5)`, which can't be negative",
+        },
+    ]
+}
@@ -0,0 +1,112 @@
+use std::error::Error;
+
+use serde::Deserialize;
+
+/// Reads the definitions of the functions from `INFO FOR DB` over HTTP, as one statement each.
+///
+/// The namespace, database and root credentials come from `SURREALDB_FUNCTIONS_NS`, `_DB`, `_USER` and `_PASS`.
+pub(crate) fn fetch(endpoint: &str) -> Result<String, Box<dyn Error>> {
+    let var = |name: &str| crate::file::get_env(&format!("SURREALDB_FUNCTIONS_{name}"));
+    let (Some(ns), Some(db)) = (var("NS"), var("DB")) else {
+        return Err(format!("{endpoint}: set SURREALDB_FUNCTIONS_NS and SURREALDB_FUNCTIONS_DB to read the functions").into());
+    };
+    let mut request = ureq::post(&format!("{}/sql", endpoint.trim_end_matches('/')))
+        .set("Accept", "application/json")
+        // 1.x and 2.x name the headers differently
+        .set("NS", &ns)
+        .set("DB", &db)
+        .set("surreal-ns", &ns)
+        .set("surreal-db", &db);
+    if let (Some(user), Some(pass)) = (var("USER"), var("PASS")) {
+        request = request.set("Authorization", &format!("Basic {}", base64(format!("{user}:{pass}").as_bytes())));
+    }
+    let body = request
+        .send_string("RETURN object::values((INFO FOR DB).functions);")
+        .map_err(|e| format!("{endpoint}: {e}"))?
+        .into_string()
+        .map_err(|e| format!("{endpoint}: {e}"))?;
+    let definitions = definitions(&body).map_err(|e| format!("{endpoint}: {e}"))?;
+    Ok(definitions.iter().map(|definition| format!("{};\n", unescape_name(definition))).collect())
+}
+
+/// A statement of a `/sql` response.
+#[derive(Deserialize)]
+struct Statement {
+    status: String,
+    result: Output,
+}
+
+/// What a statement returned, errors are a string in place of the result.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Output {
+    Definitions(Vec<String>),
+    Error(String),
+}
+
+/// The strings in the result of the first statement of a `/sql` response.
+fn definitions(body: &str) -> Result<Vec<String>, String> {
+    let statements: Vec<Statement> = serde_json::from_str(body).map_err(|e| format!("unexpected response {body}: {e}"))?;
+    let Some(statement) = statements.into_iter().next() else {
+        return Err("empty response".to_string());
+    };
+    match statement.result {
+        Output::Definitions(definitions) if statement.status == "OK" => Ok(definitions),
+        Output::Error(message) => Err(message),
+        Output::Definitions(_) => Err(format!("the query failed with status {}", statement.status)),
+    }
+}
+
+/// `definition` with the name of the function unescaped, `INFO FOR DB` escapes nested names like `fn::⟨nested::greet⟩`.
+fn unescape_name(definition: &str) -> String {
+    let Some(start) = definition.find("fn::").map(|start| start + "fn::".len()) else {
+        return definition.to_string();
+    };
+    let close = match definition[start..].chars().next() {
+        Some('`') => '`',
+        Some('⟨') => '⟩',
+        _ => return definition.to_string(),
+    };
+    let open = definition[start..].chars().next().unwrap().len_utf8();
+    let Some(end) = definition[start + open..].find(close).map(|end| start + open + end) else {
+        return definition.to_string();
+    };
+    format!("{}{}{}", &definition[..start], &definition[start + open..end], &definition[end + close.len_utf8()..])
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, byte)| n | u32::from(*byte) << (16 - 8 * i));
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char),
+                false => out.push('='),
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn info_definitions() {
+        let body = r#"[{"result":["DEFINE FUNCTION fn::⟨nested::greet⟩($name: string) { RETURN \"Hi \" + $name; } PERMISSIONS FULL", "DEFINE FUNCTION fn::a() { RETURN 'é'; }"],"status":"OK","time":"1ms"}]"#;
+        let read = definitions(body).unwrap();
+        assert_eq!(unescape_name(&read[0]), r#"DEFINE FUNCTION fn::nested::greet($name: string) { RETURN "Hi " + $name; } PERMISSIONS FULL"#);
+        assert_eq!(unescape_name(&read[1]), "DEFINE FUNCTION fn::a() { RETURN 'é'; }");
+        assert_eq!(definitions(r#"[{"result":"There was a problem","status":"ERR"}]"#).unwrap_err(), "There was a problem");
+        assert!(definitions(r#"{"code":400,"details":"Request problems detected"}"#).unwrap_err().starts_with("unexpected response"));
+    }
+
+    #[test]
+    fn basic_credentials() {
+        assert_eq!(base64(b"root:root"), "cm9vdDpyb290");
+        assert_eq!(base64(b"ab"), "YWI=");
+        assert_eq!(base64(b"a"), "YQ==");
+    }
+}
@@ -0,0 +1,50 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+
+use crate::IncludeFnArgs;
+
+/// Generates `IntoDatetime`, taken by `datetime` parameters with `chrono` or `time`.
+///
+/// `Into<Datetime>` can't be implemented for types of other crates, so the conversions live on a trait of the invoking crate.
+pub(crate) fn datetime_trait(args: &IncludeFnArgs) -> TokenStream2 {
+    let krate = crate::krate();
+    let mut out = quote! {
+        #[doc = "Anything a `datetime` parameter takes, converted into a `Datetime` when the function is called."]
+        pub trait IntoDatetime {
+            #[doc = "Converts this into a `Datetime`."]
+            fn into_datetime(self) -> #krate::sql::Datetime;
+        }
+
+        impl IntoDatetime for #krate::sql::Datetime {
+            fn into_datetime(self) -> #krate::sql::Datetime {
+                self
+            }
+        }
+
+        impl IntoDatetime for ::std::time::SystemTime {
+            fn into_datetime(self) -> #krate::sql::Datetime {
+                // Through the `chrono` type `Datetime` wraps, so the invoking crate doesn't need `chrono` for it
+                #krate::sql::Datetime(::std::convert::From::from(self))
+            }
+        }
+    };
+    if args.chrono {
+        out.extend(quote! {
+            impl<Tz: ::chrono::TimeZone> IntoDatetime for ::chrono::DateTime<Tz> {
+                fn into_datetime(self) -> #krate::sql::Datetime {
+                    #krate::sql::Datetime::from(self.with_timezone(&::chrono::Utc))
+                }
+            }
+        });
+    }
+    if args.time {
+        out.extend(quote! {
+            impl IntoDatetime for ::time::OffsetDateTime {
+                fn into_datetime(self) -> #krate::sql::Datetime {
+                    IntoDatetime::into_datetime(<::std::time::SystemTime as ::std::convert::From<_>>::from(self))
+                }
+            }
+        });
+    }
+    out
+}
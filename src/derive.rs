@@ -0,0 +1,185 @@
+use proc_macro2::{Ident, TokenStream as TokenStream2};
+use proc_macro_error::{abort, abort_call_site};
+use quote::quote;
+use syn::{Data, DeriveInput, Index, Member};
+
+use crate::{
+    api_changes, cfg_items, define_hash, file, hash_expr, instrument, literal,
+    parser::DefineFunctionStatement,
+    source::{self, SourceFile},
+    rust_ident, stored_statements, tracked_files, warnings, IncludeFnArgs, Wrappers,
+};
+
+/// Options of `include_fn!` the methods take into account, any other is rejected instead of being ignored.
+const SUPPORTED_OPTIONS: &[&str] = &[
+    "driver",
+    "roots",
+    "url",
+    "remote",
+    "database",
+    "inline",
+    "if",
+    "duplicates",
+    "override_duplicates",
+    "continue_on_error",
+    "validate",
+    "version",
+    "surreal_version",
+    "crate",
+    "bindings",
+    "map",
+    "geo",
+    "uuid",
+    "docs",
+    "examples",
+    "instrument",
+    "changelog",
+];
+
+pub(crate) fn surql_functions(input: DeriveInput) -> TokenStream2 {
+    let args = match input.attrs.iter().find(|attr| attr.path().is_ident("surql")) {
+        Some(attr) => match attr.parse_args::<IncludeFnArgs>() {
+            Ok(args) => args,
+            Err(e) => abort!(e.span(), "{}", e),
+        },
+        None => {
+            abort_call_site!("missing `#[surql(...)]` attribute"; help = "add `#[surql(driver as is; \"path/to/file.surql\")]` to the struct")
+        }
+    };
+    if args.driver.is_none() {
+        abort_call_site!("`SurqlFunctions` only generates driver methods"; help = "add `driver as <alias>` to the `#[surql(...)]` attribute");
+    }
+    if let Some(option) = args.options.iter().find(|option| !SUPPORTED_OPTIONS.contains(&option.to_string().as_str())) {
+        abort!(option, "`{}` is not supported by `SurqlFunctions`", option; help = "the methods only take {}, use `include_fn!` for the rest", SUPPORTED_OPTIONS.join(", "))
+    }
+
+    crate::set_krate(args.crate_path.as_ref());
+    let member = connection_member(&input);
+    let (sources, mut notes) = match source::load_sources(&args) {
+        Ok(loaded) => loaded,
+        Err(e) => abort_call_site!("{}", e),
+    };
+    let functions = source::functions_of(&sources);
+    args.types.check(&functions);
+    notes.extend(api_changes(&args, &functions));
+
+    let mut methods = define_method(&args, &sources, &member);
+    for function in &functions {
+        methods.extend(function_method(function, &args, &member));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let warnings = warnings(&notes);
+    let literals = literal::literal_types(&functions, &args);
+    let methods = quote! {
+        #literals
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            #methods
+        }
+    };
+    let methods = cfg_items(args.driver_cfg.as_deref(), methods);
+    let tracking = file::tracking();
+    quote! {
+        #warnings
+
+        #tracking
+
+        #methods
+    }
+}
+
+/// Picks the field holding the connection: the one marked with `#[surql(db)]`, the one named `db` or the only one.
+fn connection_member(input: &DeriveInput) -> Member {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => abort_call_site!("`SurqlFunctions` can only be derived for structs"),
+    };
+
+    let marked = fields
+        .iter()
+        .position(|field| field.attrs.iter().any(|attr| attr.path().is_ident("surql")));
+    let named = fields
+        .iter()
+        .position(|field| field.ident.as_ref().is_some_and(|ident| ident == "db"));
+    let index = match (marked, named) {
+        (Some(index), _) | (None, Some(index)) => index,
+        (None, None) if fields.len() == 1 => 0,
+        (None, None) => {
+            abort_call_site!("unable to find the connection field"; help = "mark the field holding the `Surreal<C>` with `#[surql(db)]`")
+        }
+    };
+
+    match &fields.iter().nth(index).unwrap().ident {
+        Some(ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(Index::from(index)),
+    }
+}
+
+fn method_name(args: &IncludeFnArgs, name: &str) -> Ident {
+    let alias = args.driver.as_ref().unwrap();
+    rust_ident(&alias.transform(name))
+}
+
+fn define_method(args: &IncludeFnArgs, sources: &[SourceFile], member: &Member) -> TokenStream2 {
+    let krate = crate::krate();
+    let name = method_name(args, "define_functions");
+    let tracked = tracked_files(sources);
+    let statements = stored_statements(sources);
+    let consts = statements.iter().map(|(name, function, text, _)| {
+        let cfg = function.cfg();
+        quote! { #cfg const #name: &str = #text; }
+    });
+    let names = statements.iter().map(|(name, function, _, _)| {
+        let cfg = function.cfg();
+        quote! { #cfg #name }
+    });
+    let (hash_const, hash_param) = args.hash_names();
+    let hash = hash_expr(sources);
+    let define_hash = define_hash(args, &quote! { Self::#hash_const });
+    let store = method_name(args, "store_functions_hash");
+    let version = method_name(args, "functions_version");
+    let query = format!("RETURN ${hash_param}");
+    quote! {
+        #[doc = "Hash of the included functions, ignoring line endings and trailing whitespace."]
+        #[doc = ""]
+        #[doc = "Store it in the database with `store_functions_hash` after defining the functions, compare it to `functions_version` to know if they have to be defined again."]
+        pub const #hash_const: &'static str = #hash;
+
+        #[doc = "Defines all the functions using the connection of this struct."]
+        pub async fn #name(&self) -> #krate::Result<#krate::Response> {
+            #tracked
+            #(#consts)*
+            let statements: &[&str] = &[#(#names),*];
+            self.#member.query(statements.join("\n")).await
+        }
+
+        #[doc = "Stores the hash of the included functions in the database, to read it back with `functions_version`."]
+        pub async fn #store(&self) -> #krate::Result<#krate::Response> {
+            self.#member.query(#define_hash).await
+        }
+
+        #[doc = "The hash of the functions last defined in the database, if they were defined by this crate."]
+        pub async fn #version(&self) -> #krate::Result<::std::option::Option<::std::string::String>> {
+            self.#member.query(#query).await?.check()?.take(0)
+        }
+    }
+}
+
+fn function_method(
+    function: &DefineFunctionStatement,
+    args: &IncludeFnArgs,
+    member: &Member,
+) -> TokenStream2 {
+    let name = method_name(args, &function.method_base_name());
+    let comments = function.attributes(args);
+    let signature = function.method_signature(&name, args);
+    let body = instrument::instrumented(args, function, function.method_body(quote! { self.#member }, args));
+    quote! {
+        #comments
+        pub #signature {
+            #body
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+
+use crate::{binding_name, parser::DefineFunctionStatement, IncludeFnArgs, Wrappers};
+
+/// Generates a function calling any of the included functions by name with a list of values.
+pub(crate) fn dispatch_function(
+    functions: &[DefineFunctionStatement],
+    args: &IncludeFnArgs,
+) -> TokenStream2 {
+    let krate = crate::krate();
+    let (name, _) = args.transform_fn_name("dispatch");
+    let name = name.unwrap();
+    let generics = args.engine.generics();
+    let connection = args.engine.connection();
+
+    let arms = functions.iter().map(|function| {
+        let key = function.name.join("::");
+        let query = function.custom_function_query();
+        let params = function.args.iter().map(|(param, _)| binding_name(param));
+        let cfg = function.cfg();
+        quote! {
+            #cfg
+            #key => (#query, &[#(#params),*]),
+        }
+    });
+
+    quote! {
+        #[doc = "Calls one of the included functions by name (with or without the `fn::` prefix), binding `args` to its parameters in order."]
+        #[doc = ""]
+        #[doc = "Fails without querying the database if the function is unknown or the number of arguments doesn't match."]
+        pub async fn #name #generics(
+            db: &#krate::Surreal<#connection>,
+            name: &str,
+            args: ::std::vec::Vec<#krate::sql::Value>,
+        ) -> #krate::Result<#krate::Response> {
+            let name = name.strip_prefix("fn::").unwrap_or(name);
+            let (query, params): (&str, &[&str]) = match name {
+                #(#arms)*
+                _ => {
+                    let error = #krate::error::Api::InvalidRequest(::std::format!("unknown function `fn::{name}`"));
+                    return ::std::result::Result::Err(::std::convert::Into::into(error));
+                }
+            };
+            if args.len() != params.len() {
+                let error = #krate::error::Api::InvalidParams(::std::format!(
+                    "`fn::{name}` expects {} arguments but got {}",
+                    params.len(),
+                    args.len()
+                ));
+                return ::std::result::Result::Err(::std::convert::Into::into(error));
+            }
+            let mut query = db.query(query);
+            for (param, value) in ::std::iter::Iterator::zip(params.iter(), args) {
+                query = query.bind((*param, value));
+            }
+            query.await
+        }
+    }
+}
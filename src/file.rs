@@ -1,192 +1,133 @@
-use std::{path::PathBuf, error::Error, fmt::{Display, Formatter, self}};
-
-
-pub(crate) fn resolve_path(
-    raw: &str,
-    get_env: impl Fn(&str) -> Option<String>,
-) -> Result<PathBuf, Box<dyn Error>> {
-    let mut unprocessed = raw;
-    let mut resolved = String::new();
-
-    while let Some(dollar_sign) = unprocessed.find('$') {
-        let (head, tail) = unprocessed.split_at(dollar_sign);
-        resolved.push_str(head);
-
-        match parse_identifier(&tail[1..]) {
-            Some((variable, rest)) => {
-                let value = get_env(variable).ok_or_else(|| MissingVariable {
-                    variable: variable.to_string(),
-                })?;
-                resolved.push_str(&value);
-                unprocessed = rest;
-            }
-            None => {
-                return Err(UnableToParseVariable { rest: tail.into() }.into());
-            }
-        }
-    }
-    resolved.push_str(unprocessed);
+use std::{path::{Path, PathBuf}, error::Error, cell::RefCell};
 
-    Ok(PathBuf::from(resolved))
-}
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+// Shared with surrealdb_functions_build, which sets the listing variables from build scripts
+pub(crate) use surrealdb_functions_core::path::resolve_path;
+use surrealdb_functions_core::path::{find_workspace_root, listing_var};
 
-#[derive(Debug, PartialEq)]
-struct MissingVariable {
-    variable: String,
-}
-
-impl Error for MissingVariable {}
-
-impl Display for MissingVariable {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "Unable to resolve ${}", self.variable)
-    }
+thread_local! {
+    /// Environment variables and files read during the current expansion, see `tracking()`.
+    static TRACKED: RefCell<(Vec<String>, Vec<PathBuf>)> = RefCell::default();
 }
 
-#[derive(Debug, PartialEq)]
-struct UnableToParseVariable {
-    rest: String,
+/// Looks up an environment variable, `$WORKSPACE_ROOT` defaults to the root of the workspace of the invoking crate.
+pub(crate) fn get_env(variable: &str) -> Option<String> {
+    env_var(variable).or_else(|| match variable {
+        "WORKSPACE_ROOT" => {
+            let manifest_dir = env_var("CARGO_MANIFEST_DIR")?;
+            Some(find_workspace_root(Path::new(&manifest_dir)).display().to_string())
+        }
+        _ => None,
+    })
 }
 
-impl Error for UnableToParseVariable {}
-
-impl Display for UnableToParseVariable {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "Unable to parse a variable from \"{}\"", self.rest)
-    }
+#[cfg(feature = "nightly")]
+fn env_var(variable: &str) -> Option<String> {
+    proc_macro::tracked_env::var(variable).ok()
 }
 
-fn parse_identifier(text: &str) -> Option<(&str, &str)> {
-    let mut calls = 0;
-
-    let (head, tail) = take_while(text, |c| {
-        calls += 1;
-
-        match c {
-            '_' => true,
-            letter if letter.is_ascii_alphabetic() => true,
-            digit if digit.is_ascii_digit() && calls > 1 => true,
-            _ => false,
+#[cfg(not(feature = "nightly"))]
+fn env_var(variable: &str) -> Option<String> {
+    TRACKED.with(|tracked| {
+        let env = &mut tracked.borrow_mut().0;
+        if !env.iter().any(|tracked| tracked == variable) {
+            env.push(variable.to_string());
         }
     });
-
-    if head.is_empty() {
-        None
-    } else {
-        Some((head, tail))
-    }
+    std::env::var(variable).ok()
 }
 
-fn take_while(s: &str, mut predicate: impl FnMut(char) -> bool) -> (&str, &str) {
-    let mut index = 0;
-
-    for c in s.chars() {
-        if predicate(c) {
-            index += c.len_utf8();
-        } else {
-            break;
+/// Records a file read during expansion without being included, so changing it triggers a rebuild.
+pub(crate) fn track_file(path: &Path) {
+    TRACKED.with(|tracked| {
+        let files = &mut tracked.borrow_mut().1;
+        if !files.iter().any(|tracked| tracked == path) {
+            files.push(path.to_path_buf());
         }
-    }
-
-    s.split_at(index)
+    });
 }
 
+/// Records an included directory, so adding a file to it triggers a rebuild.
 #[cfg(feature = "nightly")]
-pub(crate) fn get_env(variable: &str) -> Option<String> {
-    proc_macro::tracked_env::var(variable).ok()
+pub(crate) fn track_dir(path: &Path) {
+    if let Some(path) = path.to_str() {
+        proc_macro::tracked_path::path(path);
+    }
 }
 
+/// Records an included directory, so adding a file to it triggers a rebuild.
+///
+/// Stable can't track a directory, so this reads the variable `surrealdb_functions_build::rerun_if_changed`
+/// sets to a hash of its entries instead, which changes along with them.
 #[cfg(not(feature = "nightly"))]
-pub(crate) fn get_env(variable: &str) -> Option<String> {
-    std::env::var(variable).ok()
+pub(crate) fn track_dir(path: &Path) {
+    env_var(&listing_var(path));
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn resolve_path_with_no_environment_variables() {
-        let path = "./file.txt";
-
-        let resolved = resolve_path(path, |_| unreachable!()).unwrap();
+/// Forgets what was tracked by a previous expansion, called before parsing the arguments.
+pub(crate) fn reset_tracking() {
+    TRACKED.with(|tracked| *tracked.borrow_mut() = Default::default());
+}
 
-        assert_eq!(resolved.to_str().unwrap(), path);
+/// Items making rustc track everything read during this expansion.
+///
+/// On stable, `option_env!` and `include_bytes!` are the only way to tell rustc about them,
+/// the included files themselves are already tracked by the `include_str!` of their consts.
+pub(crate) fn tracking() -> TokenStream2 {
+    let (env, files) = TRACKED.with(|tracked| std::mem::take(&mut *tracked.borrow_mut()));
+    let files = files.iter().filter(|path| path.exists()).filter_map(|path| path.to_str());
+    quote! {
+        #(const _: ::std::option::Option<&'static str> = ::std::option_env!(#env);)*
+        #(const _: &'static [u8] = ::std::include_bytes!(#files);)*
     }
+}
 
-    #[test]
-    fn simple_environment_variable() {
-        let path = "./$VAR";
-
-        let resolved = resolve_path(path, |name| {
-            assert_eq!(name, "VAR");
-            Some("file.txt".to_string())
-        })
-        .unwrap();
-
-        assert_eq!(resolved.to_str().unwrap(), "./file.txt");
+/// Resolves a relative `path` against the first of `roots` it exists in, absolute paths are kept as they are.
+pub(crate) fn resolve_in_roots(path: PathBuf, roots: &[PathBuf]) -> Result<PathBuf, Box<dyn Error>> {
+    if roots.is_empty() || path.is_absolute() {
+        return Ok(path);
     }
-
-    #[test]
-    fn dont_resolve_recursively() {
-        let path = "./$TOP_LEVEL.txt";
-
-        let resolved = resolve_path(path, |name| match name {
-            "TOP_LEVEL" => Some("$NESTED".to_string()),
-            "$NESTED" => unreachable!("Shouldn't resolve recursively"),
-            _ => unreachable!(),
+    roots
+        .iter()
+        .map(|root| root.join(&path))
+        .find(|candidate| candidate.exists())
+        .ok_or_else(|| {
+            let tried: Vec<_> = roots.iter().map(|root| root.join(&path).display().to_string()).collect();
+            format!("{} not found in any root, tried {}", path.display(), tried.join(", ")).into()
         })
-        .unwrap();
-
-        assert_eq!(resolved.to_str().unwrap(), "./$NESTED.txt");
-    }
+}
 
-    #[test]
-    fn parse_valid_identifiers() {
-        let inputs = vec![
-            ("a", "a"),
-            ("a_", "a_"),
-            ("_asf", "_asf"),
-            ("a1", "a1"),
-            ("a1_#sd", "a1_"),
-        ];
-
-        for (src, expected) in inputs {
-            let (got, rest) = parse_identifier(src).unwrap();
-            assert_eq!(got.len() + rest.len(), src.len());
-            assert_eq!(got, expected);
-        }
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
     #[test]
-    fn unknown_environment_variable() {
-        let path = "$UNKNOWN";
-
-        let err = resolve_path(path, |_| None).unwrap_err();
-
-        let missing_variable = err.downcast::<MissingVariable>().unwrap();
-        assert_eq!(
-            *missing_variable,
-            MissingVariable {
-                variable: String::from("UNKNOWN"),
-            }
-        );
+    fn tracked_reads() {
+        reset_tracking();
+        let path = std::env::temp_dir().join("surrealdb_functions_tracked.snapshot");
+        std::fs::write(&path, "").unwrap();
+        track_file(&path);
+        track_file(&path);
+        track_file(Path::new("missing.snapshot"));
+        let tokens = tracking().to_string();
+        assert_eq!(tokens.matches("include_bytes").count(), 1, "{tokens}");
+        assert!(tracking().is_empty());
     }
 
     #[test]
-    fn invalid_variables() {
-        let inputs = &["$1", "$"];
-
-        for input in inputs {
-            let err = resolve_path(input, |_| unreachable!()).unwrap_err();
-
-            let err = err.downcast::<UnableToParseVariable>().unwrap();
-            assert_eq!(
-                *err,
-                UnableToParseVariable {
-                    rest: input.to_string(),
-                }
-            );
-        }
+    fn resolve_in_first_root() {
+        let dir = std::env::temp_dir().join("surrealdb_functions_roots");
+        std::fs::create_dir_all(dir.join("b")).unwrap();
+        std::fs::create_dir_all(dir.join("c")).unwrap();
+        std::fs::write(dir.join("b/schema.surql"), "").unwrap();
+        std::fs::write(dir.join("c/schema.surql"), "").unwrap();
+
+        let roots = vec![dir.join("a"), dir.join("b"), dir.join("c")];
+        let resolved = resolve_in_roots(PathBuf::from("schema.surql"), &roots).unwrap();
+        assert_eq!(resolved, dir.join("b/schema.surql"));
+
+        let err = resolve_in_roots(PathBuf::from("missing.surql"), &roots).unwrap_err();
+        assert!(err.to_string().starts_with("missing.surql not found in any root"));
     }
 }
@@ -0,0 +1,210 @@
+use proc_macro2::{Ident, TokenStream as TokenStream2};
+use quote::quote;
+
+use crate::{
+    binding_name,
+    parser::{escape::escape_path, DefineFunctionStatement},
+    rust_segment, Bindings, IncludeFnArgs, Wrappers,
+};
+
+/// Generates the `QueryFragment` type shared by the `_fragment` functions.
+pub(crate) fn fragment_type() -> TokenStream2 {
+    let krate = crate::krate();
+    quote! {
+        #[doc = "A call to one of the included functions, to be chained onto a query like a transaction."]
+        #[doc = ""]
+        #[doc = "Parameters are bound under names unique to the fragment, so several calls to the same function don't clash."]
+        #[derive(::std::clone::Clone)]
+        pub struct QueryFragment {
+            pub query: ::std::string::String,
+            pub bindings: ::std::collections::BTreeMap<::std::string::String, #krate::sql::Value>,
+            /// Bindings of `@sensitive` parameters, redacted from `Debug` output.
+            sensitive: ::std::vec::Vec<::std::string::String>,
+        }
+
+        impl ::std::fmt::Debug for QueryFragment {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                struct Bindings<'a>(&'a QueryFragment);
+                impl<'a> ::std::fmt::Debug for Bindings<'a> {
+                    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                        let mut map = f.debug_map();
+                        for (name, value) in &self.0.bindings {
+                            if self.0.sensitive.contains(name) {
+                                map.entry(name, &::std::format_args!("<redacted>"));
+                            } else {
+                                map.entry(name, value);
+                            }
+                        }
+                        map.finish()
+                    }
+                }
+                f.debug_struct("QueryFragment")
+                    .field("query", &self.query)
+                    .field("bindings", &Bindings(self))
+                    .finish()
+            }
+        }
+
+        impl QueryFragment {
+            fn new(function: &str, args: ::std::vec::Vec<(&str, #krate::sql::Value)>, sensitive: &[&str]) -> Self {
+                static NEXT: ::std::sync::atomic::AtomicUsize = ::std::sync::atomic::AtomicUsize::new(0);
+                let id = NEXT.fetch_add(1, ::std::sync::atomic::Ordering::Relaxed);
+                let mut params = ::std::vec::Vec::with_capacity(args.len());
+                let mut bindings = ::std::collections::BTreeMap::new();
+                let mut redacted = ::std::vec::Vec::new();
+                for (name, value) in args {
+                    let binding = ::std::format!("f{id}_{name}");
+                    params.push(::std::format!("${binding}"));
+                    if sensitive.contains(&name) {
+                        redacted.push(::std::clone::Clone::clone(&binding));
+                    }
+                    bindings.insert(binding, value);
+                }
+                Self {
+                    // Not `RETURN`, which replaces the other results of a transaction
+                    query: ::std::format!("fn::{function}({})", params.join(", ")),
+                    bindings,
+                    sensitive: redacted,
+                }
+            }
+
+            #[doc = "Chains this call onto `query`, its result gets the next index of the response."]
+            pub fn append_to<'r, C: #krate::Connection>(
+                self,
+                query: #krate::method::Query<'r, C>,
+            ) -> #krate::method::Query<'r, C> {
+                query.query(self.query).bind(self.bindings)
+            }
+        }
+    }
+}
+
+/// Generates `name`, returning the call to `function` as a `QueryFragment` instead of running it.
+pub(crate) fn fragment_function(
+    function: &DefineFunctionStatement,
+    name: &Ident,
+    args: &IncludeFnArgs,
+) -> TokenStream2 {
+    let krate = crate::krate();
+    let params = function.params_to_args(args);
+    let generics = function.generics(None, args);
+    let target = escape_path(function.alias_of.as_ref().unwrap_or(&function.name));
+    let values = function.args.iter().map(|(param, kind)| {
+        // Part of the name the fragment binds the parameter under, which isn't escaped
+        let key = rust_segment(param);
+        let value = function.param_value(param, kind, args);
+        quote! { (#key, #value) }
+    });
+    // The type lives next to `stored_functions()`, above the modules of nested functions
+    let root = function.root_path(args);
+    let sensitive = function.sensitive();
+    let comments = function.attributes(args);
+    let fragment = quote! { #root QueryFragment::new(#target, ::std::vec![#(#values),*], &[#(#sensitive),*]) };
+    if args.bindings == Bindings::Serde {
+        // Serializing the arguments can fail
+        return quote! {
+            #comments
+            #[doc = ""]
+            #[doc = "Returns the call as a fragment to chain onto another query, instead of running it."]
+            pub fn #name #generics(#params) -> #krate::Result<#root QueryFragment> {
+                ::std::result::Result::Ok(#fragment)
+            }
+        };
+    }
+    quote! {
+        #comments
+        #[doc = ""]
+        #[doc = "Returns the call as a fragment to chain onto another query, instead of running it."]
+        pub fn #name #generics(#params) -> #root QueryFragment {
+            #fragment
+        }
+    }
+}
+
+/// Generates `name`, returning the query of the driver function and its bindings instead of running it.
+pub(crate) fn query_function(function: &DefineFunctionStatement, name: &Ident, args: &IncludeFnArgs) -> TokenStream2 {
+    let krate = crate::krate();
+    let params = function.params_to_args(args);
+    let generics = function.generics(None, args);
+    let query = function.custom_function_query();
+    let inserts = function.args.iter().map(|(param, kind)| {
+        let key = binding_name(param);
+        let value = function.param_value(param, kind, args);
+        quote! { bindings.insert(::std::string::ToString::to_string(#key), #value); }
+    });
+    let comments = function.attributes(args);
+    let output = quote! { (::std::string::String, ::std::collections::BTreeMap<::std::string::String, #krate::sql::Value>) };
+    let mutable = (!function.args.is_empty()).then(|| quote! { mut });
+    let body = quote! {
+        let #mutable bindings = ::std::collections::BTreeMap::new();
+        #(#inserts)*
+        (::std::string::ToString::to_string(#query), bindings)
+    };
+    if args.bindings == Bindings::Serde {
+        // Serializing the arguments can fail
+        return quote! {
+            #comments
+            #[doc = ""]
+            #[doc = "Returns the query the driver function runs and its bindings, without running it."]
+            pub fn #name #generics(#params) -> #krate::Result<#output> {
+                ::std::result::Result::Ok({ #body })
+            }
+        };
+    }
+    quote! {
+        #comments
+        #[doc = ""]
+        #[doc = "Returns the query the driver function runs and its bindings, without running it."]
+        pub fn #name #generics(#params) -> #output {
+            #body
+        }
+    }
+}
+
+/// Generates `batch(db)`, queueing fragments to run them in a single round trip.
+pub(crate) fn batch_function(args: &IncludeFnArgs) -> TokenStream2 {
+    let krate = crate::krate();
+    let (name, _) = args.transform_fn_name("batch");
+    let name = name.unwrap();
+    let generics = args.engine.generics();
+    let connection = args.engine.connection();
+
+    quote! {
+        #[doc = "Calls queued with `push`, run together in a single query by `run`."]
+        #[derive(::std::fmt::Debug)]
+        pub struct Batch<'r, C: #krate::Connection> {
+            db: &'r #krate::Surreal<C>,
+            fragments: ::std::vec::Vec<QueryFragment>,
+        }
+
+        impl<'r, C: #krate::Connection> Batch<'r, C> {
+            #[doc = "Queues a call, returning the index of its result in the response of `run`."]
+            pub fn push(&mut self, fragment: QueryFragment) -> usize {
+                self.fragments.push(fragment);
+                self.fragments.len() - 1
+            }
+
+            #[doc = "Runs every queued call in a single round trip."]
+            pub async fn run(self) -> #krate::Result<#krate::Response> {
+                let mut fragments = ::std::iter::IntoIterator::into_iter(self.fragments);
+                let ::std::option::Option::Some(first) = ::std::iter::Iterator::next(&mut fragments) else {
+                    let error = #krate::error::Api::InvalidRequest(::std::string::ToString::to_string("the batch is empty"));
+                    return ::std::result::Result::Err(::std::convert::Into::into(error));
+                };
+                let mut query = self.db.query(first.query).bind(first.bindings);
+                for fragment in fragments {
+                    query = fragment.append_to(query);
+                }
+                query.await
+            }
+        }
+
+        #[doc = "Starts a batch of calls to the included functions, queued with their `_fragment` functions."]
+        pub fn #name #generics(db: &#krate::Surreal<#connection>) -> Batch<'_, #connection> {
+            Batch {
+                db,
+                fragments: ::std::vec::Vec::new(),
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+
+use crate::{literal, parser::DefineFunctionStatement, rust_ident, source::SourceFile, Engine, IncludeFnArgs, Wrappers};
+
+/// Generates `#[cfg(test)] mod generated_tests`, defining every function in an in-memory database and calling each one
+/// with default arguments.
+///
+/// Calls only fail on errors from the driver or the database, a `THROW` from the function itself means it ran.
+pub(crate) fn generated_tests(functions: &[DefineFunctionStatement], sources: &[SourceFile], args: &IncludeFnArgs) -> TokenStream2 {
+    let krate = crate::krate();
+    let (define, _) = args.transform_fn_name("define_functions");
+    let define = define.unwrap();
+    let (connection, connect) = match args.engine {
+        Engine::Generic => (
+            quote! { #krate::engine::local::Db },
+            quote! { #krate::Surreal::new::<#krate::engine::local::Mem>(()).await.unwrap() },
+        ),
+        Engine::Any => (
+            quote! { #krate::engine::any::Any },
+            quote! { #krate::engine::any::connect("mem://").await.unwrap() },
+        ),
+    };
+
+    let tests = functions.iter().map(|function| {
+        let (name, _) = args.transform_fn_name(&function.rust_name(args));
+        let name = name.unwrap();
+        let modules = function.module_path(sources, args);
+        let modules = modules.iter().map(|module| rust_ident(module));
+        let samples = function.args.iter().map(|(param, kind)| function.param_sample(param, kind, args));
+        let test = rust_ident(&function.flat_name());
+        let cfg = function.cfg();
+        quote! {
+            #cfg
+            #[test]
+            #[allow(deprecated)]
+            fn #test() {
+                block_on(async {
+                    let db = database().await;
+                    check(super:: #(#modules::)* #name(&db, #(#samples),*).await.unwrap());
+                })
+            }
+        }
+    });
+
+    let literals = literal::imports(&literal::literal_names(functions, args));
+
+    quote! {
+        #[cfg(test)]
+        mod generated_tests {
+            #literals
+
+            fn block_on<F: ::std::future::Future>(future: F) -> F::Output {
+                ::tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap()
+                    .block_on(future)
+            }
+
+            async fn database() -> #krate::Surreal<#connection> {
+                let db = #connect;
+                db.use_ns("test").use_db("test").await.unwrap();
+                super::#define(&db).await.unwrap().check().unwrap();
+                db
+            }
+
+            fn check(response: #krate::Response) {
+                match response.check() {
+                    ::std::result::Result::Ok(_) | ::std::result::Result::Err(#krate::Error::Db(#krate::error::Db::Thrown(_))) => {}
+                    ::std::result::Result::Err(error) => ::std::panic!("{error}"),
+                }
+            }
+
+            #[test]
+            fn define_functions() {
+                block_on(database());
+            }
+
+            #(#tests)*
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+
+use crate::{
+    binding_name,
+    parser::{kind::Kind, DefineFunctionStatement},
+    proto::snake_case,
+    rust_ident, IncludeFnArgs, Wrappers,
+};
+
+/// Generates `GraphqlFunctions`, an async-graphql object with a field resolving every function.
+pub(crate) fn graphql_object(functions: &[DefineFunctionStatement], args: &IncludeFnArgs) -> TokenStream2 {
+    let krate = crate::krate();
+    let generics = args.engine.generics();
+    let connection = args.engine.connection();
+    let fields = functions
+        .iter()
+        .filter(|function| function.alias_of.is_none())
+        .map(|function| field(function, args));
+
+    quote! {
+        #[doc = "An async-graphql object with a field calling every included function, like `nestedGreet(name: String!)` for `fn::nested::greet`."]
+        #[doc = ""]
+        #[doc = "Merge it into the query or mutation root of the schema with `#[derive(MergedObject)]`."]
+        pub struct GraphqlFunctions<C: #krate::Connection> {
+            db: #krate::Surreal<C>,
+        }
+
+        impl #generics GraphqlFunctions<#connection> {
+            pub fn new(db: #krate::Surreal<#connection>) -> Self {
+                Self { db }
+            }
+        }
+
+        #[::async_graphql::Object]
+        impl #generics GraphqlFunctions<#connection> {
+            #(#fields)*
+        }
+    }
+}
+
+/// The resolver of the field calling `function`.
+fn field(function: &DefineFunctionStatement, args: &IncludeFnArgs) -> TokenStream2 {
+    let krate = crate::krate();
+    let name = rust_ident(&function.flat_name());
+    let query = function.custom_function_query();
+    let docs = function.comments.iter().map(|line| line.trim());
+    let deprecation = function.deprecation().map(|note| quote! { #[graphql(deprecation = #note)] });
+
+    let mut params = vec![];
+    let mut bindings = vec![];
+    for (param, kind) in &function.args {
+        let raw = param.to_raw();
+        let ident = rust_ident(&snake_case(&raw));
+        let (ty, value) = match (rust_type(kind), kind) {
+            (Some(ty), _) => (ty, quote! { #ident }),
+            (None, Kind::Option(_)) => (
+                quote! { ::std::option::Option<::async_graphql::Json<::serde_json::Value>> },
+                quote! { #ident.map(|json| json.0) },
+            ),
+            (None, _) => (quote! { ::async_graphql::Json<::serde_json::Value> }, quote! { #ident.0 }),
+        };
+        params.push(quote! { #ident: #ty });
+        let key = binding_name(&raw);
+        bindings.push(quote! { .bind((#key, #value)) });
+    }
+
+    let returns = function.returns.clone().unwrap_or_default();
+    let (inner, optional) = match &returns {
+        Kind::Option(kind) => (&**kind, true),
+        kind => (kind, false),
+    };
+    let (taken, output, wrap) = match rust_type(inner) {
+        Some(ty) => (ty.clone(), ty, quote! { value }),
+        None => (
+            quote! { ::serde_json::Value },
+            quote! { ::async_graphql::Json<::serde_json::Value> },
+            quote! { ::async_graphql::Json(value) },
+        ),
+    };
+    let (output, value) = if optional {
+        (quote! { ::std::option::Option<#output> }, quote! { value.map(|value| #wrap) })
+    } else {
+        let full = format!("fn::{}", function.name.join("::"));
+        (
+            output,
+            quote! {
+                let value = value.ok_or_else(|| ::async_graphql::Error::new(::std::format!("`{}` returned NONE", #full)))?;
+                #wrap
+            },
+        )
+    };
+    let timed_out = quote! { #krate::Error::Db(#krate::error::Db::QueryTimedout) };
+    let send = args.awaited(
+        quote! {
+            self.db.query(#query)
+                #(#bindings)*
+        },
+        timed_out,
+    );
+
+    quote! {
+        #(#[doc = #docs])*
+        #deprecation
+        async fn #name(&self, #(#params),*) -> ::async_graphql::Result<#output> {
+            let value: ::std::option::Option<#taken> = #send?.check()?.take(0)?;
+            ::std::result::Result::Ok({ #value })
+        }
+    }
+}
+
+/// The Rust type async-graphql maps to the GraphQL type of `kind`, if there's one better than the `JSON` scalar.
+fn rust_type(kind: &Kind) -> Option<TokenStream2> {
+    Some(match kind {
+        Kind::Bool => quote! { bool },
+        Kind::Int => quote! { i64 },
+        Kind::Float | Kind::Number => quote! { f64 },
+        Kind::String => quote! { ::std::string::String },
+        Kind::Option(kind) => {
+            let ty = rust_type(kind)?;
+            quote! { ::std::option::Option<#ty> }
+        }
+        Kind::Array(kind, _) | Kind::Set(kind, _) => {
+            let ty = rust_type(kind)?;
+            quote! { ::std::vec::Vec<#ty> }
+        }
+        _ => return None,
+    })
+}
@@ -0,0 +1,114 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+
+use crate::{
+    binding_name,
+    parser::{kind::Kind, DefineFunctionStatement},
+    proto::{rpc_name, snake_case, Field},
+    rust_ident, IncludeFnArgs, Wrappers,
+};
+
+/// Generates `GrpcFunctions`, implementing the `Functions` service tonic generated in `module` from the .proto of the functions.
+pub(crate) fn grpc_service(functions: &[DefineFunctionStatement], module: &TokenStream2, args: &IncludeFnArgs) -> TokenStream2 {
+    let krate = crate::krate();
+    let generics = args.engine.generics();
+    let connection = args.engine.connection();
+    let methods = functions
+        .iter()
+        .filter(|function| function.alias_of.is_none())
+        .map(|function| method(function, module, args));
+
+    quote! {
+        #[doc = "Serves the included functions over gRPC, implementing the `Functions` service of the .proto"]
+        #[doc = "written by `surrealdb_functions_runtime::write_proto`."]
+        #[doc = ""]
+        #[doc = "Add it to a tonic server with `FunctionsServer::new(GrpcFunctions::new(db))`."]
+        pub struct GrpcFunctions<C: #krate::Connection> {
+            db: #krate::Surreal<C>,
+        }
+
+        impl #generics GrpcFunctions<#connection> {
+            pub fn new(db: #krate::Surreal<#connection>) -> Self {
+                Self { db }
+            }
+        }
+
+        #[::tonic::async_trait]
+        impl #generics #module::functions_server::Functions for GrpcFunctions<#connection> {
+            #(#methods)*
+        }
+    }
+}
+
+/// The method of the service calling `function`.
+fn method(function: &DefineFunctionStatement, module: &TokenStream2, args: &IncludeFnArgs) -> TokenStream2 {
+    let krate = crate::krate();
+    let rpc = rpc_name(function);
+    let name = format_ident!("{}", snake_case(&rpc));
+    let request = format_ident!("{}Request", rpc);
+    let response = format_ident!("{}Response", rpc);
+    let query = function.custom_function_query();
+
+    let bindings = function.args.iter().map(|(param, kind)| {
+        let raw = param.to_raw();
+        // prost escapes keywords the same way
+        let field = rust_ident(&snake_case(&raw));
+        let value = match Field::of(kind) {
+            Field { scalar: Some(_), .. } => quote! { request.#field },
+            Field { scalar: None, optional: false } => quote! { json(#raw, request.#field)? },
+            Field { scalar: None, optional: true } => quote! {
+                request.#field.map(|text| json(#raw, text)).transpose()?
+            },
+        };
+        let key = binding_name(&raw);
+        quote! { .bind((#key, #value)) }
+    });
+
+    let returns = Field::of(function.returns.as_ref().unwrap_or(&Kind::Any));
+    let (taken, value) = match returns {
+        Field { scalar: Some(scalar), optional } => {
+            let ty = match scalar {
+                "bool" => quote! { bool },
+                "int64" => quote! { i64 },
+                "double" => quote! { f64 },
+                _ => quote! { ::std::string::String },
+            };
+            let value = if optional { quote! { value } } else { quote! { value.unwrap_or_default() } };
+            (ty, value)
+        }
+        Field { scalar: None, optional: true } => (quote! { ::serde_json::Value }, quote! { value.map(|value| value.to_string()) }),
+        Field { scalar: None, optional: false } => (
+            quote! { ::serde_json::Value },
+            quote! { value.unwrap_or(::serde_json::Value::Null).to_string() },
+        ),
+    };
+    let timed_out = quote! { #krate::Error::Db(#krate::error::Db::QueryTimedout) };
+    let send = args.awaited(
+        quote! {
+            self.db.query(#query)
+                #(#bindings)*
+        },
+        timed_out,
+    );
+    let doc = format!("Calls `fn::{}`.", function.name.join("::"));
+
+    quote! {
+        #[doc = #doc]
+        async fn #name(
+            &self,
+            request: ::tonic::Request<#module::#request>,
+        ) -> ::std::result::Result<::tonic::Response<#module::#response>, ::tonic::Status> {
+            #[allow(unused)]
+            fn json(param: &str, text: ::std::string::String) -> ::std::result::Result<::serde_json::Value, ::tonic::Status> {
+                ::serde_json::from_str(&text).map_err(|e| ::tonic::Status::invalid_argument(::std::format!("`{param}` isn't valid JSON: {e}")))
+            }
+            #[allow(unused)]
+            let request = request.into_inner();
+            let mut response = #send.map_err(|e| ::tonic::Status::internal(e.to_string()))?;
+            let value: ::std::option::Option<#taken> = response
+                .take(0)
+                .map_err(|e| ::tonic::Status::invalid_argument(e.to_string()))?;
+            ::std::result::Result::Ok(::tonic::Response::new(#module::#response { value: #value }))
+        }
+    }
+}
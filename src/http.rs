@@ -0,0 +1,78 @@
+use proc_macro2::{Ident, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+
+use crate::{binding_name, parser::DefineFunctionStatement, rust_ident, source::SourceFile, IncludeFnArgs, Wrappers};
+
+/// Generates an axum handler calling `function` with the parameters in the JSON object of the request body.
+pub(crate) fn handler_function(function: &DefineFunctionStatement, name: &Ident, args: &IncludeFnArgs) -> TokenStream2 {
+    let krate = crate::krate();
+    let query = function.custom_function_query();
+    let generics = args.engine.generics();
+    let connection = args.engine.connection();
+    let full = format!("fn::{}", function.name.join("::"));
+    let doc = format!("An axum handler calling `{full}`, with the parameters as a JSON object like `{{\"name\": ...}}` in the body.");
+    let params = function.args.iter().map(|(param, _)| param.to_raw());
+    let bindings = function.args.iter().map(|(param, _)| binding_name(param));
+    let timed_out = quote! { #krate::Error::Db(#krate::error::Db::QueryTimedout) };
+    let send = args.awaited(quote! { query }, timed_out);
+    quote! {
+        #[doc = #doc]
+        #[doc = ""]
+        #[doc = "Responds with what the function returns as JSON, `400 Bad Request` for unknown parameters,"]
+        #[doc = "`422 Unprocessable Entity` if the call failed and `500 Internal Server Error` if the query couldn't be sent."]
+        pub async fn #name #generics(
+            ::axum::extract::State(db): ::axum::extract::State<#krate::Surreal<#connection>>,
+            ::axum::Json(mut body): ::axum::Json<::serde_json::Map<::std::string::String, ::serde_json::Value>>,
+        ) -> ::std::result::Result<::axum::Json<::serde_json::Value>, (::axum::http::StatusCode, ::std::string::String)> {
+            let mut query = db.query(#query);
+            #(
+                // Missing parameters are left as NONE, for `option` kinds
+                if let ::std::option::Option::Some(value) = body.remove(#params) {
+                    query = query.bind((#bindings, value));
+                }
+            )*
+            if let ::std::option::Option::Some(key) = body.keys().next() {
+                return ::std::result::Result::Err((::axum::http::StatusCode::BAD_REQUEST, ::std::format!("`{}` has no parameter `{key}`", #full)));
+            }
+            let mut response = #send.map_err(|e| (::axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            let value: ::std::option::Option<::serde_json::Value> = response
+                .take(0)
+                .map_err(|e| (::axum::http::StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+            ::std::result::Result::Ok(::axum::Json(value.unwrap_or(::serde_json::Value::Null)))
+        }
+    }
+}
+
+/// Generates `router(db)`, routing `POST /fn/foo/bar` to the handler of `fn::foo::bar`.
+pub(crate) fn router_function(functions: &[DefineFunctionStatement], sources: &[SourceFile], args: &IncludeFnArgs) -> TokenStream2 {
+    let krate = crate::krate();
+    let (name, _) = args.transform_fn_name("router");
+    let name = name.unwrap();
+    let generics = args.engine.generics();
+    let connection = args.engine.connection();
+    let turbofish = args.engine.param().map(|_| quote! { ::<C> });
+
+    let routes = functions.iter().filter(|function| function.alias_of.is_none()).map(|function| {
+        let path = format!("/fn/{}", function.name.join("/"));
+        let (handler, _) = args.transform_fn_name(&function.rust_name(args));
+        let handler = format_ident!("{}_handler", handler.unwrap());
+        let modules = function.module_path(sources, args).into_iter().map(|module| rust_ident(&module));
+        let cfg = function.cfg();
+        quote! {
+            #cfg
+            let router = router.route(#path, ::axum::routing::post(self::#(#modules::)*#handler #turbofish));
+        }
+    });
+
+    quote! {
+        #[doc = "An axum `Router` calling the included functions, `fn::foo::bar` being served at `POST /fn/foo/bar`."]
+        #[doc = ""]
+        #[doc = "The parameters are sent as a JSON object in the body and the result is returned as JSON."]
+        #[doc = "Nest it in the router of the application to serve them under another prefix."]
+        pub fn #name #generics(db: #krate::Surreal<#connection>) -> ::axum::Router {
+            let router = ::axum::Router::new();
+            #(#routes)*
+            router.with_state(db)
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+
+use crate::{parser::DefineFunctionStatement, IncludeFnArgs, Wrappers};
+
+/// Wraps `body`, evaluating to the `Result` of calling `function`, in a `tracing` span recording errors.
+///
+/// Only the names of the arguments are recorded, never their values.
+pub(crate) fn instrumented(
+    args: &IncludeFnArgs,
+    function: &DefineFunctionStatement,
+    body: TokenStream2,
+) -> TokenStream2 {
+    if !args.instrument {
+        return body;
+    }
+    let name = format!("fn::{}", function.name.join("::"));
+    let params = function
+        .args
+        .iter()
+        .map(|(param, _)| param.to_raw())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let query = function.custom_function_query();
+    quote! {
+        let span = ::tracing::info_span!(#name, args = #params, query = #query);
+        let result = ::tracing::Instrument::instrument(async { #body }, ::std::clone::Clone::clone(&span)).await;
+        if let ::std::result::Result::Err(error) = &result {
+            ::tracing::error!(parent: &span, %error, "function call failed");
+        }
+        result
+    }
+}
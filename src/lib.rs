@@ -11,23 +11,69 @@
 //! Check the [`surrealdb_functions::include_fn`] macro for more information.
 //! 
 //! [`surrealdb_functions::include_fn`]: macro.include_fn.html
+//!
+//! If you'd rather call the functions as methods on a client struct, check the [`surrealdb_functions::SurqlFunctions`] derive.
+//!
+//! [`surrealdb_functions::SurqlFunctions`]: derive.SurqlFunctions.html
+#![cfg_attr(feature = "nightly", feature(proc_macro_tracked_env, proc_macro_tracked_path))]
+mod changelog;
+mod checked;
+mod datetime;
+mod derive;
+mod dispatch;
 mod file;
-mod parser;
+mod fragment;
+mod generated_tests;
+mod graphql;
+mod grpc;
+mod http;
+mod instrument;
+mod literal;
+mod live;
+mod mapping;
+mod metadata;
+mod metrics;
+mod migration;
+mod page;
+mod schema;
+mod service;
+mod source;
+mod stats;
+mod target;
+#[cfg(feature = "remote")]
+mod remote;
+#[cfg(feature = "database")]
+mod database;
+mod traits;
+#[cfg(feature = "validate")]
+mod validate;
 
 use proc_macro::TokenStream;
 use std::{
-    collections::{HashMap, HashSet},
+    cell::RefCell,
+    collections::BTreeMap,
     error::Error,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
-use nom::combinator::all_consuming;
 use proc_macro2::{Ident, Literal, Span, TokenStream as TokenStream2};
-use proc_macro_error::{abort, proc_macro_error};
-use quote::quote;
-use syn::{parse::Parse, parse_macro_input};
+use proc_macro_error::{abort, abort_call_site, proc_macro_error};
+use quote::{format_ident, quote, ToTokens};
+// Shared with the runtime crate, which writes the .proto file from build scripts
+use surrealdb_functions_core::{parser, proto};
+use syn::{ext::IdentExt, parse::Parse, parse_macro_input};
 
-use parser::{kind::Kind, DefineFunctionStatement};
+use literal::{LiteralKind, LiteralTypes};
+use live::Live;
+use mapping::TypeMap;
+use page::Paged;
+use parser::{
+    escape::{escape_ident, escape_path},
+    kind::Kind,
+    DefineFunctionStatement,
+};
+use source::{Origin, SourceFile};
 
 
 
@@ -36,18 +82,174 @@ use parser::{kind::Kind, DefineFunctionStatement};
 /// This function parses a list of .surql files and generates a module containing functions for each function defined in the files.
 /// 
 /// Output:
-/// - `stored_functions() -> String`: Returns a string containing all the functions defined in the included files.
+/// - `stored_functions() -> String`: Returns a string containing all the functions defined in the included files,
+///   their `DEFINE FUNCTION` statements without the comments around them.
+/// - `stored_function_names() -> &[&str]` and `stored_function(name) -> Option<&str>`: The names of the included functions,
+///   and the `DEFINE FUNCTION` statement of a single one.
+/// - `enum FunctionId` and `define_function(db: &Surreal, function: FunctionId) -> Result<Response>`: A variant for each included function,
+///   like `FunctionId::NestedGreet` for `fn::nested::greet`, and defines only that one.
 /// - `define_functions(db: &Surreal) -> Result<Response>`: Defines all the functions using the provided connection.
+///   `define_functions_report(db: &Surreal) -> Result<DefineReport<Error>>` runs the same query, but returns the result of each
+///   `DEFINE FUNCTION` statement by function name, to tell which ones failed.
+/// - `FUNCTIONS_HASH: &str`: Hash of the included functions. `store_functions_hash(db: &Surreal) -> Result<Response>` stores it
+///   in the `$functions_hash` param, `define_functions` doesn't write anything else than the functions.
+/// - `functions_version(db: &Surreal) -> Result<Option<String>>`: Reads back the hash last stored in the database.
 /// - `async fn <name>(db: &Surreal, /* parsed arguments */) -> Result<Response>`: Defined functions from the .surql file.
 ///   If a function has a comment above it, the comment will be used as the documentation for the function.
+///   A `-- @alias old_name` comment line also generates a deprecated `old_name` calling the function, to ease renames.
+///   A `-- @deprecated use fn::v2::greet` comment line marks the wrappers of the function `#[deprecated]` with the rest of the line as note.
+///   A `-- @rust_name fetch_user` comment line names the generated function `fetch_user` instead.
+///   Escaped names like ``fn::⟨café⟩::`1st-greet`($⟨first name⟩: string)`` have accented letters transliterated and other invalid
+///   characters replaced with `_`, becoming `cafe::_1st_greet(first_name)`, while names like `⟨名前⟩` get a hash
+///   of the name instead; `-- @rust_param $⟨first name⟩ given_name` names a parameter.
+///   A `-- @cfg(feature = "admin")` comment line only compiles the wrappers of the function, and stores it in `stored_functions()`,
+///   under that `#[cfg(...)]`. `FUNCTIONS_HASH` only covers the functions compiled in.
 ///   <name> is the last part of the function's name that's transformed based on the driver and datastore arguments.
 ///   If a function in the .surql file has a name that is more than one part, each part is treated as a module.
 ///   For example, a function named `foo::bar` will be generated as `mod foo { async fn bar(/* ... */) } }`.
+/// - `<NAME>_FN: &str`: Next to each function, its full name like `fn::nested::greet`, for queries written by hand.
+/// - `<name>_value(db: &Surreal, ...) -> Result<T>`: Next to each driver function, checks the response and takes the result.
+///   `T` is a `Value`, or a Rust type when the function declares what it returns with `-> kind` or a `-- @returns kind` comment.
+///   Object literals like `-> { id: record<user>, score: float }` deserialize into a generated `<Function>Output` struct,
+///   the invoking crate needs `serde` as a dependency.
+/// - `<name>_query(...) -> (String, BTreeMap<String, Value>)`: Next to each driver function, returns the query it runs and its bindings
+///   without running it, for logging, tests or embedding the call into a larger query.
+/// - `<name>_live(db: &Surreal, ...) -> Result<QueryStream<Notification<Value>>>`: Next to each driver function whose body has a `LIVE SELECT`,
+///   or marked with a `-- @live` comment line, runs that statement with the parameters bound and streams its notifications.
 /// 
 /// Arguments:
 /// - `driver as <alias>`: The alias to use for the driver functions. If not provided, the functions will not be generated.
 /// - `datastore as <alias>`: The alias to use for the datastore functions. If not provided, the functions will not be generated.
+/// - `driver as <alias> if "<feature>"`: Only generate the driver or datastore functions when the invoking crate enables `<feature>`,
+///   like `datastore as ds_$ if "kv";`. Everything depending on them is behind `#[cfg(feature = "<feature>")]` as well.
+///   Datastore functions return the result of the call, add `raw` (`datastore as <alias> raw`) to get every `Response` instead.
+///   Each also gets a `<name>_with_vars` variant taking an extra `BTreeMap<String, Value>` of variables, which never override the parameters.
+/// - `duplicates as error|first|last`: What to do when several files define the same function.
+///   `error` (the default) fails the build naming both files, `first` and `last` keep the wrapper
+///   for the first or last definition in the order the files are included. With `first`, the later definitions aren't stored.
+/// - `override_duplicates`: Like `duplicates as last`, but the overridden definitions are also left out of `stored_functions()`.
+///   Every overridden function is reported as a warning.
+/// - `continue_on_error`: Skip the statements of a file that fail to parse instead of failing the build, listing them in a single warning.
+///   The functions that do parse still get wrappers, and only they are stored.
+/// - `dispatch`: Also generate `dispatch(db, name, args: Vec<Value>)` calling any of the functions by name, checking the number of arguments.
+///   Useful for scripting environments that only know the function names at runtime. Requires `driver`.
+/// - `fragments`: Also generate `<name>_fragment(...) -> QueryFragment` next to each driver function, returning the call instead of running it.
+///   `QueryFragment::append_to(query)` chains it onto another query, like several calls inside `BEGIN TRANSACTION ... COMMIT TRANSACTION`. Requires `driver`.
+/// - `batch`: Like `fragments`, also generating `batch(db)` to queue fragments with `push` and `run` them in a single round trip,
+///   the result of every call is at the index returned by `push`. Requires `driver`.
+/// - `paged`: Also generate `<name>_paged(..., limit, start) -> Page<T>` next to each driver function returning an `array` or `set`,
+///   taking a page of the items. Functions taking a `$limit` or `$start` of their own, or a parameter named `limit` or `start` in Rust, are left out. Requires `driver`.
+/// - `stats`: Also generate `<name>_with_stats(...) -> Timed<T>` next to each driver function, taking the result like `_value`
+///   along with the execution time the database reported for the call, to record slow calls. Requires `driver`.
+/// - `targets`: Also generate `<name>_in(db, target: &CallTarget, ...) -> T` next to each driver function, running the call in another
+///   namespace or database with a `USE` statement ahead of it in the same query, like per-tenant databases. Requires `driver`.
+/// - `tower`: Like `dispatch`, also generating `service(db) -> FnService<C>`, a `tower::Service<FnRequest>` calling the functions by name,
+///   to use them behind tower middleware. The invoking crate needs `tower` as a dependency. Requires `driver`.
+/// - `retry` or `retry(times = 3, backoff = "100ms")`: Retry the driver functions on network errors (`Api::Http` and `Api::Ws`),
+///   up to `times` times after the first attempt, waiting `backoff` before the first retry and twice as long before every next one.
+///   The arguments are converted once up front so they can be bound again. The invoking crate needs `tokio` with the `time` feature.
+/// - `define as parallel(8)`: Send every statement of `define_functions` in its own query, at most 8 at once,
+///   instead of a single query holding all of them, to define many functions faster over a remote connection.
+///   It fails on the first statement that fails, dropping the queries still running.
+///   Applies to the driver and blocking functions, the invoking crate needs `futures` as a dependency.
+/// - `timeout "5s"`: Fail the driver functions once they take longer, with `surrealdb::Error::Db(surrealdb::error::Db::QueryTimedout)`.
+///   With `retry`, it covers every attempt. The call only stops waiting, the database may still run the query to the end.
+///   The invoking crate needs `tokio` with the `time` feature.
+///   Datastore calls can't be dropped halfway through their transaction, use `Datastore::with_query_timeout` for them instead.
+/// - `instrument`: Wrap every call of a driver, datastore or trait function in a `tracing` span named after the function,
+///   with the names of its arguments (never their values) and the query, recording errors as events.
+///   The invoking crate needs `tracing` as a dependency.
+/// - `metrics`: Also generate `set_function_observer`, registering a `FunctionCallObserver` told about every call of a driver,
+///   datastore or trait function, with how long it took and whether it succeeded. Only needs `std`.
+/// - `tests`: Also generate `#[cfg(test)] mod generated_tests`, defining the functions in an in-memory database and calling each one
+///   with default arguments, so drift between the files and the driver is caught by `cargo test`. A `THROW` from a function
+///   still passes. The invoking crate needs surrealdb's `kv-mem` feature and `tokio` as (dev-)dependencies. Requires `driver`.
+/// - `geo`: Take parameters of a single geometry type, like `point` or `geometry<polygon>`, as the matching `geo` type
+///   (`impl Into<geo::Point<f64>>`, `impl Into<geo::Polygon<f64>>`, ...) instead of any `Geometry`, so passing the wrong
+///   geometry type fails to compile. The invoking crate needs `geo` (or at least the `geo-types` 0.7 surrealdb uses) as a dependency.
+/// - `uuid`: Take `uuid` parameters as `impl Into<uuid::Uuid>` (and `option<uuid>` as `Option<uuid::Uuid>`) instead of surrealdb's `Uuid`,
+///   converted when the function is called. The invoking crate needs `uuid` 1.x as a dependency.
+/// - `map <kind> => <type>` or `map fn::<name>($<param>) => <type>`: Take `impl Into<type>` for every parameter of the kind, written like in
+///   the .surql files, or for a single parameter, like `map record<user> => crate::UserId;`. The type has to convert into the type the kind
+///   is bound as otherwise, like `Thing` for records, `String` for strings or `Value` for `option`, `any` and either kinds,
+///   and should be an absolute path, nested functions are generated in modules.
+///   With `tests`, the mapped types also need to implement `Default`.
+/// - `chrono` and `time`: Also generate `IntoDatetime`, taken by `datetime` parameters instead of `impl Into<Datetime>`, implemented for
+///   `Datetime` and `std::time::SystemTime`, plus `chrono::DateTime<Tz>` in any timezone with `chrono` and `time::OffsetDateTime` with `time`.
+///   The invoking crate needs the `chrono` or `time` crate as a dependency.
+/// - `checked_args`: Take `bool`, `string`, `int`, `float`, `decimal`, `number` and `duration` parameters as `impl IntoString` and co.,
+///   traits generated next to `stored_functions()` for the Rust types of each kind, so a wrong argument fails with a message naming the kind.
+/// - `wasm`: Generate code for `wasm32-unknown-unknown` and the WASM SDK, where futures aren't `Send`.
+///   Only changes what needs a `Send` bound otherwise, like the future of `FnService`.
+/// - `http as axum`: Also generate an axum handler for every function, `<name>_handler`, taking its parameters as a JSON object in the body,
+///   and `router(db)` serving them at `POST /fn/foo/bar` for `fn::foo::bar`. The invoking crate needs `axum` and `serde_json` as dependencies.
+///   Requires `driver`.
+/// - `grpc as <module>`: Also generate `GrpcFunctions`, implementing the `Functions` service tonic generated in `<module>`
+///   from the .proto written by `surrealdb_functions_runtime::write_proto` in the build script, with an rpc for every function.
+///   The invoking crate needs `tonic` and `serde_json` as dependencies. Requires `driver`.
+/// - `graphql`: Also generate `GraphqlFunctions`, an async-graphql object with a field resolving every function, like `nestedGreet` for
+///   `fn::nested::greet`. Parameters and results are mapped to GraphQL scalars and lists from their kind, anything else is `JSON`.
+///   The invoking crate needs `async-graphql` and `serde_json` as dependencies. Requires `driver`.
+/// - `migrations`: Also generate `plan_migration(db) -> MigrationPlan`, comparing the functions in the database with the included ones.
+///   The plan lists which functions to create, update or remove, and `apply(db)` runs it in a transaction. Requires `driver`.
+///   The invoking crate needs `surrealdb_functions_runtime` with its `migrations` feature as a dependency, like `verify`.
+/// - `verify`: Also generate `verify_functions(db) -> VerificationReport`, listing the functions missing from the database
+///   or defined differently, like a health check on startup. Requires `driver` and `surrealdb_functions_runtime` with its `migrations` feature.
+/// - `sync`: Also generate `sync_functions(db) -> SyncSummary`, only defining the functions whose hash differs from the one stored
+///   in the `_surql_functions_meta` table, then storing the new hashes. Requires `driver`.
+/// - `metadata`: Also generate `FUNCTIONS: &[FunctionMeta]`, describing the name, parameters, return kind and signature of every function.
+///   Parameters named by a `-- @sensitive $param` comment line are flagged, their values are also redacted from the `Debug` output of
+///   `QueryFragment` and `FnRequest`.
+/// - `export_schema`: Also generate `functions_schema() -> String`, a JSON document describing every function as an operation,
+///   with a JSON Schema of its parameters and of what it returns, for documenting an HTTP or RPC API calling them.
+/// - `bindings as typed|serde`: `typed` (the default) takes every parameter as the surrealdb type matching its kind,
+///   `serde` takes any `impl serde::Serialize` instead, converted with `surrealdb::sql::to_value`, for custom domain types.
+///   Conversion errors are returned by the call, `_fragment` and `_query` functions return a `Result` for them. The invoking crate needs `serde` as a dependency.
+/// - `examples as run|no_run|ignore`: How the ```rust blocks of the comments above a function run as doctests. They're moved to an
+///   `# Examples` section of the first wrapper generated for it, `run` (the default) runs them, `no_run` only compiles them, like examples
+///   needing a database.
+/// - `modules as nested|flat|per_file`: `nested` (the default) generates `fn::foo::bar` as `foo::bar`, `flat` generates `foo_bar` without any modules.
+///   `per_file` generates a module for every file, named after it, with the functions of the file flat inside it,
+///   like `user::foo_bar` for `fn::foo::bar` from `user.surql`.
+/// - `docs as signature|body`: Every wrapper is documented with the kinds of its parameters and return value and the
+///   `DEFINE FUNCTION` signature in a ```` ```surql ```` block. `body` shows the whole statement as written in the file instead.
+/// - `stored as <name>`: Name of the function returning the stored text instead of `stored_functions`.
+///   The functions listing and returning single statements are named after it, `<name>` without a trailing `s` or `<name>_function`.
+/// - `mod as <name>`: Wrap every generated item in `pub mod <name>`, so several invocations in one module don't collide.
+/// - `version as <segment>`: Prefix the name of every included function with `<segment>` in SurrealQL, like `fn::v2::greet` for
+///   `fn::greet`, in the stored statements, the calls between them and the queries of the wrappers. The Rust names stay the same,
+///   so two builds of the same files can be defined side by side for blue/green rollouts. The hash param is prefixed too, `$v2_functions_hash`.
+/// - `blocking as <alias> [tokio] [if "feature"]`: Also generate synchronous functions (and `define_functions`) for codebases
+///   that aren't async, blocking the current thread until the query is done. They wait with `futures::executor::block_on`,
+///   the invoking crate needs `futures` as a dependency, or with `tokio` on a `&tokio::runtime::Handle` taken as the first argument.
+/// - `engine as any|generic`: `any` generates non-generic functions taking `&Surreal<Any>` instead of any `&Surreal<C>`,
+///   to put them behind `dyn` interfaces and avoid monomorphizing them for every connection. `generic` by default. Requires `driver`.
+/// - `crate as <path>`: Path of the surrealdb crate in the generated code, like `crate as ::my_company_db::surrealdb;`
+///   for a renamed or re-exported dependency. `::surrealdb` by default.
+/// - `surreal_version 1|2`: Major version of surrealdb to generate code for, `1` by default.
+///   With `2`, `_value` functions return the `surrealdb::Value` of the client API (and `RecordId` for records) instead of `surrealdb::sql` types.
+/// - `validate`: Run the included files through the official surrealdb parser and fail the build on syntax errors,
+///   including the ones inside function bodies, or when it disagrees with this crate about the functions and their parameters.
+///   Requires the `validate` feature.
+/// - `trait as <Name>`: Also generate a `pub trait <Name>` with a method for every function (named like the driver functions,
+///   nested names joined with `_`), implemented for `Surreal<C>`. Useful for mocking the functions in tests. Requires `driver`.
+/// - `changelog "<path>"`: Compare the generated API against the snapshot at `<path>` and report every added, removed or changed
+///   function as a compiler warning. The changes are also written to `<path>` with a `.changes` extension, one tab separated line each.
+///   A missing snapshot is created, set `SURREALDB_FUNCTIONS_UPDATE_SNAPSHOT` to rewrite an existing one.
+/// - `url "<url>" sha256 "<hex>"` or `remote "<url>" sha256 "<hex>"`: Fetch a file over HTTPS during expansion and embed its contents,
+///   failing if its SHA-256 doesn't match. Requires the `remote` feature.
+/// - `database`: Generate wrappers for the functions defined in a development database instead of (or along with) files,
+///   reading them from `INFO FOR DB` during expansion. Only done when `SURREALDB_FUNCTIONS_ENDPOINT` is set to its HTTP endpoint,
+///   like `http://localhost:8000`, with `SURREALDB_FUNCTIONS_NS`, `_DB` and the optional root `_USER` and `_PASS`.
+///   Requires the `database` feature.
+/// - `inline "<statements>"`: Include `DEFINE FUNCTION` statements written in the macro input, like `inline r#"DEFINE FUNCTION fn::tiny($x: int) { RETURN $x; };"#;`,
+///   for tests and examples that shouldn't need a file. They're stored like the ones of a file, in the order they're included.
+/// - `roots [<root>, ...]`: Directories relative paths after it are resolved against, trying each root in order,
+///   like `roots [$CARGO_MANIFEST_DIR, $WORKSPACE_ROOT];`. Roots are either `$VARIABLE`s or string literals.
 /// - `[<path>]`: The path to the .surql file to include. If the path is a directory, all .surql files in the directory will be included.
+///   Files are used in the order they are included, directories are walked in sorted order.
+/// - `if env "<VARIABLE>" == "<value>" { <path>; ... } else { <path>; ... }`: Include the paths of the first branch whose condition holds,
+///   like stubs in dev builds. `!=` and `else if env ...` work too, an unset variable is unequal to every value.
 /// 
 /// <alias> can be one of the following:
 /// - `is` will not apply any changes to the method names.
@@ -86,18 +288,203 @@ use parser::{kind::Kind, DefineFunctionStatement};
 #[proc_macro]
 #[proc_macro_error]
 pub fn include_fn(input: TokenStream) -> TokenStream {
+    file::reset_tracking();
     include_fn_impl(parse_macro_input!(input as IncludeFnArgs)).into()
 }
 
+/// Generate inherent methods on a struct holding a `Surreal<C>` for every function in the included .surql files.
+///
+/// The struct must be annotated with `#[surql(...)]`, which accepts the same arguments as [`include_fn!`],
+/// except only the `driver as <alias>` flavor is used for naming the methods.
+///
+/// Output:
+/// - `async fn define_functions(&self) -> Result<Response>`: Defines all the functions using the struct's connection.
+/// - `FUNCTIONS_HASH: &str`, `async fn store_functions_hash(&self) -> Result<Response>` and
+///   `async fn functions_version(&self) -> Result<Option<String>>`: Like the ones `include_fn!` generates.
+/// - `async fn <name>(&self, /* parsed arguments */) -> Result<Response>`: Defined functions from the .surql file.
+///   Since methods can't be put in modules, nested function names are joined with `_`,
+///   so `fn::foo::bar` becomes `foo_bar`.
+///
+/// The connection used is the field marked with `#[surql(db)]`, otherwise the field named `db`,
+/// otherwise the only field of the struct.
+///
+/// # Example
+///
+/// ```
+/// # extern crate surrealdb;
+/// # extern crate tokio;
+/// #
+/// use surrealdb::{engine::local::{Db, Mem}, Surreal};
+/// use surrealdb_functions::SurqlFunctions;
+///
+/// #[derive(SurqlFunctions)]
+/// #[surql(driver as is; "$CARGO_MANIFEST_DIR/tests/main.surql")]
+/// struct Client {
+///     db: Surreal<Db>,
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> surrealdb::Result<()> {
+///     let db = Surreal::new::<Mem>(()).await?;
+///     db.use_ns("test").use_db("test").await?;
+///     let client = Client { db };
+///     client.define_functions().await?.check()?;
+///     dbg!(client.greet_but_with_number("client", 10).await?.check()?);
+///     Ok(())
+/// }
+/// ```
+#[proc_macro_derive(SurqlFunctions, attributes(surql))]
+#[proc_macro_error]
+pub fn derive_surql_functions(input: TokenStream) -> TokenStream {
+    file::reset_tracking();
+    derive::surql_functions(parse_macro_input!(input as syn::DeriveInput)).into()
+}
+
 fn include_fn_impl(input: IncludeFnArgs) -> TokenStream2 {
-    let bootstrap = bootstrap_for_files(&input).unwrap();
-    let functions = build_mod_tree(&input).unwrap();
+    set_krate(input.crate_path.as_ref());
+    let (sources, mut notes) = match source::load_sources(&input) {
+        Ok(loaded) => loaded,
+        Err(e) => abort_call_site!("{}", e),
+    };
+    let bootstrap = bootstrap_for_files(&input, &sources);
+    let parsed = source::functions_of(&sources);
+    input.types.check(&parsed);
+    notes.extend(api_changes(&input, &parsed));
+    notes.extend(kind_notes(&parsed));
+    let warnings = warnings(&notes);
+    let stored_trait = match &input.trait_name {
+        Some(name) => traits::stored_functions_trait(name, &parsed, &input),
+        None => TokenStream2::new(),
+    };
+    let dispatch = match input.dispatch {
+        true => dispatch::dispatch_function(&parsed, &input),
+        false => TokenStream2::new(),
+    };
+    let mut fragment = match input.fragments {
+        true => fragment::fragment_type(),
+        false => TokenStream2::new(),
+    };
+    if input.batch {
+        fragment.extend(fragment::batch_function(&input));
+    }
+    if input.paged {
+        fragment.extend(page::page_type());
+    }
+    if input.stats {
+        fragment.extend(stats::timed_type());
+    }
+    if input.targets {
+        fragment.extend(target::target_type());
+    }
+    let service = match input.tower {
+        true => service::service_type(&parsed, &input),
+        false => TokenStream2::new(),
+    };
+    let router = match input.http {
+        Some(Http::Axum) => http::router_function(&parsed, &sources, &input),
+        None => TokenStream2::new(),
+    };
+    let grpc = match &input.grpc {
+        Some(module) => grpc::grpc_service(&parsed, module, &input),
+        None => TokenStream2::new(),
+    };
+    let graphql = match input.graphql {
+        true => graphql::graphql_object(&parsed, &input),
+        false => TokenStream2::new(),
+    };
+    let metadata = match input.metadata {
+        true => metadata::metadata_const(&parsed),
+        false => TokenStream2::new(),
+    };
+    let schema = match input.export_schema {
+        true => schema::schema_function(&parsed),
+        false => TokenStream2::new(),
+    };
+    let metrics = match input.metrics {
+        true => metrics::observer_items(),
+        false => TokenStream2::new(),
+    };
+    let datetime = match input.datetimes() {
+        true => datetime::datetime_trait(&input),
+        false => TokenStream2::new(),
+    };
+    let checked = match input.checked_args {
+        true => checked::checked_traits(),
+        false => TokenStream2::new(),
+    };
+    let literals = literal::literal_types(&parsed, &input);
+    let mut migration = TokenStream2::new();
+    if input.migrations || input.verify {
+        migration.extend(migration::database_functions(&input));
+    }
+    if input.migrations {
+        migration.extend(migration::migration_functions(&input, &sources));
+    }
+    if input.verify {
+        migration.extend(migration::verify_function(&input, &sources));
+    }
+    if input.sync {
+        migration.extend(migration::sync_function(&input));
+    }
+    let tests = match input.tests {
+        true => generated_tests::generated_tests(&parsed, &sources, &input),
+        false => TokenStream2::new(),
+    };
+    let driver_only = quote! {
+        #stored_trait
+
+        #dispatch
+
+        #service
+
+        #router
+
+        #grpc
+
+        #graphql
+
+        #fragment
+
+        #migration
+
+        #tests
+    };
+    let driver_only = cfg_items(input.driver_cfg.as_deref(), driver_only);
+    let functions = build_mod_tree(parsed, &sources, &input);
 
     // eprintln!("{}", functions.to_string());
-    quote! {
+    let items = quote! {
         #bootstrap
 
+        #driver_only
+
+        #metadata
+        #schema
+
+        #metrics
+
+        #datetime
+        #checked
+
+        #literals
+
         #functions
+    };
+    let items = match &input.module {
+        Some(name) => quote! {
+            pub mod #name {
+                #items
+            }
+        },
+        None => items,
+    };
+    let tracking = file::tracking();
+    quote! {
+        #warnings
+
+        #tracking
+
+        #items
     }
 }
 
@@ -151,416 +538,4428 @@ impl Parse for Alias {
     }
 }
 
-#[derive(Debug)]
-struct IncludeFnArgs {
-    paths: HashSet<PathBuf>,
-    driver: Option<Alias>,
-    datastore: Option<Alias>,
+/// How to handle a function defined more than once across the included files.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+enum Duplicates {
+    #[default]
+    Error,
+    First,
+    Last,
+    /// Last wins, also for `stored_functions()`.
+    Override,
 }
 
-impl IncludeFnArgs {
-    fn transform_fn_name(&self, name: &str) -> (Option<Ident>, Option<Ident>) {
-        (
-            self.driver
-                .as_ref()
-                .map(|alias| Ident::new(&alias.transform(name), Span::call_site())),
-            self.datastore
-                .as_ref()
-                .map(|alias| Ident::new(&alias.transform(name), Span::call_site())),
-        )
+impl Parse for Duplicates {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        let ident: Ident = input.parse()?;
+        match ident.to_string().as_str() {
+            "error" => Ok(Self::Error),
+            "first" => Ok(Self::First),
+            "last" => Ok(Self::Last),
+            _ => abort!(ident, "invalid duplicates policy"; help = "expected `error`, `first` or `last`"),
+        }
     }
 }
 
-impl Parse for IncludeFnArgs {
-    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
-        let mut paths = HashSet::new();
-        let mut driver = None;
-        let mut datastore = None;
+/// Where functions with multi-part names are generated.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+enum Modules {
+    /// `fn::foo::bar` becomes `foo::bar`.
+    #[default]
+    Nested,
+    /// `fn::foo::bar` becomes `foo_bar`.
+    Flat,
+    /// `fn::foo::bar` from `user.surql` becomes `user::foo_bar`.
+    PerFile,
+}
 
-        while !input.is_empty() {
-            let ident: Option<Ident> = input.parse()?;
-            if let Some(ident) = ident {
-                match ident.to_string().as_str() {
-                    "driver" => {
-                        input.parse::<syn::Token![as]>()?;
-                        driver = Some(Alias::parse(input)?);
-                        if driver.eq(&datastore) {
-                            abort!(ident, "driver and datastore cannot be the same")
-                        }
-                    }
-                    "datastore" => {
-                        input.parse::<syn::Token![as]>()?;
-                        datastore = Some(Alias::parse(input)?);
-                        if driver.eq(&datastore) {
-                            abort!(ident, "driver and datastore cannot be the same")
-                        }
-                    }
-                    _ => {
-                        abort!(ident, "unknown argument"; help="only driver and datastore are supported")
-                    }
-                }
-            } else {
-                let lit: Literal = input.parse()?;
-                match file::resolve_path(lit.to_string().trim_matches('"'), file::get_env) {
-                    Ok(path) => {
-                        if path.exists() {
-                            paths.extend(expand_path(&path).unwrap());
-                        } else {
-                            abort!(lit, "file does not exist"; note="make sure the file exists");
-                        }
-                    }
-                    Err(e) => {
-                        abort!(lit, format!("failed to resolve path: {e}"); note="make sure the path is valid")
-                    }
-                }
-            }
-            if input.is_empty() {
-                break;
-            }
-            input.parse::<syn::Token![;]>()?;
+impl Parse for Modules {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        let ident: Ident = input.parse()?;
+        match ident.to_string().as_str() {
+            "nested" => Ok(Self::Nested),
+            "flat" => Ok(Self::Flat),
+            "per_file" => Ok(Self::PerFile),
+            _ => abort!(ident, "invalid modules layout"; help = "expected `nested`, `flat` or `per_file`"),
         }
+    }
+}
 
-        if datastore.is_none() && driver.is_none() {
-            panic!("no driver or datastore provided");
-        }
+/// How much of the statement the documentation of the wrappers shows.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+enum Docs {
+    /// `DEFINE FUNCTION fn::name($param: kind) -> kind`.
+    #[default]
+    Signature,
+    /// The statement as written in the file, including the body.
+    Body,
+}
 
-        if paths.is_empty() {
-            panic!("no paths provided");
+impl Parse for Docs {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        let ident: Ident = input.parse()?;
+        match ident.to_string().as_str() {
+            "signature" => Ok(Self::Signature),
+            "body" => Ok(Self::Body),
+            _ => abort!(ident, "invalid docs"; help = "expected `signature` or `body`"),
         }
-
-        Ok(Self {
-            paths,
-            driver,
-            datastore,
-        })
     }
 }
 
-#[derive(Debug, Default)]
-struct Function(Vec<DefineFunctionStatement>, HashMap<String, Function>);
+/// How the parameters of the functions are taken and bound.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+enum Bindings {
+    /// `impl Into<T>` of the surrealdb type matching the kind.
+    #[default]
+    Typed,
+    /// `impl serde::Serialize`, converted with `surrealdb::sql::to_value`.
+    Serde,
+}
 
-impl From<Vec<DefineFunctionStatement>> for Function {
-    fn from(v: Vec<DefineFunctionStatement>) -> Self {
-        let mut rooted = vec![];
-        let mut nested = HashMap::new();
+impl Parse for Bindings {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        let ident: Ident = input.parse()?;
+        match ident.to_string().as_str() {
+            "typed" => Ok(Self::Typed),
+            "serde" => Ok(Self::Serde),
+            _ => abort!(ident, "invalid bindings"; help = "expected `typed` or `serde`"),
+        }
+    }
+}
 
-        for item in v {
-            if item.name.len() == 1 {
-                // This function doesn't have a parent, so it's treated as a root function
-                rooted.push(item);
-            } else {
-                // This function has more than one part for it's name so each part is treated as a module
-                let mut current = &mut nested;
-                let mut next_items: &mut Vec<DefineFunctionStatement> = &mut vec![];
-                // Iterate over each part of the name, if the part doesn't exist in the current module, create it
-                // If it's the last part of the name, add the function to the module
-                let len = item.name.len();
-                for (i, part) in item.name.iter().enumerate() {
-                    if i == len - 1 {
-                        next_items.push(item.clone());
-                    } else {
-                        let nested: &mut Function = current.entry(part.clone()).or_default();
-                        next_items = &mut nested.0;
-                        current = &mut nested.1;
-                    }
+/// How `define_functions` sends the statements.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+enum Define {
+    /// Every statement in a single query.
+    #[default]
+    Query,
+    /// A query per statement, at most this many at once.
+    Parallel(usize),
+}
+
+impl Parse for Define {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        let ident: Ident = input.parse()?;
+        match ident.to_string().as_str() {
+            "query" => Ok(Self::Query),
+            "parallel" => {
+                let content;
+                syn::parenthesized!(content in input);
+                let limit: syn::LitInt = content.parse()?;
+                match limit.base10_parse()? {
+                    0 => abort!(limit, "`parallel` needs at least one query at once"),
+                    limit => Ok(Self::Parallel(limit)),
                 }
             }
+            _ => abort!(ident, "invalid define"; help = "expected `query` or `parallel(8)`"),
         }
-
-        Self(rooted, nested)
     }
 }
 
-impl Function {
-    fn to_tokens(&self, args: &IncludeFnArgs) -> TokenStream2 {
-        let mut out = TokenStream2::new();
+/// Major version of surrealdb the generated code targets.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+enum SurrealVersion {
+    #[default]
+    V1,
+    /// Results are taken as the `surrealdb::Value` of the client API instead of `surrealdb::sql::Value`.
+    V2,
+}
 
-        for item in &self.0 {
-            out.extend(item.to_tokens(args));
+impl Parse for SurrealVersion {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        let version: syn::LitInt = input.parse()?;
+        match version.base10_digits() {
+            "1" => Ok(Self::V1),
+            "2" => Ok(Self::V2),
+            _ => abort!(version, "unsupported surrealdb version"; help = "expected `1` or `2`"),
         }
+    }
+}
 
-        for (name, item) in &self.1 {
-            let name = Ident::new(name, Span::call_site());
-            let item = item.to_tokens(args);
-            out.extend(quote! {
-                pub mod #name {
-                    #item
-                }
-            });
+impl SurrealVersion {
+    /// Type results are taken as when no better type is known.
+    fn value_tokens(self) -> TokenStream2 {
+        let krate = krate();
+        match self {
+            Self::V1 => quote! { #krate::sql::Value },
+            Self::V2 => quote! { #krate::Value },
         }
-
-        out
     }
-}
 
-impl Kind {
-    fn to_tokens(&self) -> TokenStream2 {
-        // TODO: These are best guess only, still need to test them
+    /// Converts `value`, taken as `value_tokens()`, into a `surrealdb::sql::Value`.
+    fn into_sql(self, value: TokenStream2) -> TokenStream2 {
+        let krate = krate();
         match self {
-            Kind::Bool => quote! { impl Into < bool > },
-            Kind::Bytes => quote! { impl Into < ::surrealdb::sql::Bytes > },
-            Kind::Datetime => quote! { impl Into < ::surrealdb::sql::Datetime > },
-            Kind::Duration => quote! { impl Into < ::surrealdb::sql::Duration > },
-            Kind::Float | Kind::Int | Kind::Decimal | Kind::Number => {
-                quote! { impl Into < ::surrealdb::sql::Number > }
-            }
-            Kind::String => quote! { impl Into< ::surrealdb::sql::Strand > },
-            Kind::Uuid => quote! { impl Into < ::surrealdb::sql::Uuid > },
-            Kind::Record(_) => quote! { impl Into < ::surrealdb::sql::Thing > },
-            Kind::Point | Kind::Geometry(_) => quote! { impl Into < ::surrealdb::sql::Geometry > },
-            Kind::Option(nested) => {
-                let nested = nested.to_tokens();
-                quote! { Option < #nested > }
-            }
-            Kind::Any | Kind::Either(_) => {
-                // TODO: Either probably needs to be resolved better than throwing it all into Value
-                quote! { impl Into < ::surrealdb::sql::Value > }
-            }
-            Kind::Object => {
-                quote! { impl Into < ::surrealdb::sql::Object >  }
-            }
-            Kind::Set(_, _) | Kind::Array(_, _) => {
-                quote! { impl Into < ::surrealdb::sql::Array >  }
-            }
+            Self::V1 => value,
+            Self::V2 => quote! { #krate::Value::into_inner(#value) },
         }
     }
 }
 
-impl DefineFunctionStatement {
-    fn params_to_args(&self) -> TokenStream2 {
-        let mut out = TokenStream2::new();
+/// How the ```` ```rust ```` blocks of the comments are run by `cargo test --doc`.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+enum Examples {
+    #[default]
+    Run,
+    /// Only compiled, like examples needing a database.
+    NoRun,
+    Ignore,
+}
 
-        for (name, kind) in &self.args {
-            let name = Ident::new(name, Span::call_site());
-            let kind = kind.to_tokens();
-            out.extend(quote! { #name: #kind, });
+impl Parse for Examples {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        let ident: Ident = input.parse()?;
+        match ident.to_string().as_str() {
+            "run" => Ok(Self::Run),
+            "no_run" => Ok(Self::NoRun),
+            "ignore" => Ok(Self::Ignore),
+            _ => abort!(ident, "invalid examples"; help = "expected `run`, `no_run` or `ignore`"),
         }
-
-        out
     }
+}
 
-    fn params_to_bindings(&self) -> TokenStream2 {
-        let mut out = TokenStream2::new();
+/// Web framework the handlers of `http as <framework>` are generated for.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Http {
+    Axum,
+}
 
-        for (name, _) in &self.args {
-            let key = name.to_string();
-            let value = Ident::new(name, Span::call_site());
-            out.extend(quote! {
-                .bind((#key, #value.into()))
-            });
+impl Parse for Http {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        let ident: Ident = input.parse()?;
+        match ident.to_string().as_str() {
+            "axum" => Ok(Self::Axum),
+            _ => abort!(ident, "unsupported web framework"; help = "expected `axum`"),
         }
-
-        out
     }
+}
 
-    fn params_to_variables(&self) -> TokenStream2 {
-        // Build a Option<BTreeMap<String, Value>> for the variables
-        let mut out = quote! {
-            let mut variables: std::collections::BTreeMap<String, ::surrealdb::sql::Value> = ::std::collections::BTreeMap::new();
-        };
-        for (name, _) in &self.args {
-            let key = name.to_string();
-            let value = Ident::new(name, Span::call_site());
-            out.extend(quote! {
-                variables.insert(#key.to_string(), ::surrealdb::sql::Value::from(#value.into()));
-            });
+/// What the blocking functions wait for their query with.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+enum BlockingRuntime {
+    /// `futures::executor::block_on`, the invoking crate needs `futures` as a dependency.
+    #[default]
+    Futures,
+    /// A `tokio::runtime::Handle` passed as the first argument.
+    Tokio,
+}
+
+impl BlockingRuntime {
+    /// Extra leading parameters of the blocking functions.
+    fn params(self) -> TokenStream2 {
+        match self {
+            Self::Futures => TokenStream2::new(),
+            Self::Tokio => quote! { rt: &::tokio::runtime::Handle, },
         }
+    }
 
-        out
+    /// Waits for `future`, anything implementing `IntoFuture`, on the runtime.
+    fn block_on(self, future: TokenStream2) -> TokenStream2 {
+        let future = quote! { ::std::future::IntoFuture::into_future(#future) };
+        match self {
+            Self::Futures => quote! { ::futures::executor::block_on(#future) },
+            Self::Tokio => quote! { rt.block_on(#future) },
+        }
     }
+}
 
-    fn custom_function_query(&self) -> String {
-        let mut out = String::new();
-        out.push_str("RETURN fn");
-        for name in &self.name {
-            out.push_str("::");
-            out.push_str(name);
+/// Retries of the driver functions on network errors, parsed from `retry(times = 3, backoff = "100ms")`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Retry {
+    /// Retries after the first attempt.
+    times: u32,
+    /// Wait before the first retry, doubled for every one after it.
+    backoff: Duration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self {
+            times: 3,
+            backoff: Duration::from_millis(100),
         }
+    }
+}
 
-        out.push('(');
-        for (i, (name, _)) in self.args.iter().enumerate() {
-            let name = name.to_string();
-            out.push('$');
-            out.push_str(&name);
-            if i < self.args.len() - 1 {
-                out.push_str(", ");
+impl Parse for Retry {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        let mut retry = Self::default();
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            input.parse::<syn::Token![=]>()?;
+            match key.to_string().as_str() {
+                "times" => retry.times = input.parse::<syn::LitInt>()?.base10_parse()?,
+                "backoff" => retry.backoff = parse_duration(&input.parse()?)?,
+                _ => abort!(key, "unknown retry option"; help = "expected `times` or `backoff`"),
+            }
+            if !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
             }
         }
-        out.push(')');
+        Ok(retry)
+    }
+}
 
-        out
+/// Parses a duration like `"100ms"`, `"5s"` or `"1m30s"`.
+fn parse_duration(lit: &syn::LitStr) -> syn::Result<Duration> {
+    let text = lit.value();
+    let mut rest = text.trim();
+    let mut total = Duration::ZERO;
+    let invalid = || syn::Error::new(lit.span(), "invalid duration, expected something like \"100ms\", \"5s\" or \"1m30s\"");
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+        let Ok(amount) = rest[..digits].parse::<u64>() else {
+            return Err(invalid());
+        };
+        rest = &rest[digits..];
+        let unit = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        total += match &rest[..unit] {
+            "ns" => Duration::from_nanos(amount),
+            "us" | "µs" => Duration::from_micros(amount),
+            "ms" => Duration::from_millis(amount),
+            "s" => Duration::from_secs(amount),
+            "m" => Duration::from_secs(amount * 60),
+            "h" => Duration::from_secs(amount * 60 * 60),
+            _ => return Err(invalid()),
+        };
+        rest = &rest[unit..];
     }
+    Ok(total)
+}
 
-    fn to_tokens(&self, args: &IncludeFnArgs) -> TokenStream2 {
-        let (driver, datastore) = args.transform_fn_name(self.name.last().unwrap());
-        let args = self.params_to_args();
-        let query = self.custom_function_query();
-        // turn comments into rust comments
-        let comments = self
-            .comments
-            .iter()
-            .map(|s| {
-                quote! {
-                    #[doc = #s]
-                }
-            })
-            .collect::<TokenStream2>();
+/// Connection the driver functions are generated for.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+enum Engine {
+    /// Generic over `C: Connection`.
+    #[default]
+    Generic,
+    /// Only `Surreal<Any>`, so the functions aren't generic.
+    Any,
+}
+
+impl Parse for Engine {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        let ident: Ident = input.parse()?;
+        match ident.to_string().as_str() {
+            "generic" => Ok(Self::Generic),
+            "any" => Ok(Self::Any),
+            _ => abort!(ident, "invalid engine"; help = "expected `any` or `generic`"),
+        }
+    }
+}
+
+impl Engine {
+    /// Generic parameters of the functions taking a connection.
+    fn generics(self) -> TokenStream2 {
+        match self.param() {
+            Some(param) => quote! { <#param> },
+            None => TokenStream2::new(),
+        }
+    }
+
+    /// The generic parameter of `generics()`, for merging it with others.
+    fn param(self) -> Option<TokenStream2> {
+        let krate = krate();
+        match self {
+            Self::Generic => Some(quote! { C: #krate::Connection }),
+            Self::Any => None,
+        }
+    }
+
+    /// The connection these functions take, as in `Surreal<#connection>`.
+    fn connection(self) -> TokenStream2 {
+        let krate = krate();
+        match self {
+            Self::Generic => quote! { C },
+            Self::Any => quote! { #krate::engine::any::Any },
+        }
+    }
+}
+
+#[derive(Debug)]
+struct IncludeFnArgs {
+    /// Files in the order they were included, without duplicates.
+    includes: Vec<Origin>,
+    driver: Option<Alias>,
+    datastore: Option<Alias>,
+    /// Feature of the invoking crate the driver functions are only generated with.
+    driver_cfg: Option<String>,
+    /// Feature of the invoking crate the datastore functions are only generated with.
+    datastore_cfg: Option<String>,
+    /// Datastore functions return every response instead of the result of the call.
+    datastore_raw: bool,
+    /// Synchronous functions waiting for the driver query.
+    blocking: Option<Alias>,
+    blocking_runtime: BlockingRuntime,
+    /// Feature of the invoking crate the blocking functions are only generated with.
+    blocking_cfg: Option<String>,
+    trait_name: Option<Ident>,
+    dispatch: bool,
+    /// Also generate `QueryFragment`s to chain calls onto other queries.
+    fragments: bool,
+    /// Also generate `batch(db)`, implies `fragments`.
+    batch: bool,
+    /// Also generate `_paged` functions for the functions returning an array.
+    paged: bool,
+    /// Also generate `_with_stats` functions.
+    stats: bool,
+    /// Also generate `_in` functions, taking a `CallTarget`.
+    targets: bool,
+    /// Also generate a `tower::Service`, implies `dispatch`.
+    tower: bool,
+    /// Also generate HTTP handlers and a router serving them.
+    http: Option<Http>,
+    /// Also implement the tonic service generated in this module from the .proto of the functions.
+    grpc: Option<TokenStream2>,
+    /// Also generate an async-graphql object resolving the functions.
+    graphql: bool,
+    /// Retry the driver functions on network errors.
+    retry: Option<Retry>,
+    /// Fail the driver functions taking longer.
+    timeout: Option<Duration>,
+    /// How `define_functions` sends the statements.
+    define: Define,
+    /// Generate a test for every function in `#[cfg(test)] mod generated_tests`.
+    tests: bool,
+    /// Report the function calls to a `FunctionCallObserver`.
+    metrics: bool,
+    /// Wrap the function calls in `tracing` spans.
+    instrument: bool,
+    /// Take parameters of a single geometry type as the matching `geo` type.
+    geo: bool,
+    /// Take `uuid` parameters as `uuid::Uuid`.
+    uuid: bool,
+    /// Rust types mapped to kinds or parameters with `map`.
+    types: TypeMap,
+    /// Also take `chrono::DateTime`s on `datetime` parameters.
+    chrono: bool,
+    /// Also take `time::OffsetDateTime`s on `datetime` parameters.
+    time: bool,
+    /// Take parameters of scalar kinds as the generated `IntoString` and co. instead of `impl Into<T>`.
+    checked_args: bool,
+    /// Generate code for `wasm32-unknown-unknown`, where futures of the client aren't `Send`.
+    wasm: bool,
+    /// Also generate `plan_migration(db)`.
+    migrations: bool,
+    /// Also generate `verify_functions(db)`.
+    verify: bool,
+    /// Also generate `sync_functions(db)`.
+    sync: bool,
+    /// Also generate `FUNCTIONS: &[FunctionMeta]`.
+    metadata: bool,
+    /// Also generate `functions_schema()`.
+    export_schema: bool,
+    validate: bool,
+    duplicates: Duplicates,
+    /// Skip the statements that fail to parse, with a warning.
+    continue_on_error: bool,
+    modules: Modules,
+    bindings: Bindings,
+    docs: Docs,
+    /// How the ```` ```rust ```` blocks of the comments are run as doctests.
+    examples: Examples,
+    /// Name of the function returning the stored text, `stored_functions` by default.
+    stored: Option<Ident>,
+    /// Module every generated item is wrapped in.
+    module: Option<Ident>,
+    /// Segment prefixed to the name of every function in SurrealQL, like `v2` for `fn::v2::greet`.
+    name_version: Option<String>,
+    /// Snapshot of the generated API to compare against.
+    changelog: Option<PathBuf>,
+    version: SurrealVersion,
+    engine: Engine,
+    /// Path of the surrealdb crate, `::surrealdb` by default.
+    crate_path: Option<TokenStream2>,
+    /// Every option given, in order, for `SurqlFunctions` to reject the ones it doesn't support.
+    options: Vec<Ident>,
+}
+
+impl IncludeFnArgs {
+    fn stored_functions_name(&self) -> Ident {
+        self.stored
+            .clone()
+            .unwrap_or_else(|| Ident::new("stored_functions", Span::call_site()))
+    }
+
+    /// Names of the functions listing the stored functions and returning a single one,
+    /// `stored_function_names` and `stored_function` by default.
+    fn stored_function_names(&self) -> (Ident, Ident) {
+        let stored = self.stored_functions_name().to_string();
+        let single = match stored.strip_suffix('s') {
+            Some(single) => single.to_string(),
+            None => format!("{stored}_function"),
+        };
+        (format_ident!("{}_names", single), format_ident!("{}", single))
+    }
+
+    /// Name of the enum with a variant for each stored function, `FunctionId` by default.
+    fn function_id_name(&self) -> Ident {
+        match self.stored {
+            Some(_) => format_ident!("{}Id", literal::camel_case(&self.stored_function_names().1.to_string())),
+            None => format_ident!("FunctionId"),
+        }
+    }
+
+    /// Name of the type reporting which functions `define_functions_report` defined.
+    fn define_report_name(&self) -> Ident {
+        match self.stored {
+            Some(_) => format_ident!("{}DefineReport", literal::camel_case(&self.stored_function_names().1.to_string())),
+            None => format_ident!("DefineReport"),
+        }
+    }
+
+    /// Name of the hash const, and of the database param `store_functions_hash` stores it in.
+    fn hash_names(&self) -> (Ident, String) {
+        let name = match &self.stored {
+            Some(stored) => format!("{stored}_hash"),
+            None => "functions_hash".to_string(),
+        };
+        let mut param = name.clone();
+        // Versions are defined side by side, each with its own hash
+        if let Some(version) = &self.name_version {
+            param = format!("{version}_{param}");
+        }
+        if let Some(module) = &self.module {
+            param = format!("{module}_{param}");
+        }
+        (Ident::new(&name.to_uppercase(), Span::call_site()), param)
+    }
+
+    /// Awaits `future`, failing with `timed_out` once the `timeout` elapses.
+    fn awaited(&self, future: TokenStream2, timed_out: TokenStream2) -> TokenStream2 {
+        let Some(timeout) = self.timeout else {
+            return quote! { #future.await };
+        };
+        let timeout = timeout.as_nanos() as u64;
+        quote! {
+            match ::tokio::time::timeout(::std::time::Duration::from_nanos(#timeout), ::std::future::IntoFuture::into_future(#future)).await {
+                ::std::result::Result::Ok(result) => result,
+                ::std::result::Result::Err(_) => ::std::result::Result::Err(#timed_out),
+            }
+        }
+    }
+
+    /// Whether `datetime` parameters take `impl IntoDatetime`.
+    fn datetimes(&self) -> bool {
+        self.chrono || self.time
+    }
+
+    /// Name of the blocking function for `name`, if they are generated.
+    fn blocking_fn_name(&self, name: &str) -> Option<Ident> {
+        self.blocking
+            .as_ref()
+            .map(|alias| rust_ident(&alias.transform(name)))
+    }
+
+    fn transform_fn_name(&self, name: &str) -> (Option<Ident>, Option<Ident>) {
+        (
+            self.driver
+                .as_ref()
+                .map(|alias| rust_ident(&alias.transform(name))),
+            self.datastore
+                .as_ref()
+                .map(|alias| rust_ident(&alias.transform(name))),
+        )
+    }
+}
+
+impl Parse for IncludeFnArgs {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        let mut includes: Vec<Origin> = vec![];
+        let mut driver = None;
+        let mut datastore = None;
+        let mut datastore_raw = false;
+        let mut driver_cfg = None;
+        let mut datastore_cfg = None;
+        let mut blocking = None;
+        let mut blocking_runtime = BlockingRuntime::default();
+        let mut blocking_cfg = None;
+        let mut trait_name = None;
+        let mut dispatch = false;
+        let mut fragments = false;
+        let mut paged = false;
+        let mut stats = false;
+        let mut targets = false;
+        let mut batch = false;
+        let mut tower = false;
+        let mut http = None;
+        let mut grpc = None;
+        let mut graphql = false;
+        let mut wasm = false;
+        let mut instrument = false;
+        let mut metrics = false;
+        let mut tests = false;
+        let mut geo = false;
+        let mut uuid = false;
+        let mut types = TypeMap::default();
+        let mut chrono = false;
+        let mut checked_args = false;
+        let mut time = false;
+        let mut retry = None;
+        let mut define = Define::default();
+        let mut timeout = None;
+        let mut migrations = false;
+        let mut verify = false;
+        let mut sync = false;
+        let mut metadata = false;
+        let mut export_schema = false;
+        let mut validate = false;
+        let mut duplicates = Duplicates::default();
+        let mut continue_on_error = false;
+        let mut changelog = None;
+        let mut modules = Modules::default();
+        let mut bindings = Bindings::default();
+        let mut docs = Docs::default();
+        let mut examples = Examples::default();
+        let mut name_version = None;
+        let mut version = SurrealVersion::default();
+        let mut crate_path = None;
+        let mut engine = Engine::default();
+        let mut roots: Vec<PathBuf> = vec![];
+        let mut module = None;
+        let mut stored = None;
+        let mut options = vec![];
+
+        while !input.is_empty() {
+            // `trait` is a keyword, so peek for any ident
+            if input.peek(Ident::peek_any) {
+                let ident = Ident::parse_any(input)?;
+                options.push(ident.clone());
+                match ident.to_string().as_str() {
+                    "driver" => {
+                        input.parse::<syn::Token![as]>()?;
+                        driver = Some(Alias::parse(input)?);
+                        if driver.eq(&datastore) {
+                            abort!(ident, "driver and datastore cannot be the same")
+                        }
+                        driver_cfg = parse_cfg(input)?;
+                    }
+                    "datastore" => {
+                        input.parse::<syn::Token![as]>()?;
+                        datastore = Some(Alias::parse(input)?);
+                        if driver.eq(&datastore) {
+                            abort!(ident, "driver and datastore cannot be the same")
+                        }
+                        if input.peek(syn::Ident) {
+                            let mode: Ident = input.parse()?;
+                            if mode != "raw" {
+                                abort!(mode, "unknown datastore mode"; help = "expected `datastore as <alias> raw`")
+                            }
+                            datastore_raw = true;
+                        }
+                        datastore_cfg = parse_cfg(input)?;
+                    }
+                    "blocking" => {
+                        input.parse::<syn::Token![as]>()?;
+                        blocking = Some(Alias::parse(input)?);
+                        if input.peek(syn::Ident) {
+                            let runtime: Ident = input.parse()?;
+                            if runtime != "tokio" {
+                                abort!(runtime, "unknown blocking runtime"; help = "expected `blocking as <alias> tokio`, or nothing for `futures::executor::block_on`")
+                            }
+                            blocking_runtime = BlockingRuntime::Tokio;
+                        }
+                        blocking_cfg = parse_cfg(input)?;
+                    }
+                    "trait" => {
+                        input.parse::<syn::Token![as]>()?;
+                        trait_name = Some(input.parse::<Ident>()?);
+                    }
+                    "duplicates" => {
+                        input.parse::<syn::Token![as]>()?;
+                        duplicates = input.parse()?;
+                    }
+                    "roots" => {
+                        let content;
+                        syn::bracketed!(content in input);
+                        while !content.is_empty() {
+                            // Either `$VARIABLE` or a string literal, which may contain variables as well
+                            let (raw, span) = if content.peek(syn::Token![$]) {
+                                let dollar: syn::Token![$] = content.parse()?;
+                                let variable: Ident = content.parse()?;
+                                (format!("${variable}"), dollar.span)
+                            } else {
+                                let lit: syn::LitStr = content.parse()?;
+                                (lit.value(), lit.span())
+                            };
+                            match file::resolve_path(&raw, file::get_env) {
+                                Ok(root) => roots.push(root),
+                                Err(e) => {
+                                    abort!(span, format!("failed to resolve root: {e}"); note="make sure the variable is set")
+                                }
+                            }
+                            if content.is_empty() {
+                                break;
+                            }
+                            content.parse::<syn::Token![,]>()?;
+                        }
+                    }
+                    "stored" => {
+                        input.parse::<syn::Token![as]>()?;
+                        stored = Some(input.parse::<Ident>()?);
+                    }
+                    "mod" => {
+                        input.parse::<syn::Token![as]>()?;
+                        module = Some(input.parse::<Ident>()?);
+                    }
+                    "version" => {
+                        input.parse::<syn::Token![as]>()?;
+                        let segment = input.parse::<Ident>()?;
+                        if !segment.to_string().chars().all(parser::common::val_char) {
+                            abort!(segment, "invalid version"; help = "expected a segment of a function name, like `version as v2;`")
+                        }
+                        name_version = Some(segment.to_string());
+                    }
+                    "modules" => {
+                        input.parse::<syn::Token![as]>()?;
+                        modules = input.parse()?;
+                    }
+                    "bindings" => {
+                        input.parse::<syn::Token![as]>()?;
+                        bindings = input.parse()?;
+                    }
+                    "docs" => {
+                        input.parse::<syn::Token![as]>()?;
+                        docs = input.parse()?;
+                    }
+                    "examples" => {
+                        input.parse::<syn::Token![as]>()?;
+                        examples = input.parse()?;
+                    }
+                    "surreal_version" => {
+                        version = input.parse()?;
+                    }
+                    "engine" => {
+                        input.parse::<syn::Token![as]>()?;
+                        engine = input.parse()?;
+                    }
+                    "crate" => {
+                        input.parse::<syn::Token![as]>()?;
+                        crate_path = Some(input.parse::<syn::Path>()?.into_token_stream());
+                    }
+                    "override_duplicates" => {
+                        duplicates = Duplicates::Override;
+                    }
+                    "continue_on_error" => {
+                        continue_on_error = true;
+                    }
+                    "url" | "remote" => {
+                        let url: syn::LitStr = input.parse()?;
+                        if !url.value().starts_with("https://") {
+                            abort!(url, "remote files are only fetched over HTTPS"; help = "use an `https://` URL")
+                        }
+                        let checksum: Ident = input.parse()?;
+                        if checksum != "sha256" {
+                            abort!(checksum, "expected `sha256`"; help = "pin the contents with `{} \"...\" sha256 \"<hex>\"`", ident)
+                        }
+                        let sha256: syn::LitStr = input.parse()?;
+                        if sha256.value().len() != 64 || !sha256.value().chars().all(|c| c.is_ascii_hexdigit()) {
+                            abort!(sha256, "invalid checksum"; help = "expected the SHA-256 of the contents as 64 hex digits")
+                        }
+                        if cfg!(not(feature = "remote")) {
+                            abort!(ident, "`{}` requires the `remote` feature", ident; help = "enable the `remote` feature of surrealdb_functions")
+                        }
+                        let origin = Origin::Url {
+                            url: url.value(),
+                            sha256: sha256.value(),
+                        };
+                        if !includes.contains(&origin) {
+                            includes.push(origin);
+                        }
+                    }
+                    "database" => {
+                        if cfg!(not(feature = "database")) {
+                            abort!(ident, "`database` requires the `database` feature"; help = "enable the `database` feature of surrealdb_functions")
+                        }
+                        // Opt-in, so no build reads a database it wasn't pointed at
+                        let Some(endpoint) = file::get_env("SURREALDB_FUNCTIONS_ENDPOINT") else {
+                            abort!(ident, "`database` reads the functions of the database at $SURREALDB_FUNCTIONS_ENDPOINT, which isn't set"; help = "set it to the HTTP endpoint of a development database, like `http://localhost:8000`")
+                        };
+                        let origin = Origin::Database { endpoint };
+                        if !includes.contains(&origin) {
+                            includes.push(origin);
+                        }
+                    }
+                    "inline" => {
+                        let sql: syn::LitStr = input.parse()?;
+                        let index = includes.iter().filter(|origin| matches!(origin, Origin::Inline { .. })).count() + 1;
+                        includes.push(Origin::Inline { index, sql: sql.value() });
+                    }
+                    "dispatch" => {
+                        dispatch = true;
+                    }
+                    "fragments" => {
+                        fragments = true;
+                    }
+                    "batch" => {
+                        fragments = true;
+                        batch = true;
+                    }
+                    "paged" => {
+                        paged = true;
+                    }
+                    "stats" => {
+                        stats = true;
+                    }
+                    "targets" => {
+                        targets = true;
+                    }
+                    "tower" => {
+                        dispatch = true;
+                        tower = true;
+                    }
+                    "http" => {
+                        input.parse::<syn::Token![as]>()?;
+                        http = Some(input.parse()?);
+                    }
+                    "graphql" => {
+                        graphql = true;
+                    }
+                    "grpc" => {
+                        input.parse::<syn::Token![as]>()?;
+                        grpc = Some(input.parse::<syn::Path>()?.into_token_stream());
+                    }
+                    "retry" => {
+                        retry = Some(if input.peek(syn::token::Paren) {
+                            let content;
+                            syn::parenthesized!(content in input);
+                            content.parse()?
+                        } else {
+                            Retry::default()
+                        });
+                    }
+                    "timeout" => {
+                        timeout = Some(parse_duration(&input.parse()?)?);
+                    }
+                    "define" => {
+                        input.parse::<syn::Token![as]>()?;
+                        define = input.parse()?;
+                    }
+                    "tests" => {
+                        tests = true;
+                    }
+                    "metrics" => {
+                        metrics = true;
+                    }
+                    "instrument" => {
+                        instrument = true;
+                    }
+                    "geo" => {
+                        geo = true;
+                    }
+                    "map" => {
+                        types.push(input.parse()?);
+                    }
+                    "uuid" => {
+                        uuid = true;
+                    }
+                    "chrono" => {
+                        chrono = true;
+                    }
+                    "checked_args" => {
+                        checked_args = true;
+                    }
+                    "time" => {
+                        time = true;
+                    }
+                    "wasm" => {
+                        wasm = true;
+                    }
+                    "migrations" => {
+                        migrations = true;
+                    }
+                    "verify" => {
+                        verify = true;
+                    }
+                    "sync" => {
+                        sync = true;
+                    }
+                    "metadata" => {
+                        metadata = true;
+                    }
+                    "export_schema" => {
+                        export_schema = true;
+                    }
+                    "changelog" => {
+                        let lit: syn::LitStr = input.parse()?;
+                        match file::resolve_path(&lit.value(), file::get_env) {
+                            Ok(path) => changelog = Some(path),
+                            Err(e) => {
+                                abort!(lit, format!("failed to resolve path: {e}"); note="make sure the path is valid")
+                            }
+                        }
+                    }
+                    "if" => {
+                        for lit in parse_env_paths(input)? {
+                            include_path(&lit, &roots, &mut includes);
+                        }
+                    }
+                    "validate" => {
+                        if cfg!(not(feature = "validate")) {
+                            abort!(ident, "`validate` requires the `validate` feature"; help = "enable the `validate` feature of surrealdb_functions")
+                        }
+                        validate = true;
+                    }
+                    _ => {
+                        abort!(ident, "unknown argument"; help="only driver, datastore, blocking, engine, crate, bindings, map, trait, dispatch, fragments, batch, paged, stats, targets, tower, http, grpc, graphql, retry, timeout, define, instrument, metrics, tests, geo, uuid, chrono, time, checked_args, wasm, migrations, verify, sync, metadata, export_schema, duplicates, override_duplicates, continue_on_error, mod, modules, docs, examples, roots, stored, version, surreal_version, url, remote, inline, database, validate and changelog are supported")
+                    }
+                }
+            } else {
+                let lit: Literal = input.parse()?;
+                include_path(&lit, &roots, &mut includes);
+            }
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<syn::Token![;]>()?;
+        }
+
+        if datastore.is_none() && driver.is_none() && blocking.is_none() {
+            panic!("no driver, datastore or blocking provided");
+        }
+
+        if blocking.is_some() && (blocking == driver || blocking == datastore) {
+            abort_call_site!("blocking cannot be the same as driver or datastore"; help = "use another alias, like `blocking as sync_$`")
+        }
+
+        if retry.is_some() && driver.is_none() {
+            abort_call_site!("retry requires driver functions"; help = "add `driver as <alias>`")
+        }
+
+        if retry.is_some() && wasm {
+            abort_call_site!("`retry` is not available with `wasm`"; help = "waiting between attempts needs `tokio::time::sleep`")
+        }
+
+        if timeout.is_some() && driver.is_none() {
+            abort_call_site!("timeout requires driver functions"; help = "add `driver as <alias>`, datastores have `Datastore::with_query_timeout`")
+        }
+
+        if timeout.is_some() && wasm {
+            abort_call_site!("`timeout` is not available with `wasm`"; help = "timing out needs `tokio::time::timeout`")
+        }
+
+        if tests && driver.is_none() {
+            abort_call_site!("tests require driver functions"; help = "add `driver as <alias>`")
+        }
+
+        if metrics && wasm {
+            abort_call_site!("`metrics` is not available with `wasm`"; help = "timing the calls needs `std::time::Instant`, which panics on `wasm32-unknown-unknown`")
+        }
+
+        if blocking.is_some() && wasm {
+            abort_call_site!("blocking functions are not available with `wasm`"; help = "the browser can't block on a future, use the driver functions")
+        }
+
+        if bindings == Bindings::Serde && (geo || uuid || chrono || time || checked_args || !types.is_empty()) {
+            abort_call_site!("`bindings as serde` takes any `Serialize`"; help = "remove `geo`, `uuid`, `chrono`, `time`, `checked_args` and `map`, they only change the types of `bindings as typed`")
+        }
+
+        if includes.is_empty() {
+            panic!("no paths provided");
+        }
+
+        if let (Some(name), None) = (&trait_name, &driver) {
+            abort!(name, "trait requires driver functions"; help = "add `driver as <alias>`")
+        }
+
+        if dispatch && driver.is_none() {
+            abort_call_site!("{} requires driver functions", if tower { "tower" } else { "dispatch" }; help = "add `driver as <alias>`")
+        }
+
+        if http.is_some() && driver.is_none() {
+            abort_call_site!("http requires driver functions"; help = "add `driver as <alias>`")
+        }
+
+        if grpc.is_some() && driver.is_none() {
+            abort_call_site!("grpc requires driver functions"; help = "add `driver as <alias>`")
+        }
+
+        if graphql && driver.is_none() {
+            abort_call_site!("graphql requires driver functions"; help = "add `driver as <alias>`")
+        }
+
+        if migrations && driver.is_none() {
+            abort_call_site!("migrations require driver functions"; help = "add `driver as <alias>`")
+        }
+
+        if verify && driver.is_none() {
+            abort_call_site!("verify requires driver functions"; help = "add `driver as <alias>`")
+        }
+
+        if sync && driver.is_none() {
+            abort_call_site!("sync requires driver functions"; help = "add `driver as <alias>`")
+        }
+
+        if engine == Engine::Any && driver.is_none() {
+            abort_call_site!("`engine as any` requires driver functions"; help = "add `driver as <alias>`")
+        }
+
+        if fragments && driver.is_none() {
+            abort_call_site!("{} require driver functions", if batch { "batches" } else { "fragments" }; help = "add `driver as <alias>`")
+        }
+        if paged && driver.is_none() {
+            abort_call_site!("paged functions require driver functions"; help = "add `driver as <alias>`")
+        }
+        if stats && driver.is_none() {
+            abort_call_site!("stats require driver functions"; help = "add `driver as <alias>`")
+        }
+        if targets && driver.is_none() {
+            abort_call_site!("targets require driver functions"; help = "add `driver as <alias>`")
+        }
+
+        Ok(Self {
+            includes,
+            driver,
+            datastore,
+            driver_cfg,
+            datastore_cfg,
+            datastore_raw,
+            blocking,
+            blocking_runtime,
+            blocking_cfg,
+            trait_name,
+            dispatch,
+            fragments,
+            batch,
+            paged,
+            stats,
+            targets,
+            tower,
+            http,
+            grpc,
+            graphql,
+            geo,
+            uuid,
+            types,
+            chrono,
+            checked_args,
+            time,
+            wasm,
+            instrument,
+            metrics,
+            tests,
+            retry,
+            define,
+            timeout,
+            migrations,
+            verify,
+            sync,
+            metadata,
+            export_schema,
+            validate,
+            duplicates,
+            continue_on_error,
+            modules,
+            bindings,
+            docs,
+            examples,
+            name_version,
+            stored,
+            module,
+            changelog,
+            version,
+            crate_path,
+            engine,
+            options,
+        })
+    }
+}
+
+/// The optional `if "feature"` after a driver or datastore alias.
+fn parse_cfg(input: syn::parse::ParseStream<'_>) -> syn::Result<Option<String>> {
+    if !input.peek(syn::Token![if]) {
+        return Ok(None);
+    }
+    input.parse::<syn::Token![if]>()?;
+    Ok(Some(input.parse::<syn::LitStr>()?.value()))
+}
+
+/// The paths of the branch an `if env "APP_ENV" == "prod" { "a.surql" } else { "b.surql" }` section selects, after the `if`.
+///
+/// Branches can be chained with `else if env ...`, the variable compares unequal to any value when it isn't set.
+fn parse_env_paths(input: syn::parse::ParseStream<'_>) -> syn::Result<Vec<Literal>> {
+    let env: Ident = input.parse()?;
+    if env != "env" {
+        abort!(env, "unknown condition"; help = "expected `if env \"VARIABLE\" == \"value\" { \"path\" }`")
+    }
+    let variable: syn::LitStr = input.parse()?;
+    let equal = if input.peek(syn::Token![!=]) {
+        input.parse::<syn::Token![!=]>()?;
+        false
+    } else {
+        input.parse::<syn::Token![==]>()?;
+        true
+    };
+    let value: syn::LitStr = input.parse()?;
+    let selected = (file::get_env(&variable.value()) == Some(value.value())) == equal;
+
+    let content;
+    syn::braced!(content in input);
+    let then = syn::punctuated::Punctuated::<Literal, syn::Token![;]>::parse_terminated(&content)?;
+    let otherwise = if input.peek(syn::Token![else]) {
+        input.parse::<syn::Token![else]>()?;
+        if input.peek(syn::Token![if]) {
+            input.parse::<syn::Token![if]>()?;
+            parse_env_paths(input)?
+        } else {
+            let content;
+            syn::braced!(content in input);
+            syn::punctuated::Punctuated::<Literal, syn::Token![;]>::parse_terminated(&content)?.into_iter().collect()
+        }
+    } else {
+        vec![]
+    };
+    Ok(if selected { then.into_iter().collect() } else { otherwise })
+}
+
+/// Adds the file or the .surql files of the directory at the path `lit`, resolving its variables and roots.
+fn include_path(lit: &Literal, roots: &[PathBuf], includes: &mut Vec<Origin>) {
+    let resolved = file::resolve_path(lit.to_string().trim_matches('"'), file::get_env)
+        .and_then(|path| file::resolve_in_roots(path, roots));
+    match resolved {
+        Ok(path) => {
+            if path.exists() {
+                for path in expand_path(&path).unwrap() {
+                    let origin = Origin::File(path);
+                    if !includes.contains(&origin) {
+                        includes.push(origin);
+                    }
+                }
+            } else {
+                abort!(lit, "file does not exist"; note="make sure the file exists");
+            }
+        }
+        Err(e) => {
+            abort!(lit, format!("failed to resolve path: {e}"); note="make sure the path is valid")
+        }
+    }
+}
+
+/// Functions of a module, its nested modules and the documentation of the module from `@module` directives.
+#[derive(Debug, Default)]
+struct Function(Vec<DefineFunctionStatement>, BTreeMap<String, Function>, Vec<String>);
+
+impl From<Vec<DefineFunctionStatement>> for Function {
+    fn from(v: Vec<DefineFunctionStatement>) -> Self {
+        let mut rooted = vec![];
+        let mut nested = BTreeMap::new();
+
+        for item in v {
+            let name = item.rust_path();
+            if name.len() == 1 {
+                // This function doesn't have a parent, so it's treated as a root function
+                rooted.push(item);
+            } else {
+                // This function has more than one part for it's name so each part is treated as a module
+                let mut current = &mut nested;
+                let mut next_items: &mut Vec<DefineFunctionStatement> = &mut vec![];
+                // Iterate over each part of the name, if the part doesn't exist in the current module, create it
+                // If it's the last part of the name, add the function to the module
+                let len = name.len();
+                for (i, part) in name.iter().enumerate() {
+                    if i == len - 1 {
+                        next_items.push(item.clone());
+                    } else {
+                        let nested: &mut Function = current.entry(part.clone()).or_default();
+                        next_items = &mut nested.0;
+                        current = &mut nested.1;
+                    }
+                }
+            }
+        }
+
+        Self(rooted, nested, vec![])
+    }
+}
+
+impl Function {
+    /// Attaches every `-- @module foo::bar: description` line to the module it names, in the order they're written.
+    fn document_modules(&mut self, functions: &[DefineFunctionStatement]) {
+        for function in functions {
+            for directive in function.directives.iter().filter(|directive| directive.name == "module") {
+                let Some((path, doc)) = directive.value.split_once(": ") else {
+                    abort_call_site!(format!("invalid module documentation `{}` above `fn::{}`", directive.value, function.name.join("::")); help = "expected `-- @module foo::bar: description`")
+                };
+                let mut module = &mut *self;
+                for part in path.trim().trim_start_matches("fn::").split("::") {
+                    let Some(nested) = module.1.get_mut(part) else {
+                        abort_call_site!(format!("no function is defined in module `{}`", path.trim()); help = "`@module` documents the module of functions like `fn::foo::bar`")
+                    };
+                    module = nested;
+                }
+                module.2.push(doc.trim().to_string());
+            }
+        }
+    }
+
+    fn to_tokens(&self, args: &IncludeFnArgs) -> TokenStream2 {
+        let mut out = TokenStream2::new();
+
+        for item in &self.0 {
+            out.extend(item.to_tokens(args));
+        }
+
+        // The trait is generated next to `stored_functions()`, where the nested functions can't see it otherwise
+        let datetime = match args.datetimes() {
+            true => quote! { use super::IntoDatetime; },
+            false => TokenStream2::new(),
+        };
+        let checked = match args.checked_args {
+            true => checked::imports(),
+            false => TokenStream2::new(),
+        };
+        for (name, item) in &self.1 {
+            let name = rust_ident(name);
+            let literals = literal::imports(&item.literal_types(args));
+            let docs = &item.2;
+            let item = item.to_tokens(args);
+            out.extend(quote! {
+                #(#[doc = #docs])*
+                pub mod #name {
+                    #datetime
+                    #checked
+                    #literals
+
+                    #item
+                }
+            });
+        }
+
+        out
+    }
+}
+
+/// How parameters and results of a kind are written in the generated Rust code.
+pub(crate) trait KindTokens {
+    fn to_tokens(&self, args: &IncludeFnArgs) -> TokenStream2;
+
+    /// Kind of the items of an `array` or `set` taken item by item, when it has an element kind or a maximum length.
+    fn items(&self) -> Option<&Kind>;
+
+    /// Whether a parameter of this kind is bound with a plain `Into::into`, instead of `value_tokens()`.
+    fn bound_into(&self, args: &IncludeFnArgs) -> bool;
+
+    /// The only geometry type of `point` and `geometry<T>` kinds.
+    fn geometry_type(&self) -> Option<&str>;
+
+    /// The `geo` type taken for a single geometry type with `geo`, and the variant of `Geometry` holding it.
+    fn geo_type(&self, args: &IncludeFnArgs) -> Option<(TokenStream2, Ident)>;
+
+    /// Type of another crate taken for this kind instead of the surrealdb one, and the surrealdb type it converts into.
+    fn external_type(&self, args: &IncludeFnArgs) -> Option<(TokenStream2, TokenStream2)>;
+
+    /// The surrealdb type a type mapped to this kind converts into.
+    fn mapped_into(&self, args: &IncludeFnArgs) -> TokenStream2;
+
+    /// The first kind in this one the parser didn't know, as written.
+    fn unknown(&self) -> Option<&str>;
+
+    /// A single type accepted for this kind, for where `impl Into<_>` can't be inferred.
+    fn concrete_tokens(&self, args: &IncludeFnArgs) -> TokenStream2;
+
+    /// The surrealdb type of `concrete_tokens()`, when the kind isn't taken as the type of another crate.
+    fn surreal_tokens(&self, args: &IncludeFnArgs) -> TokenStream2;
+
+    /// Converts the parameter `value` of this kind into something bindable as a `Value`.
+    fn value_tokens(&self, value: TokenStream2, args: &IncludeFnArgs) -> TokenStream2;
+
+    /// An argument of this kind, as simple as possible, for the generated tests.
+    fn sample_tokens(&self, args: &IncludeFnArgs) -> TokenStream2;
+
+    /// Rust type a returned value of this kind deserializes into, `None` when it has to stay a `Value`.
+    fn output_tokens(&self, version: SurrealVersion) -> Option<TokenStream2>;
+}
+
+impl KindTokens for Kind {
+    fn to_tokens(&self, args: &IncludeFnArgs) -> TokenStream2 {
+        let krate = krate();
+        if let Some((external, _)) = self.external_type(args) {
+            return quote! { impl ::std::convert::Into < #external > };
+        }
+        if let Some((name, _)) = checked::checked_trait(self).filter(|_| args.checked_args) {
+            return quote! { impl #name };
+        }
+        // Every kind is round-tripped through the driver and datastore wrappers in tests/kinds.rs
+        match self {
+            Kind::Bool => quote! { impl ::std::convert::Into < bool > },
+            Kind::Bytes => quote! { impl ::std::convert::Into < #krate::sql::Bytes > },
+            Kind::Datetime if args.datetimes() => quote! { impl IntoDatetime },
+            Kind::Datetime => quote! { impl ::std::convert::Into < #krate::sql::Datetime > },
+            Kind::Duration => quote! { impl ::std::convert::Into < #krate::sql::Duration > },
+            Kind::Float | Kind::Int | Kind::Decimal | Kind::Number => {
+                quote! { impl ::std::convert::Into < #krate::sql::Number > }
+            }
+            Kind::String => quote! { impl ::std::convert::Into < #krate::sql::Strand > },
+            // Coerced into a regex by the database, like the string it's written as
+            Kind::Regex => quote! { impl ::std::convert::Into < ::std::string::String > },
+            Kind::Range => quote! { impl ::std::convert::Into < #krate::sql::Range > },
+            Kind::Uuid => quote! { impl ::std::convert::Into < #krate::sql::Uuid > },
+            Kind::Record(_) => quote! { impl ::std::convert::Into < #krate::sql::Thing > },
+            Kind::Point | Kind::Geometry(_) => quote! { impl ::std::convert::Into < #krate::sql::Geometry > },
+            Kind::Option(nested) => {
+                // A concrete type so a plain `None` works without a turbofish
+                let nested = nested.concrete_tokens(args);
+                quote! { ::std::option::Option < #nested > }
+            }
+            Kind::Any | Kind::Either(_) | Kind::Literal(_) | Kind::Function | Kind::Unknown(_) => {
+                // TODO: Either probably needs to be resolved better than throwing it all into Value
+                quote! { impl ::std::convert::Into < #krate::sql::Value > }
+            }
+            Kind::Object => {
+                quote! { impl ::std::convert::Into < #krate::sql::Object >  }
+            }
+            Kind::References(_, _) => quote! { impl ::std::convert::Into < #krate::sql::Array > },
+            Kind::Set(_, _) | Kind::Array(_, _) => {
+                if let Some(items) = self.items() {
+                    let items = items.to_tokens(args);
+                    quote! { impl ::std::iter::IntoIterator < Item = #items > }
+                } else {
+                    quote! { impl ::std::convert::Into < #krate::sql::Array >  }
+                }
+            }
+        }
+    }
+
+    fn items(&self) -> Option<&Kind> {
+        match self {
+            Kind::Set(inner, max) | Kind::Array(inner, max) if !inner.is_any() || max.is_some() => Some(inner),
+            _ => None,
+        }
+    }
+
+    fn bound_into(&self, args: &IncludeFnArgs) -> bool {
+        // Types of other crates and `IntoDatetime`s aren't bindable as they are, they have to become a surrealdb type first
+        !matches!(self, Kind::Option(_))
+            && self.items().is_none()
+            && self.external_type(args).is_none()
+            && !(self == &Kind::Datetime && args.datetimes())
+            && !(args.checked_args && checked::checked_trait(self).is_some())
+    }
+
+    fn geometry_type(&self) -> Option<&str> {
+        match self {
+            Kind::Point => Some("point"),
+            Kind::Geometry(types) if types.len() == 1 => Some(&types[0]),
+            _ => None,
+        }
+    }
+
+    fn geo_type(&self, args: &IncludeFnArgs) -> Option<(TokenStream2, Ident)> {
+        if !args.geo {
+            return None;
+        }
+        // `feature` and `collection` have no single `geo` type, they stay a `Geometry`
+        let (ty, variant) = match self.geometry_type()? {
+            "point" => (quote! { ::geo::Point<f64> }, "Point"),
+            "line" => (quote! { ::geo::LineString<f64> }, "Line"),
+            "polygon" => (quote! { ::geo::Polygon<f64> }, "Polygon"),
+            "multipoint" => (quote! { ::geo::MultiPoint<f64> }, "MultiPoint"),
+            "multiline" => (quote! { ::geo::MultiLineString<f64> }, "MultiLine"),
+            "multipolygon" => (quote! { ::geo::MultiPolygon<f64> }, "MultiPolygon"),
+            _ => return None,
+        };
+        Some((ty, Ident::new(variant, Span::call_site())))
+    }
+
+    fn external_type(&self, args: &IncludeFnArgs) -> Option<(TokenStream2, TokenStream2)> {
+        let krate = krate();
+        if let Some(mapped) = args.types.kind(self) {
+            return Some((mapped.clone(), self.mapped_into(args)));
+        }
+        if let Some((geo, _)) = self.geo_type(args) {
+            return Some((geo, quote! { #krate::sql::Geometry }));
+        }
+        match self {
+            Kind::Uuid if args.uuid => Some((quote! { ::uuid::Uuid }, quote! { #krate::sql::Uuid })),
+            _ => None,
+        }
+    }
+
+    fn mapped_into(&self, args: &IncludeFnArgs) -> TokenStream2 {
+        let krate = krate();
+        match self {
+            Kind::Option(_) | Kind::Any | Kind::Either(_) | Kind::Literal(_) | Kind::Function | Kind::Unknown(_) => quote! { #krate::sql::Value },
+            _ => self.surreal_tokens(args),
+        }
+    }
+
+    fn unknown(&self) -> Option<&str> {
+        match self {
+            Kind::Unknown(kind) => Some(kind),
+            Kind::Option(kind) | Kind::Set(kind, _) | Kind::Array(kind, _) => kind.unknown(),
+            Kind::Either(kinds) | Kind::Literal(parser::kind::Literal::Array(kinds)) => kinds.iter().find_map(Kind::unknown),
+            Kind::Literal(parser::kind::Literal::Object(fields)) => fields.iter().find_map(|(_, kind)| kind.unknown()),
+            _ => None,
+        }
+    }
+
+    fn concrete_tokens(&self, args: &IncludeFnArgs) -> TokenStream2 {
+        if let Some((external, _)) = self.external_type(args) {
+            return external;
+        }
+        self.surreal_tokens(args)
+    }
+
+    fn surreal_tokens(&self, args: &IncludeFnArgs) -> TokenStream2 {
+        let krate = krate();
+        match self {
+            Kind::Bool => quote! { bool },
+            Kind::Bytes => quote! { #krate::sql::Bytes },
+            Kind::Datetime => quote! { #krate::sql::Datetime },
+            Kind::Duration => quote! { #krate::sql::Duration },
+            Kind::Int => quote! { i64 },
+            Kind::Float => quote! { f64 },
+            Kind::Decimal | Kind::Number => quote! { #krate::sql::Number },
+            Kind::String | Kind::Regex => quote! { ::std::string::String },
+            Kind::Range => quote! { #krate::sql::Range },
+            Kind::Uuid => quote! { #krate::sql::Uuid },
+            Kind::Record(_) => quote! { #krate::sql::Thing },
+            Kind::Point | Kind::Geometry(_) => quote! { #krate::sql::Geometry },
+            Kind::Object => quote! { #krate::sql::Object },
+            Kind::Set(_, _) | Kind::Array(_, _) | Kind::References(_, _) => quote! { #krate::sql::Array },
+            // `option<option<T>>` can't be told apart from `option<T>` once bound
+            Kind::Option(nested) => nested.concrete_tokens(args),
+            Kind::Any | Kind::Either(_) | Kind::Literal(_) | Kind::Function | Kind::Unknown(_) => quote! { #krate::sql::Value },
+        }
+    }
+
+    fn value_tokens(&self, value: TokenStream2, args: &IncludeFnArgs) -> TokenStream2 {
+        let krate = krate();
+        if let Some((external, sql)) = self.external_type(args) {
+            return external_value(&external, &sql, value);
+        }
+        if let Some(items) = self.items() {
+            // Taken as an iterator of the element kind, every item is converted on its own
+            let item = items.value_tokens(quote! { item }, args);
+            return quote! {
+                <#krate::sql::Value as ::std::convert::From<_>>::from(<#krate::sql::Array as ::std::convert::From<::std::vec::Vec<#krate::sql::Value>>>::from(
+                    ::std::iter::Iterator::collect(::std::iter::Iterator::map(::std::iter::IntoIterator::into_iter(#value), |item| #item))
+                ))
+            };
+        }
+        if let (Kind::Datetime, true) = (self, args.datetimes()) {
+            return quote! { <#krate::sql::Value as ::std::convert::From<_>>::from(IntoDatetime::into_datetime(#value)) };
+        }
+        if let Some((name, method)) = checked::checked_trait(self).filter(|_| args.checked_args) {
+            return quote! { <#krate::sql::Value as ::std::convert::From<_>>::from(#name::#method(#value)) };
+        }
+        if let Kind::Option(nested) = self {
+            if let Some((external, sql)) = nested.external_type(args) {
+                return quote! {
+                    <#krate::sql::Value as ::std::convert::From<_>>::from(#value.map(|item| <#krate::sql::Value as ::std::convert::From<_>>::from(<#sql as ::std::convert::From<#external>>::from(item))))
+                };
+            }
+            quote! { <#krate::sql::Value as ::std::convert::From<_>>::from(#value.map(<#krate::sql::Value as ::std::convert::From<_>>::from)) }
+        } else {
+            quote! { <#krate::sql::Value as ::std::convert::From<_>>::from(::std::convert::Into::into(#value)) }
+        }
+    }
+
+    fn sample_tokens(&self, args: &IncludeFnArgs) -> TokenStream2 {
+        let krate = krate();
+        if let (Some((external, _)), None) = (self.external_type(args), self.geo_type(args)) {
+            return quote! { <#external as ::std::default::Default>::default() };
+        }
+        if let Some(items) = self.items() {
+            // A single item, also fitting any maximum length
+            let item = items.sample_tokens(args);
+            return quote! { [#item] };
+        }
+        if let Some((_, variant)) = self.geo_type(args) {
+            let geometry = geometry_sample(self.geometry_type());
+            return quote! {
+                match #geometry {
+                    #krate::sql::Geometry::#variant(geometry) => geometry,
+                    _ => ::std::unreachable!(),
+                }
+            };
+        }
+        match self {
+            Kind::Bool => quote! { false },
+            Kind::Record(tables) => {
+                let table = tables.first().map_or("test", |table| table.0.as_str());
+                quote! { <#krate::sql::Thing as ::std::convert::From<(&str, &str)>>::from((#table, "test")) }
+            }
+            Kind::Point => quote! { <#krate::sql::Geometry as ::std::convert::From<(f64, f64)>>::from((0.0, 0.0)) },
+            Kind::Geometry(types) => geometry_sample(types.first().map(String::as_str)),
+            Kind::Range => quote! {
+                #krate::sql::Range { beg: ::std::ops::Bound::Unbounded, end: ::std::ops::Bound::Unbounded }
+            },
+            // Any `Serialize` can't tell which `None` it is
+            Kind::Option(_) if args.bindings == Bindings::Serde => quote! { ::std::option::Option::<#krate::sql::Value>::None },
+            Kind::Option(_) => quote! { ::std::option::Option::None },
+            Kind::Any | Kind::Unknown(_) => quote! { #krate::sql::Value::None },
+            Kind::Either(kinds) => kinds.first().map_or_else(|| quote! { #krate::sql::Value::None }, |kind| kind.sample_tokens(args)),
+            Kind::Literal(_) => self.value_sample(args),
+            _ => {
+                let concrete = self.concrete_tokens(args);
+                quote! { <#concrete as ::std::default::Default>::default() }
+            }
+        }
+    }
+
+    fn output_tokens(&self, version: SurrealVersion) -> Option<TokenStream2> {
+        let krate = krate();
+        Some(match self {
+            Kind::Bool => quote! { bool },
+            Kind::Duration => quote! { ::std::time::Duration },
+            Kind::Int => quote! { i64 },
+            Kind::Float | Kind::Number => quote! { f64 },
+            Kind::String => quote! { ::std::string::String },
+            Kind::Record(_) if version == SurrealVersion::V2 => quote! { #krate::RecordId },
+            Kind::Record(_) => quote! { #krate::sql::Thing },
+            _ => return None,
+        })
+    }
+}
+
+/// Generates the Rust wrappers of a function.
+pub(crate) trait Wrappers {
+    /// Return type of the `_value` wrapper, unwrapping a declared `array`, `set` or `option`.
+    ///
+    /// Object literals deserialize into the struct generated for them, see `literal::literal_types()`.
+    fn value_output(&self, args: &IncludeFnArgs) -> TokenStream2;
+
+    /// Rust type a returned item of `kind` deserializes into, like `output_tokens()` but with the generated output struct.
+    fn item_output(&self, kind: &Kind, args: &IncludeFnArgs) -> Option<TokenStream2>;
+
+    fn params_to_args(&self, args: &IncludeFnArgs) -> TokenStream2;
+
+    /// Generic parameters of the wrappers, with `connection` and the lengths of the arrays with a maximum length.
+    fn generics(&self, connection: Option<TokenStream2>, args: &IncludeFnArgs) -> TokenStream2;
+
+    /// The type mapped for the parameter `name` with `map fn::name($param)`, and the surrealdb type it converts into.
+    fn mapped_param(&self, name: &str, kind: &Kind, args: &IncludeFnArgs) -> Option<(TokenStream2, TokenStream2)>;
+
+    /// Whether the parameter `name` is taken as a fixed-size array, for `array<T, N>` kinds that aren't mapped.
+    fn takes_array(&self, name: &str, kind: &Kind, args: &IncludeFnArgs) -> bool;
+
+    /// An argument for the parameter `name`, as simple as possible, for the generated tests.
+    fn param_sample(&self, name: &str, kind: &Kind, args: &IncludeFnArgs) -> TokenStream2;
+
+    /// Converts the parameter `name` into a `Value`, failing to compile if an array is longer than its maximum length.
+    ///
+    /// With `bindings as serde`, the conversion can fail, so it's followed by `?`.
+    fn param_value(&self, name: &str, kind: &Kind, args: &IncludeFnArgs) -> TokenStream2;
+
+    fn params_to_bindings(&self, args: &IncludeFnArgs) -> TokenStream2;
+
+    /// Expression running the query of the driver function, retried on network errors with `retry`.
+    fn driver_query(&self, args: &IncludeFnArgs) -> TokenStream2;
+
+    fn params_to_variables(&self, args: &IncludeFnArgs) -> TokenStream2;
+
+    /// Documentation, the deprecation notice and the `@cfg` of the function.
+    fn attributes(&self, args: &IncludeFnArgs) -> TokenStream2;
+
+    /// The predicate of a `-- @cfg(feature = "admin")` directive, with its parentheses.
+    fn cfg_predicate(&self) -> Option<&str>;
+
+    /// `#[cfg(...)]` for the items generated for this function, if it has a `@cfg` directive.
+    fn cfg(&self) -> Option<TokenStream2>;
+
+    /// Why calling the function is deprecated, from the alias it was generated for or a `-- @deprecated note` directive.
+    fn deprecation(&self) -> Option<String>;
+
+    /// Deprecated copies of this function for every `@alias` directive, still calling this function.
+    fn aliases(&self) -> Vec<DefineFunctionStatement>;
+
+    /// Parameters named by `@sensitive` directives, like `-- @sensitive $password $token`.
+    fn sensitive(&self) -> Vec<String>;
+
+    /// A `# SurrealQL` section with a table of the parameters and the statement the wrapper calls, in a ```` ```surql ```` block.
+    ///
+    /// Only the signature by default, the whole statement with `docs as body`. Aliases point to the function instead.
+    fn surql_docs(&self, args: &IncludeFnArgs) -> TokenStream2;
+
+    fn doc_comments(&self) -> TokenStream2;
+
+    /// The lines of the comments, and the ```` ```rust ```` blocks among them moved to a list of examples.
+    fn split_examples(&self) -> (Vec<String>, Vec<Vec<String>>);
+
+    /// An `# Examples` section with the ```` ```rust ```` blocks of the comments, run as doctests as `examples as ...` says.
+    fn examples(&self, args: &IncludeFnArgs) -> TokenStream2;
+
+    /// Signature of the method wrapping this function, taking `&self` instead of a connection.
+    fn method_signature(&self, name: &Ident, args: &IncludeFnArgs) -> TokenStream2;
+
+    /// Body of the method wrapping this function, `receiver` has to evaluate to a `Surreal<C>`.
+    fn method_body(&self, receiver: TokenStream2, args: &IncludeFnArgs) -> TokenStream2;
+
+    /// The function name with all of its parts joined by `_`, used where modules aren't available.
+    fn flat_name(&self) -> String;
+
+    /// The segments of the name the Rust names are made from, without the version and made of valid characters.
+    fn rust_path(&self) -> Vec<String>;
+
+    /// The Rust name of the parameter `name`, from a `-- @rust_param $name other_name` directive, or `name` without invalid characters.
+    fn param_name(&self, name: &str) -> String;
+
+    fn param_ident(&self, name: &str) -> Ident;
+
+    /// Name of the generated function, before the driver or datastore alias is applied.
+    fn rust_name(&self, args: &IncludeFnArgs) -> String;
+
+    /// Name of the trait and derive methods, nested names joined with `_`.
+    fn method_base_name(&self) -> String;
+
+    /// The identifier from a `-- @rust_name fetch_user` directive, used instead of the name of the function.
+    fn renamed(&self) -> Option<String>;
+
+    /// Path from the module of the generated function to the one of `stored_functions()`.
+    fn root_path(&self, args: &IncludeFnArgs) -> TokenStream2;
+
+    /// Modules the generated function is in, from the one of `stored_functions()`.
+    fn module_path(&self, sources: &[SourceFile], args: &IncludeFnArgs) -> Vec<String>;
+
+    /// How many modules deep the generated function is.
+    fn module_depth(&self, args: &IncludeFnArgs) -> usize;
+
+    fn custom_function_query(&self) -> String;
+
+    fn to_tokens(&self, args: &IncludeFnArgs) -> TokenStream2;
+}
+
+impl Wrappers for DefineFunctionStatement {
+    fn value_output(&self, args: &IncludeFnArgs) -> TokenStream2 {
+        let version = args.version;
+        let item = |kind: &Kind| self.item_output(kind, args);
+        let output = match &self.returns {
+            Some(Kind::Array(kind, _) | Kind::Set(kind, _)) => item(kind).map(|kind| quote! { ::std::vec::Vec<#kind> }),
+            Some(Kind::Option(kind)) => item(kind).map(|kind| quote! { ::std::option::Option<#kind> }),
+            Some(kind) => item(kind).map(|kind| quote! { ::std::option::Option<#kind> }),
+            None => None,
+        };
+        output.unwrap_or_else(|| version.value_tokens())
+    }
+
+    fn item_output(&self, kind: &Kind, args: &IncludeFnArgs) -> Option<TokenStream2> {
+        match (self.output_type(args), kind) {
+            (Some(ty), Kind::Literal(parser::kind::Literal::Object(_))) => Some(ty.to_token_stream()),
+            _ => kind.output_tokens(args.version),
+        }
+    }
+
+    fn params_to_args(&self, args: &IncludeFnArgs) -> TokenStream2 {
+        let mut out = TokenStream2::new();
+
+        for (name, kind) in &self.args {
+            let mapped = self.mapped_param(name, kind, args);
+            let literal = self.literal_type(name, kind, args);
+            let kind = match (kind.items(), mapped) {
+                _ if args.bindings == Bindings::Serde => quote! { impl ::serde::Serialize },
+                (_, Some((ty, _))) => quote! { impl ::std::convert::Into < #ty > },
+                // Generated next to the functions, see `literal::literal_types()`
+                _ if literal.is_some() => {
+                    if let Kind::Option(_) = kind {
+                        quote! { ::std::option::Option < #literal > }
+                    } else {
+                        quote! { impl ::std::convert::Into < #literal > }
+                    }
+                }
+                // A fixed-size array, so its length can be checked at compile time
+                (Some(items), None) if self.takes_array(name, kind, args) => {
+                    let items = items.to_tokens(args);
+                    let len = length_param(&self.param_name(name));
+                    quote! { [#items; #len] }
+                }
+                _ => kind.to_tokens(args),
+            };
+            let name = self.param_ident(name);
+            out.extend(quote! { #name: #kind, });
+        }
+
+        out
+    }
+
+    fn generics(&self, connection: Option<TokenStream2>, args: &IncludeFnArgs) -> TokenStream2 {
+        let typed = args.bindings == Bindings::Typed;
+        let lengths = self.args.iter().filter(|(name, kind)| typed && self.takes_array(name, kind, args)).map(|(name, _)| {
+            let len = length_param(&self.param_name(name));
+            quote! { const #len: usize }
+        });
+        let params: Vec<_> = connection.into_iter().chain(lengths).collect();
+        if params.is_empty() {
+            return TokenStream2::new();
+        }
+        quote! { <#(#params),*> }
+    }
+
+    fn mapped_param(&self, name: &str, kind: &Kind, args: &IncludeFnArgs) -> Option<(TokenStream2, TokenStream2)> {
+        let function = self.alias_of.as_ref().unwrap_or(&self.name).join("::");
+        args.types.param(&function, name).map(|ty| (ty.clone(), kind.mapped_into(args)))
+    }
+
+    fn takes_array(&self, name: &str, kind: &Kind, args: &IncludeFnArgs) -> bool {
+        matches!(kind, Kind::Array(_, Some(_))) && kind.external_type(args).is_none() && self.mapped_param(name, kind, args).is_none()
+    }
+
+    fn param_sample(&self, name: &str, kind: &Kind, args: &IncludeFnArgs) -> TokenStream2 {
+        if let Some(ty) = self.literal_type(name, kind, args) {
+            return self.literal_sample(&ty, kind, args);
+        }
+        match self.mapped_param(name, kind, args) {
+            Some((ty, _)) => quote! { <#ty as ::std::default::Default>::default() },
+            None => kind.sample_tokens(args),
+        }
+    }
+
+    fn param_value(&self, name: &str, kind: &Kind, args: &IncludeFnArgs) -> TokenStream2 {
+        let krate = krate();
+        if args.bindings == Bindings::Serde {
+            let name = self.param_ident(name);
+            return quote! { #krate::sql::to_value(#name)? };
+        }
+        let value = self.param_ident(name).into_token_stream();
+        if let Some((ty, sql)) = self.mapped_param(name, kind, args) {
+            return external_value(&ty, &sql, value);
+        }
+        match (self.literal_type(name, kind, args), kind) {
+            (Some(_), Kind::Option(_)) => {
+                return quote! { <#krate::sql::Value as ::std::convert::From<_>>::from(#value.map(<#krate::sql::Value as ::std::convert::From<_>>::from)) }
+            }
+            (Some(ty), _) => return external_value(&ty.into_token_stream(), &quote! { #krate::sql::Value }, value),
+            (None, _) => {}
+        }
+        let value = kind.value_tokens(value, args);
+        let (true, Kind::Array(_, Some(max))) = (self.takes_array(name, kind, args), kind) else {
+            return value;
+        };
+        let len = length_param(&self.param_name(name));
+        let message = format!("`${name}` of `fn::{}` takes at most {max} items", self.name.join("::"));
+        quote! {{
+            const { ::std::assert!(#len <= #max as usize, #message) };
+            #value
+        }}
+    }
+
+    fn params_to_bindings(&self, args: &IncludeFnArgs) -> TokenStream2 {
+        let mut out = TokenStream2::new();
+
+        for (name, kind) in &self.args {
+            let key = binding_name(name);
+            let value = self.param_ident(name);
+            let value = if args.bindings == Bindings::Serde {
+                // The driver serializes bindings itself
+                value.into_token_stream()
+            } else if !kind.bound_into(args)
+                || self.mapped_param(name, kind, args).is_some()
+                || self.literal_type(name, kind, args).is_some()
+            {
+                self.param_value(name, kind, args)
+            } else {
+                quote! { ::std::convert::Into::into(#value) }
+            };
+            out.extend(quote! {
+                .bind((#key, #value))
+            });
+        }
+
+        out
+    }
+
+    fn driver_query(&self, args: &IncludeFnArgs) -> TokenStream2 {
+        let krate = krate();
+        let query = self.custom_function_query();
+        let timed_out = quote! { #krate::Error::Db(#krate::error::Db::QueryTimedout) };
+        let Some(retry) = args.retry else {
+            let bind = self.params_to_bindings(args);
+            return args.awaited(
+                quote! {
+                    db.query(#query)
+                    #bind
+                },
+                timed_out,
+            );
+        };
+        // Converted once, so every attempt can bind a clone
+        let bindings = self.args.iter().map(|(name, kind)| {
+            let key = binding_name(name);
+            let value = self.param_value(name, kind, args);
+            quote! { (#key, #value) }
+        });
+        let times = retry.times;
+        let backoff = retry.backoff.as_nanos() as u64;
+        let retried = quote! {{
+            let bindings: ::std::vec::Vec<(&str, #krate::sql::Value)> = ::std::vec![#(#bindings),*];
+            let mut retries = 0u32;
+            let mut backoff = ::std::time::Duration::from_nanos(#backoff);
+            loop {
+                let mut query = db.query(#query);
+                for (key, value) in &bindings {
+                    query = query.bind((*key, ::std::clone::Clone::clone(value)));
+                }
+                match query.await {
+                    ::std::result::Result::Err(#krate::Error::Api(#krate::error::Api::Http(_) | #krate::error::Api::Ws(_)))
+                        if retries < #times =>
+                    {
+                        retries += 1;
+                        ::tokio::time::sleep(backoff).await;
+                        backoff *= 2;
+                    }
+                    result => break result,
+                }
+            }
+        }};
+        match args.timeout {
+            // The timeout covers every attempt
+            Some(_) => args.awaited(quote! { async #retried }, timed_out),
+            None => retried,
+        }
+    }
+
+    fn params_to_variables(&self, args: &IncludeFnArgs) -> TokenStream2 {
+        let krate = krate();
+        // Build a Option<BTreeMap<String, Value>> for the variables
+        let mut out = quote! {
+            let mut variables: ::std::collections::BTreeMap<::std::string::String, #krate::sql::Value> = ::std::collections::BTreeMap::new();
+        };
+        for (name, kind) in &self.args {
+            let key = binding_name(name);
+            let value = self.param_value(name, kind, args);
+            out.extend(quote! {
+                variables.insert(::std::string::ToString::to_string(#key), #value);
+            });
+        }
+
+        out
+    }
+
+    fn attributes(&self, args: &IncludeFnArgs) -> TokenStream2 {
+        let mut out = self.cfg().unwrap_or_default();
+        out.extend(self.doc_comments());
+        out.extend(self.surql_docs(args));
+        let sensitive = self.sensitive();
+        if !sensitive.is_empty() {
+            let names: Vec<_> = sensitive.iter().map(|name| format!("`{name}`")).collect();
+            let note = format!("Sensitive parameters, redacted from `Debug` output: {}.", names.join(", "));
+            out.extend(quote! {
+                #[doc = ""]
+                #[doc = #note]
+            });
+        }
+        match self.deprecation() {
+            Some(note) if note.is_empty() => out.extend(quote! { #[deprecated] }),
+            Some(note) => out.extend(quote! { #[deprecated(note = #note)] }),
+            None => {}
+        }
+        out
+    }
+
+    fn cfg_predicate(&self) -> Option<&str> {
+        self.directives
+            .iter()
+            .find(|directive| directive.name == "cfg")
+            .map(|directive| directive.value.as_str())
+    }
+
+    fn cfg(&self) -> Option<TokenStream2> {
+        let condition = cfg_condition(self.cfg_predicate()?);
+        Some(quote! { #[cfg(#condition)] })
+    }
+
+    fn deprecation(&self) -> Option<String> {
+        if let Some(target) = &self.alias_of {
+            return Some(format!("renamed to `fn::{}`", target.join("::")));
+        }
+        self.directives
+            .iter()
+            .find(|directive| directive.name == "deprecated")
+            .map(|directive| directive.value.clone())
+    }
+
+    fn aliases(&self) -> Vec<DefineFunctionStatement> {
+        self.directives
+            .iter()
+            .filter(|directive| directive.name == "alias")
+            .map(|directive| {
+                let mut name: Vec<String> = directive
+                    .value
+                    .trim_start_matches("fn::")
+                    .split("::")
+                    .map(str::to_string)
+                    .collect();
+                let valid = name.iter().all(|part| {
+                    part.chars().all(parser::common::val_char)
+                        && part.starts_with(|c: char| !c.is_ascii_digit())
+                });
+                if !valid {
+                    abort_call_site!(format!("invalid alias `{}` for `fn::{}`", directive.value, self.name.join("::")); help = "expected `-- @alias old_name` or `-- @alias fn::old::name`")
+                }
+                // Named like the functions of the same version, for the Rust names to leave it out the same way
+                if let Some(version) = &self.version {
+                    name.insert(0, version.clone());
+                }
+                let mut comments = vec![format!(
+                    "Deprecated alias of `fn::{}`.",
+                    self.name.join("::")
+                )];
+                comments.extend(self.comments.iter().cloned());
+                DefineFunctionStatement {
+                    comments,
+                    directives: self
+                        .directives
+                        .iter()
+                        .filter(|directive| directive.name == "sensitive" || directive.name == "cfg")
+                        .cloned()
+                        .collect(),
+                    name,
+                    args: self.args.clone(),
+                    arg_comments: self.arg_comments.clone(),
+                    returns: self.returns.clone(),
+                    alias_of: Some(self.name.clone()),
+                    definition: None,
+                    version: self.version.clone(),
+                }
+            })
+            .collect()
+    }
+
+    fn sensitive(&self) -> Vec<String> {
+        let mut out = vec![];
+        for directive in self.directives.iter().filter(|directive| directive.name == "sensitive") {
+            for param in directive.value.split([',', ' ', '\t']).filter(|param| !param.is_empty()) {
+                let name = param.trim_start_matches('$');
+                if !self.args.iter().any(|(arg, _)| arg.0 == name) {
+                    abort_call_site!(format!("`fn::{}` has no parameter `{param}`", self.name.join("::")); help = "expected `-- @sensitive $param`, naming parameters of the function")
+                }
+                out.push(name.to_string());
+            }
+        }
+        out
+    }
+
+    fn surql_docs(&self, args: &IncludeFnArgs) -> TokenStream2 {
+        if self.alias_of.is_some() {
+            return quote! {};
+        }
+        let definition = match (args.docs, &self.definition) {
+            (Docs::Body, Some(definition)) => definition.clone(),
+            _ => format!("DEFINE FUNCTION {}", self.signature()),
+        };
+        let mut lines = vec![String::new(), "# SurrealQL".to_string(), String::new()];
+        if !self.args.is_empty() {
+            // Pipes of either kinds would end the cell
+            let cell = |text: String| text.replace('|', "\\|");
+            let described = !self.arg_comments.is_empty();
+            let (header, separator) = match described {
+                true => ("| Parameter | Kind | Description |", "|---|---|---|"),
+                false => ("| Parameter | Kind |", "|---|---|"),
+            };
+            lines.extend([header.to_string(), separator.to_string()]);
+            for (name, kind) in &self.args {
+                let mut row = format!("| `${}` | `{}` |", name.0, cell(kind.to_string()));
+                if described {
+                    let comment = self.arg_comments.iter().find(|(arg, _)| arg == name).map(|(_, comment)| comment.clone());
+                    row.push_str(&format!(" {} |", cell(comment.unwrap_or_default())));
+                }
+                lines.push(row);
+            }
+            lines.push(String::new());
+        }
+        if let Some(kind) = &self.returns {
+            lines.push(format!("Returns `{kind}`."));
+            lines.push(String::new());
+        }
+        lines.push("```surql".to_string());
+        lines.extend(definition.lines().map(str::to_string));
+        lines.push("```".to_string());
+        lines.iter().map(|line| quote! { #[doc = #line] }).collect()
+    }
+
+    fn doc_comments(&self) -> TokenStream2 {
+        // turn comments into rust comments
+        let (prose, _) = self.split_examples();
+        prose
+            .iter()
+            .map(|s| {
+                quote! {
+                    #[doc = #s]
+                }
+            })
+            .collect::<TokenStream2>()
+    }
+
+    fn split_examples(&self) -> (Vec<String>, Vec<Vec<String>>) {
+        let mut prose = vec![];
+        let mut examples: Vec<Vec<String>> = vec![];
+        // Whether the code block the line is in is Rust, if it's in one
+        let mut fence: Option<bool> = None;
+        for s in &self.comments {
+            let language = s.trim_start().strip_prefix("```").map(str::trim);
+            match (fence, language) {
+                (None, Some(language)) => {
+                    let rust = language.split(',').next() == Some("rust");
+                    fence = Some(rust);
+                    match rust {
+                        true => examples.push(vec![language.to_string()]),
+                        // Untagged code blocks would be compiled as Rust doctests
+                        false if language.is_empty() => prose.push(format!("{}text", s.trim_end())),
+                        false => prose.push(s.clone()),
+                    }
+                }
+                (Some(rust), Some(_)) => {
+                    fence = None;
+                    match rust {
+                        true => examples.last_mut().unwrap().push("```".to_string()),
+                        false => prose.push(s.clone()),
+                    }
+                }
+                (Some(true), None) => examples.last_mut().unwrap().push(s.clone()),
+                _ => prose.push(s.clone()),
+            }
+        }
+        if fence == Some(true) {
+            examples.last_mut().unwrap().push("```".to_string());
+        }
+        // Blank lines left where examples were taken out
+        while prose.last().is_some_and(|line| line.is_empty()) {
+            prose.pop();
+        }
+        (prose, examples)
+    }
+
+    fn examples(&self, args: &IncludeFnArgs) -> TokenStream2 {
+        let (_, examples) = self.split_examples();
+        if examples.is_empty() {
+            return TokenStream2::new();
+        }
+        let mut lines = vec![String::new(), "# Examples".to_string()];
+        for example in examples {
+            let language = match (&example[0][..], args.examples) {
+                ("rust", Examples::NoRun) => "rust,no_run",
+                ("rust", Examples::Ignore) => "rust,ignore",
+                (language, _) => language,
+            };
+            lines.push(String::new());
+            lines.push(format!("```{language}"));
+            lines.extend(example[1..].iter().cloned());
+        }
+        quote! { #(#[doc = #lines])* }
+    }
+
+    fn method_signature(&self, name: &Ident, args: &IncludeFnArgs) -> TokenStream2 {
+        let krate = krate();
+        let params = self.params_to_args(args);
+        let generics = self.generics(None, args);
+        quote! {
+            async fn #name #generics(&self, #params) -> #krate::Result<#krate::Response>
+        }
+    }
+
+    fn method_body(&self, receiver: TokenStream2, args: &IncludeFnArgs) -> TokenStream2 {
+        let query = self.custom_function_query();
+        let bind = self.params_to_bindings(args);
+        quote! {
+            #receiver.query(#query)
+            #bind
+            .await
+        }
+    }
+
+    fn flat_name(&self) -> String {
+        self.rust_path().join("_")
+    }
+
+    fn rust_path(&self) -> Vec<String> {
+        self.unversioned_name().iter().map(|segment| rust_segment(segment)).collect()
+    }
+
+    fn param_name(&self, name: &str) -> String {
+        for directive in self.directives.iter().filter(|directive| directive.name == "rust_param") {
+            let help = "expected `-- @rust_param $param rust_name`, like `-- @rust_param $⟨first name⟩ first_name`";
+            let parsed = directive.value.strip_prefix('$').and_then(|value| parser::ident::ident(value).ok());
+            let Some((rust_name, param)) = parsed.map(|(rest, param)| (rest.trim(), param)) else {
+                abort_call_site!(format!("invalid `@rust_param {}` for `fn::{}`", directive.value, escape_path(&self.name)); help = help)
+            };
+            if syn::parse_str::<Ident>(rust_name).is_err() {
+                abort_call_site!(format!("invalid `@rust_param {}` for `fn::{}`", directive.value, escape_path(&self.name)); help = help)
+            }
+            if !self.args.iter().any(|(arg, _)| *arg == param) {
+                abort_call_site!(format!("`fn::{}` has no parameter `${param}`", escape_path(&self.name)); help = help)
+            }
+            if param.0 == name {
+                return rust_name.to_string();
+            }
+        }
+        rust_segment(name)
+    }
+
+    fn param_ident(&self, name: &str) -> Ident {
+        rust_ident(&self.param_name(name))
+    }
+
+    fn rust_name(&self, args: &IncludeFnArgs) -> String {
+        if let Some(name) = self.renamed() {
+            return name;
+        }
+        match args.modules {
+            Modules::Nested => self.rust_path().pop().unwrap(),
+            Modules::Flat | Modules::PerFile => self.flat_name(),
+        }
+    }
+
+    fn method_base_name(&self) -> String {
+        self.renamed().unwrap_or_else(|| self.flat_name())
+    }
+
+    fn renamed(&self) -> Option<String> {
+        let directive = self.directives.iter().find(|directive| directive.name == "rust_name")?;
+        if syn::parse_str::<Ident>(&directive.value).is_err() {
+            abort_call_site!(format!("invalid `@rust_name {}` for `fn::{}`", directive.value, self.name.join("::")); help = "expected an identifier, like `-- @rust_name fetch_user`")
+        }
+        Some(directive.value.clone())
+    }
+
+    fn root_path(&self, args: &IncludeFnArgs) -> TokenStream2 {
+        (0..self.module_depth(args)).map(|_| quote! { super:: }).collect()
+    }
+
+    fn module_path(&self, sources: &[SourceFile], args: &IncludeFnArgs) -> Vec<String> {
+        match args.modules {
+            Modules::Nested => {
+                let mut name = self.rust_path();
+                name.pop();
+                name
+            }
+            Modules::Flat => vec![],
+            Modules::PerFile => {
+                let name = self.alias_of.as_ref().unwrap_or(&self.name);
+                let source = sources.iter().find(|source| source.functions.iter().any(|(_, function)| function.name == *name));
+                vec![source.expect("functions come from the sources").module_name()]
+            }
+        }
+    }
+
+    fn module_depth(&self, args: &IncludeFnArgs) -> usize {
+        match args.modules {
+            Modules::Nested => self.unversioned_name().len() - 1,
+            Modules::Flat => 0,
+            Modules::PerFile => 1,
+        }
+    }
+
+    fn custom_function_query(&self) -> String {
+        let mut out = String::new();
+        out.push_str("RETURN fn");
+        for name in self.alias_of.as_ref().unwrap_or(&self.name) {
+            out.push_str("::");
+            out.push_str(&escape_ident(name));
+        }
+
+        out.push('(');
+        for (i, (name, _)) in self.args.iter().enumerate() {
+            let name = parser::ident::Ident::from(binding_name(name)).to_string();
+            out.push('$');
+            out.push_str(&name);
+            if i < self.args.len() - 1 {
+                out.push_str(", ");
+            }
+        }
+        out.push(')');
+
+        out
+    }
+
+    fn to_tokens(&self, args: &IncludeFnArgs) -> TokenStream2 {
+        let krate = krate();
+        let (driver, datastore) = args.transform_fn_name(&self.rust_name(args));
+        let params = self.params_to_args(args);
+        let query = self.custom_function_query();
+        let comments = self.attributes(args);
+        // Only on the first wrapper, every copy would run again
+        let mut examples = Some(self.examples(args));
+
+        let generics = self.generics(args.engine.param(), args);
+        let lengths = self.generics(None, args);
+        let connection = args.engine.connection();
+        let root = self.root_path(args);
 
         let mut tokens = TokenStream2::new();
-        if let Some(name) = driver {
-            let bind = self.params_to_bindings();
+        if self.alias_of.is_none() {
+            let cfg = self.cfg();
+            let name = format_ident!("{}_FN", self.rust_name(args).to_uppercase());
+            let full = format!("fn::{}", escape_path(&self.name));
+            let doc = format!("The name of `{full}`, for queries calling it by hand.");
             tokens.extend(quote! {
+                #cfg
+                #[doc = #doc]
+                pub const #name: &str = #full;
+            });
+        }
+        if let Some(name) = driver {
+            let mut driver = TokenStream2::new();
+            let call = instrument::instrumented(args, self, self.driver_query(args));
+            let call = metrics::observed(args, self, &root, call);
+            let examples = examples.take();
+            driver.extend(quote! {
                 #comments
-                pub async fn #name<C: ::surrealdb::Connection>(db: &::surrealdb::Surreal<C>, #args) -> ::surrealdb::Result<::surrealdb::Response> {
-                    db.query(#query)
-                    #bind
-                    .await
+                #examples
+                pub async fn #name #generics(db: &#krate::Surreal<#connection>, #params) -> #krate::Result<#krate::Response> {
+                    #call
+                }
+            });
+
+            let value = format_ident!("{}_value", name);
+            let output = self.value_output(args);
+            let send = self.driver_query(args);
+            let take = instrument::instrumented(args, self, quote! {
+                #send?
+                .check()?
+                .take(0)
+            });
+            let take = metrics::observed(args, self, &root, take);
+            driver.extend(quote! {
+                #comments
+                #[doc = ""]
+                #[doc = "Checks the response for errors and takes the result of the call."]
+                pub async fn #value #generics(db: &#krate::Surreal<#connection>, #params) -> #krate::Result<#output> {
+                    #take
+                }
+            });
+
+            driver.extend(fragment::query_function(self, &format_ident!("{}_query", name), args));
+            if args.fragments {
+                driver.extend(fragment::fragment_function(self, &format_ident!("{}_fragment", name), args));
+            }
+            if args.paged && self.returns_array() {
+                driver.extend(page::paged_function(self, &format_ident!("{}_paged", name), args));
+            }
+            if args.stats {
+                driver.extend(stats::stats_function(self, &format_ident!("{}_with_stats", name), args));
+            }
+            if args.targets {
+                driver.extend(target::target_function(self, &format_ident!("{}_in", name), args));
+            }
+            if args.http.is_some() && self.alias_of.is_none() {
+                driver.extend(http::handler_function(self, &format_ident!("{}_handler", name), args));
+            }
+            if let Some(statement) = self.live_statement() {
+                driver.extend(live::live_function(self, &statement, &format_ident!("{}_live", name), args));
+            }
+            tokens.extend(cfg_items(args.driver_cfg.as_deref(), driver));
+        }
+
+        if let Some(name) = datastore {
+            let bind = self.params_to_variables(args);
+            let (output, execute) = if args.datastore_raw {
+                (
+                    quote! { ::std::vec::Vec<#krate::dbs::Response> },
+                    quote! { ds.execute(#query, session, ::std::option::Option::Some(variables)).await },
+                )
+            } else {
+                (
+                    quote! { #krate::sql::Value },
+                    // A single statement always has a single response
+                    quote! { ds.execute(#query, session, ::std::option::Option::Some(variables)).await?.remove(0).result },
+                )
+            };
+            let with_vars = format_ident!("{}_with_vars", name);
+            let names = self.args.iter().map(|(param, _)| self.param_ident(param));
+            let execute = instrument::instrumented(args, self, quote! {
+                #bind
+                for (key, value) in extra_vars {
+                    variables.entry(key).or_insert(value);
+                }
+                #execute
+            });
+            let execute = metrics::observed(args, self, &root, execute);
+            let examples = examples.take();
+            let datastore = quote! {
+                #comments
+                #examples
+                pub async fn #name #lengths(ds: &#krate::kvs::Datastore, session: &#krate::dbs::Session, #params) -> ::std::result::Result<#output, #krate::err::Error> {
+                    #[allow(deprecated)]
+                    #with_vars(ds, session, #(#names,)* ::std::collections::BTreeMap::new()).await
+                }
+
+                #comments
+                #[doc = ""]
+                #[doc = "Also binds `extra_vars`, like context variables the function body expects. The parameters of the function take precedence."]
+                pub async fn #with_vars #lengths(ds: &#krate::kvs::Datastore, session: &#krate::dbs::Session, #params extra_vars: ::std::collections::BTreeMap<::std::string::String, #krate::sql::Value>) -> ::std::result::Result<#output, #krate::err::Error> {
+                    #execute
+                }
+            };
+            tokens.extend(cfg_items(args.datastore_cfg.as_deref(), datastore));
+        }
+
+        if let Some(name) = args.blocking_fn_name(&self.rust_name(args)) {
+            let runtime = args.blocking_runtime.params();
+            let bind = self.params_to_bindings(args);
+            let call = args.blocking_runtime.block_on(quote! {
+                db.query(#query)
+                #bind
+            });
+            let value = format_ident!("{}_value", name);
+            let output = self.value_output(args);
+            let examples = examples.take();
+            let blocking = quote! {
+                #comments
+                #[doc = ""]
+                #[doc = "Blocks the current thread until the query is done."]
+                #examples
+                pub fn #name #generics(#runtime db: &#krate::Surreal<#connection>, #params) -> #krate::Result<#krate::Response> {
+                    #call
+                }
+
+                #comments
+                #[doc = ""]
+                #[doc = "Blocks the current thread until the query is done, then checks the response for errors and takes the result of the call."]
+                pub fn #value #generics(#runtime db: &#krate::Surreal<#connection>, #params) -> #krate::Result<#output> {
+                    #call?.check()?.take(0)
+                }
+            };
+            tokens.extend(cfg_items(args.blocking_cfg.as_deref(), blocking));
+        }
+        tokens
+    }
+}
+
+/// Converts `value`, taken as `impl Into<external>`, into a `Value` through the surrealdb type `sql`.
+fn external_value(external: &TokenStream2, sql: &TokenStream2, value: TokenStream2) -> TokenStream2 {
+    let krate = krate();
+    quote! {
+        <#krate::sql::Value as ::std::convert::From<_>>::from(<#sql as ::std::convert::From<#external>>::from(::std::convert::Into::into(#value)))
+    }
+}
+
+/// A `Geometry` of the geometry type `name`, for the generated tests.
+fn geometry_sample(name: Option<&str>) -> TokenStream2 {
+    let krate = krate();
+    let geojson = match name {
+        Some("line") => r#"{ type: "LineString", coordinates: [[0, 0], [1, 1]] }"#,
+        Some("polygon") => r#"{ type: "Polygon", coordinates: [[[0, 0], [1, 0], [1, 1], [0, 0]]] }"#,
+        Some("multipoint") => r#"{ type: "MultiPoint", coordinates: [[0, 0]] }"#,
+        Some("multiline") => r#"{ type: "MultiLineString", coordinates: [[[0, 0], [1, 1]]] }"#,
+        Some("multipolygon") => r#"{ type: "MultiPolygon", coordinates: [[[[0, 0], [1, 0], [1, 1], [0, 0]]]] }"#,
+        Some("collection") => r#"{ type: "GeometryCollection", geometries: [{ type: "Point", coordinates: [0, 0] }] }"#,
+        _ => r#"{ type: "Point", coordinates: [0, 0] }"#,
+    };
+    quote! {
+        match #krate::sql::value(#geojson) {
+            ::std::result::Result::Ok(#krate::sql::Value::Geometry(geometry)) => geometry,
+            _ => ::std::unreachable!(),
+        }
+    }
+}
+
+/// `name` as an identifier, raw like `r#type` if it's a keyword, or with a `_` suffix for `self` and the others that can't be raw.
+///
+/// Characters Rust identifiers can't hold are replaced first, see [`rust_segment`].
+pub(crate) fn rust_ident(name: &str) -> Ident {
+    let name = rust_segment(name);
+    let name = name.as_str();
+    match syn::parse_str::<Ident>(name) {
+        // Reserved since the 2024 edition, which syn still parses as an identifier
+        Ok(_) if name == "gen" => Ident::new_raw(name, Span::call_site()),
+        Ok(name) => name,
+        Err(_) if matches!(name, "_" | "self" | "Self" | "super" | "crate") => format_ident!("{}_", name),
+        Err(_) => Ident::new_raw(name, Span::call_site()),
+    }
+}
+
+/// Const generic parameter holding the length of the array parameter `name`.
+fn length_param(name: &str) -> Ident {
+    format_ident!("{}_LEN", rust_segment(name).to_uppercase())
+}
+
+/// The name the parameter `name` is bound as, prefixed so it can't collide with protected parameters like `$auth`
+/// or `$value`, nor with the bindings of a query the call is part of.
+pub(crate) fn binding_name(name: &str) -> String {
+    format!("__fnarg_{name}")
+}
+
+fn build_mod_tree(functions: Vec<DefineFunctionStatement>, sources: &[SourceFile], args: &IncludeFnArgs) -> TokenStream2 {
+    let mut seen = BTreeMap::new();
+    for function in &functions {
+        let name = function.name.join("::");
+        let rust_name = function.rust_name(args);
+        if let Some(other) = seen.insert((function.module_path(sources, args), rust_name.clone()), name.clone()) {
+            match args.modules {
+                Modules::Flat => abort_call_site!("`fn::{}` and `fn::{}` both become `{}` with flat modules", other, name, rust_name; help = "rename one of them or use nested modules"),
+                _ => abort_call_site!("`fn::{}` and `fn::{}` both become `{}`", other, name, rust_name; help = "change the `@rust_name` of one of them"),
+            }
+        }
+        let mut params = BTreeMap::new();
+        for (param, _) in &function.args {
+            let rust_name = function.param_name(param);
+            if let Some(other) = params.insert(rust_name.clone(), param) {
+                abort_call_site!("`${}` and `${}` of `fn::{}` both become `{}`", other, param, name, rust_name; help = "name one of them with `-- @rust_param ${} other_name`", param)
+            }
+        }
+    }
+
+    let mut tree = match args.modules {
+        Modules::Flat => return Function(functions, BTreeMap::new(), vec![]).to_tokens(args),
+        Modules::PerFile => {
+            let mut tree = Function::default();
+            for function in &functions {
+                let module = function.module_path(sources, args).remove(0);
+                tree.1.entry(module).or_default().0.push(function.clone());
+            }
+            tree
+        }
+        // Builds a tree of functions
+        Modules::Nested => Function::from(functions.clone()),
+    };
+    tree.document_modules(&functions);
+
+    tree.to_tokens(args)
+}
+
+/// Reports a file rejected by the official surrealdb parser, or parsed differently than by this crate, if `validate` was requested.
+///
+/// `parsed` holds the functions this crate's parser found, if it accepted the file.
+#[cfg_attr(not(feature = "validate"), allow(unused_variables))]
+fn validate_source(
+    args: &IncludeFnArgs,
+    origin: &Origin,
+    contents: &str,
+    parsed: Option<&[DefineFunctionStatement]>,
+) {
+    #[cfg(feature = "validate")]
+    if args.validate {
+        for e in validate::validate_file(&origin.to_string(), contents, parsed) {
+            proc_macro_error::emit_call_site_error!(e);
+        }
+    }
+}
+
+/// Accented Latin letters and the ASCII letters they are written with in Rust names, in lowercase.
+const TRANSLITERATIONS: &[(&str, &str)] = &[
+    ("àáâãäåāăą", "a"),
+    ("æ", "ae"),
+    ("çćĉċč", "c"),
+    ("ďđð", "d"),
+    ("èéêëēĕėęě", "e"),
+    ("ĝğġģ", "g"),
+    ("ĥħ", "h"),
+    ("ìíîïĩīĭįı", "i"),
+    ("ĵ", "j"),
+    ("ķ", "k"),
+    ("ĺļľŀł", "l"),
+    ("ñńņň", "n"),
+    ("òóôõöøōŏő", "o"),
+    ("œ", "oe"),
+    ("ŕŗř", "r"),
+    ("śŝşš", "s"),
+    ("ß", "ss"),
+    ("ţťŧ", "t"),
+    ("þ", "th"),
+    ("ùúûüũūŭůűų", "u"),
+    ("ŵ", "w"),
+    ("ýÿŷ", "y"),
+    ("źżž", "z"),
+];
+
+/// `name` with only ASCII letters, digits and `_`, as escaped SurrealQL names can hold anything.
+///
+/// Accented letters are transliterated, `é` becomes `e` and `Æ` becomes `Ae`, other ASCII characters become `_`,
+/// and a leading digit gets a `_` in front. Other letters, like `名前` or emoji, are dropped for a hash of the whole
+/// name, so names differing only by them stay apart, and the same name gets the same hash on every build.
+pub(crate) fn rust_segment(name: &str) -> String {
+    let mut out = String::new();
+    let mut dropped = false;
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            out.push(c);
+            continue;
+        }
+        let lower = c.to_lowercase().next().unwrap_or(c);
+        match TRANSLITERATIONS.iter().find(|(letters, _)| letters.contains(lower)) {
+            Some((_, ascii)) if c.is_uppercase() => {
+                out.push(ascii.as_bytes()[0].to_ascii_uppercase() as char);
+                out.push_str(&ascii[1..]);
+            }
+            Some((_, ascii)) => out.push_str(ascii),
+            None if c.is_ascii() => out.push('_'),
+            None => dropped = true,
+        }
+    }
+    if dropped {
+        // FNV-1a, which unlike `DefaultHasher` won't change with the compiler
+        let hash = name.bytes().fold(0x811c9dc5u32, |hash, byte| (hash ^ u32::from(byte)).wrapping_mul(0x01000193));
+        if !out.is_empty() && !out.ends_with('_') {
+            out.push('_');
+        }
+        out.push_str(&format!("u{hash:08x}"));
+    }
+    if !out.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        out.insert(0, '_');
+    }
+    out
+}
+
+/// Every statement stored in the database, along with the const holding it, named after its position.
+fn stored_statements(sources: &[SourceFile]) -> Vec<(Ident, &DefineFunctionStatement, String, bool)> {
+    sources
+        .iter()
+        .flat_map(SourceFile::stored_statements)
+        .enumerate()
+        .map(|(i, (function, text, wrapped))| (format_ident!("STATEMENT_{}", i), function, text, wrapped))
+        .collect()
+}
+
+/// Includes the files, so changes to them trigger a rebuild although only their statements are stored.
+fn tracked_files(sources: &[SourceFile]) -> TokenStream2 {
+    sources
+        .iter()
+        .filter_map(|source| match &source.origin {
+            Origin::File(path) => {
+                let path = path.to_str().unwrap();
+                Some(quote! { const _: & 'static str = ::std::include_str ! (#path) ; })
+            }
+            Origin::Url { .. } | Origin::Inline { .. } | Origin::Database { .. } => None,
+        })
+        .collect()
+}
+
+/// Notes for every change to the generated API since the `changelog` snapshot, if one was requested.
+fn api_changes(args: &IncludeFnArgs, functions: &[DefineFunctionStatement]) -> Vec<String> {
+    match &args.changelog {
+        Some(path) => match changelog::compare(path, functions) {
+            Ok(notes) => notes,
+            Err(e) => abort_call_site!("{}", e),
+        },
+        None => vec![],
+    }
+}
+
+/// Notes for the parameters and return values of a kind the wrappers can't type, taken as a `Value` instead.
+///
+/// Fails on the kinds SurrealDB doesn't accept for parameters at all, naming the parameter.
+fn kind_notes(functions: &[DefineFunctionStatement]) -> Vec<String> {
+    let mut notes = vec![];
+    for function in functions.iter().filter(|function| function.alias_of.is_none()) {
+        let name = function.name.join("::");
+        for (param, kind) in &function.args {
+            if let Kind::References(..) = literal::returned_item(kind) {
+                abort_call_site!("`${}` of `fn::{}` is of kind `{}`, which only fields can have", param.as_str(), name, kind; help = "take the record instead, like `${}: record`, and read the references from it", param.as_str())
+            }
+            if let Some(unknown) = kind.unknown() {
+                notes.push(format!("`${}` of `fn::{name}` has the kind `{kind}`, `{unknown}` is unknown to surrealdb_functions so it takes any `Value`", param.as_str()));
+            }
+        }
+        if let Some(unknown) = function.returns.as_ref().and_then(Kind::unknown) {
+            notes.push(format!("`fn::{name}` returns `{unknown}`, unknown to surrealdb_functions so `_value` returns a `Value`"));
+        }
+    }
+    notes
+}
+
+/// Surfaces messages as warnings on stable, by referring to a deprecated const.
+fn warnings(notes: &[String]) -> TokenStream2 {
+    notes
+        .iter()
+        .map(|note| {
+            quote! {
+                const _: () = {
+                    #[deprecated(note = #note)]
+                    #[allow(non_upper_case_globals)]
+                    const surql_note: () = ();
+                    surql_note
+                };
+            }
+        })
+        .collect()
+}
+
+/// Puts `#[cfg(feature = "...")]` on every item of `tokens`, for the flavors only generated when the invoking crate enables a feature.
+fn cfg_items(feature: Option<&str>, tokens: TokenStream2) -> TokenStream2 {
+    attributed_items(feature.map(|feature| quote! { #[cfg(feature = #feature)] }), tokens)
+}
+
+/// Puts `attributes` on every item of `tokens`.
+fn attributed_items(attributes: Option<TokenStream2>, tokens: TokenStream2) -> TokenStream2 {
+    let Some(attributes) = attributes else {
+        return tokens;
+    };
+    let file: syn::File = match syn::parse2(tokens) {
+        Ok(file) => file,
+        Err(e) => abort!(e.span(), "{}", e),
+    };
+    file.items
+        .iter()
+        .map(|item| quote! { #attributes #item })
+        .collect()
+}
+
+thread_local! {
+    /// Path of the surrealdb crate in the code generated by the current expansion, see `krate()`.
+    static KRATE: RefCell<Option<TokenStream2>> = const { RefCell::new(None) };
+}
+
+/// Path of the surrealdb crate the generated paths start with, `crate as <path>` or `::surrealdb` by default.
+fn krate() -> TokenStream2 {
+    KRATE.with(|krate| krate.borrow().clone()).unwrap_or_else(|| quote! { ::surrealdb })
+}
+
+/// Sets the path `krate()` returns for the rest of the expansion, for renamed or re-exported dependencies.
+fn set_krate(path: Option<&TokenStream2>) {
+    KRATE.with(|krate| *krate.borrow_mut() = path.cloned());
+}
+
+/// The text a statement is hashed as, ignoring line endings and trailing whitespace.
+fn normalized(text: &str) -> String {
+    text.trim().lines().map(|line| format!("{}\n", line.trim_end())).collect()
+}
+
+/// Feeds `text` to the FNV-1a `hash`, ignoring line endings and trailing whitespace.
+fn hash_text(hash: u64, text: &str) -> u64 {
+    normalized(text).bytes().fold(hash, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME))
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Expression hashing the stored statements compiled in with FNV-1a during constant evaluation, as a `&'static str`.
+///
+/// The statements of functions with a `@cfg` directive are left out of the list under the same `#[cfg(...)]`,
+/// so the hash only covers the functions compiled in, whatever the number of predicates.
+fn hash_expr(sources: &[SourceFile]) -> TokenStream2 {
+    let statements = sources.iter().flat_map(SourceFile::stored_statements).map(|(function, text, _)| {
+        let cfg = function.cfg();
+        let text = normalized(&text);
+        quote! { #cfg #text }
+    });
+    quote! {{
+        const STATEMENTS: &[&str] = &[#(#statements),*];
+        const HEX: [u8; 16] = {
+            let mut hash: u64 = #FNV_OFFSET;
+            let mut i = 0;
+            while i < STATEMENTS.len() {
+                let bytes = STATEMENTS[i].as_bytes();
+                let mut j = 0;
+                while j < bytes.len() {
+                    hash ^= bytes[j] as u64;
+                    hash = hash.wrapping_mul(#FNV_PRIME);
+                    j += 1;
                 }
+                i += 1;
+            }
+            let mut hex = [0u8; 16];
+            let mut i = 0;
+            while i < 16 {
+                hex[i] = b"0123456789abcdef"[((hash >> (60 - 4 * i)) & 0xf) as usize];
+                i += 1;
+            }
+            hex
+        };
+        match ::std::str::from_utf8(&HEX) {
+            ::std::result::Result::Ok(hash) => hash,
+            ::std::result::Result::Err(_) => ::std::panic!("the hash is ASCII"),
+        }
+    }}
+}
+
+/// `pub const` holding the hash of the functions compiled in.
+fn hash_consts(args: &IncludeFnArgs, sources: &[SourceFile]) -> TokenStream2 {
+    let (hash_const, _) = args.hash_names();
+    let hash = hash_expr(sources);
+    quote! {
+        #[doc = "Hash of the included functions, ignoring line endings and trailing whitespace."]
+        #[doc = ""]
+        #[doc = "Store it in the database with `store_functions_hash` after defining the functions, compare it to `functions_version` to know if they have to be defined again."]
+        pub const #hash_const: &str = #hash;
+    }
+}
+
+/// Expression formatting the query storing the `hash` of the functions in the database.
+fn define_hash(args: &IncludeFnArgs, hash: &TokenStream2) -> TokenStream2 {
+    let (_, hash_param) = args.hash_names();
+    let template = format!("DEFINE PARAM ${hash_param} VALUE \"{{}}\"");
+    quote! { ::std::format!(#template, #hash) }
+}
+
+/// The condition of a `-- @cfg(...)` directive, what goes between the parentheses of `#[cfg(...)]`.
+fn cfg_condition(predicate: &str) -> TokenStream2 {
+    match syn::parse_str::<syn::MetaList>(&format!("cfg{predicate}")) {
+        Ok(list) if matches!(list.delimiter, syn::MacroDelimiter::Paren(_)) => list.tokens,
+        _ => abort_call_site!(format!("invalid `@cfg{predicate}` directive"); help = "expected a predicate like `-- @cfg(feature = \"admin\")`"),
+    }
+}
+
+fn bootstrap_for_files(args: &IncludeFnArgs, sources: &[SourceFile]) -> TokenStream2 {
+    let krate = krate();
+    let id = args.function_id_name();
+    let tracked = tracked_files(sources);
+    let statements = stored_statements(sources).into_iter().map(|(name, function, _, _)| {
+        let cfg = function.cfg();
+        quote! { #cfg #id::#name }
+    });
+
+    let (driver, datastore) = args.transform_fn_name("define_functions");
+    let stored = args.stored_functions_name();
+    let (hash_const, hash_param) = args.hash_names();
+    let hash_consts = hash_consts(args, sources);
+    let define_hash = define_hash(args, &quote! { #hash_const });
+
+    let mut tokens = quote! {
+        #[doc = "Returns a string containing all the functions defined in the included files."]
+        pub fn #stored() -> ::std::string::String {
+            #tracked
+            let statements: &[&str] = &[#(#statements),*];
+            statements.join("\n")
+        }
+
+        #hash_consts
+    };
+    tokens.extend(stored_function_items(args, sources));
+    tokens.extend(function_id_items(args, sources));
+    tokens.extend(define_report_items(args, sources));
+
+    if let Some(name) = driver {
+        let (version, _) = args.transform_fn_name("functions_version");
+        let version = version.unwrap();
+        let query = format!("RETURN ${hash_param}");
+        let generics = args.engine.generics();
+        let connection = args.engine.connection();
+        let define = if let Some(define) = parallel_define(args, sources) {
+            quote! { #define.await }
+        } else {
+            quote! { db.query(#stored()).await }
+        };
+        let (store, _) = args.transform_fn_name("store_functions_hash");
+        let driver = quote! {
+            #[doc = "Defines all the functions using the provided connection."]
+            pub async fn #name #generics(db: &#krate::Surreal<#connection>) -> #krate::Result<#krate::Response> {
+                #define
+            }
+
+            #[doc = "Stores the hash of the included functions in the database, to read it back with `functions_version`."]
+            pub async fn #store #generics(db: &#krate::Surreal<#connection>) -> #krate::Result<#krate::Response> {
+                db.query(#define_hash).await
+            }
+
+            #[doc = "The hash of the functions last defined in the database, if they were defined by this crate."]
+            pub async fn #version #generics(db: &#krate::Surreal<#connection>) -> #krate::Result<::std::option::Option<::std::string::String>> {
+                db.query(#query).await?.check()?.take(0)
+            }
+        };
+        tokens.extend(cfg_items(args.driver_cfg.as_deref(), driver));
+    }
+
+    if let (Some(name), true) = (args.driver.as_ref(), cfg!(feature = "runtime-reload")) {
+        let name = Ident::new(&name.transform("load_functions_from"), Span::call_site());
+        let generics = args.engine.generics();
+        let connection = args.engine.connection();
+        let signatures = source::functions_of(sources)
+            .into_iter()
+            .filter(|function| function.alias_of.is_none())
+            .map(|function| {
+                let signature = function.signature();
+                let cfg = function.cfg();
+                quote! { #cfg #signature }
             });
+        let reload = quote! {
+            #[doc = "Defines the functions from the .surql files at `path`, read at runtime so they can change without recompiling."]
+            #[doc = ""]
+            #[doc = "Nothing is defined if a function the generated wrappers call went missing or its signature changed."]
+            pub async fn #name #generics(path: impl ::std::convert::AsRef<::std::path::Path>, db: &#krate::Surreal<#connection>) -> ::std::result::Result<#krate::Response, ::surrealdb_functions_runtime::LoadError> {
+                ::surrealdb_functions_runtime::load_functions_from(path, db, &[#(#signatures),*]).await
+            }
+        };
+        tokens.extend(cfg_items(args.driver_cfg.as_deref(), reload));
+    }
+
+    if let Some(name) = datastore {
+        let (_, store) = args.transform_fn_name("store_functions_hash");
+        let datastore = quote! {
+            #[doc = "Defines all the functions using the provided datastore and session."]
+            pub async fn #name(ds: &#krate::kvs::Datastore, session: &#krate::dbs::Session) -> ::std::result::Result<::std::vec::Vec<#krate::dbs::Response>, #krate::err::Error> {
+                ds.execute(&#stored(), session, ::std::option::Option::None).await
+            }
+
+            #[doc = "Stores the hash of the included functions using the provided datastore and session."]
+            pub async fn #store(ds: &#krate::kvs::Datastore, session: &#krate::dbs::Session) -> ::std::result::Result<::std::vec::Vec<#krate::dbs::Response>, #krate::err::Error> {
+                ds.execute(&#define_hash, session, ::std::option::Option::None).await
+            }
+        };
+        tokens.extend(cfg_items(args.datastore_cfg.as_deref(), datastore));
+    }
+
+    if let Some(name) = args.blocking_fn_name("define_functions") {
+        let runtime = args.blocking_runtime.params();
+        let generics = args.engine.generics();
+        let connection = args.engine.connection();
+        let call = match parallel_define(args, sources) {
+            Some(define) => args.blocking_runtime.block_on(define),
+            None => args.blocking_runtime.block_on(quote! { db.query(#stored()) }),
+        };
+        let store = args.blocking_fn_name("store_functions_hash").unwrap();
+        let store_call = args.blocking_runtime.block_on(quote! { db.query(#define_hash) });
+        let blocking = quote! {
+            #[doc = "Defines all the functions using the provided connection, blocking the current thread until done."]
+            pub fn #name #generics(#runtime db: &#krate::Surreal<#connection>) -> #krate::Result<#krate::Response> {
+                #call
+            }
+
+            #[doc = "Stores the hash of the included functions using the provided connection, blocking the current thread until done."]
+            pub fn #store #generics(#runtime db: &#krate::Surreal<#connection>) -> #krate::Result<#krate::Response> {
+                #store_call
+            }
+        };
+        tokens.extend(cfg_items(args.blocking_cfg.as_deref(), blocking));
+    }
+
+    tokens
+}
+
+/// With `define as parallel(n)`, a future sending every stored statement in its own query, `n` at once.
+///
+/// Fails on the first statement that fails, dropping the queries still running. Resolves to the response of the statement that finished last.
+fn parallel_define(args: &IncludeFnArgs, sources: &[SourceFile]) -> Option<TokenStream2> {
+    let Define::Parallel(limit) = args.define else {
+        return None;
+    };
+    let id = args.function_id_name();
+    let tracked = tracked_files(sources);
+    let statements = stored_statements(sources).into_iter().map(|(name, function, _, _)| {
+        let cfg = function.cfg();
+        quote! { #cfg #id::#name }
+    });
+    let stored = args.stored_functions_name();
+    Some(quote! {
+        async {
+            #tracked
+            let statements: &[&str] = &[#(#statements),*];
+            let queries = statements.iter().map(|statement| ::std::future::IntoFuture::into_future(db.query(*statement)));
+            let mut responses = ::futures::stream::StreamExt::buffer_unordered(::futures::stream::iter(queries), #limit);
+            let mut last = ::std::option::Option::None;
+            while let ::std::option::Option::Some(response) = ::futures::stream::StreamExt::next(&mut responses).await {
+                last = ::std::option::Option::Some(response?.check()?);
+            }
+            match last {
+                ::std::option::Option::Some(response) => ::std::result::Result::Ok(response),
+                ::std::option::Option::None => db.query(#stored()).await,
+            }
+        }
+    })
+}
+
+/// Generates the enum of the stored functions, and the functions defining a single one of them.
+fn function_id_items(args: &IncludeFnArgs, sources: &[SourceFile]) -> TokenStream2 {
+    let krate = krate();
+    let id = args.function_id_name();
+    let functions: Vec<_> = sources.iter().flat_map(|source| source.functions.iter().map(|(_, function)| function)).collect();
+    let cfgs: Vec<_> = functions.iter().map(|function| function.cfg()).collect();
+    let variants: Vec<_> = functions.iter().map(|function| rust_ident(&literal::camel_case(&function.rust_path().join("_")))).collect();
+    let names = functions.iter().map(|function| format!("fn::{}", function.name.join("::")));
+    let statements = stored_statements(sources);
+    let consts = statements.iter().map(|(name, function, text, _)| {
+        let cfg = function.cfg();
+        quote! { #cfg const #name: &'static str = #text; }
+    });
+    // Definitions left without a wrapper are stored, but have no variant
+    let wrapped: Vec<_> = statements.iter().filter(|(_, _, _, wrapped)| *wrapped).collect();
+    let texts = wrapped.iter().map(|(name, _, _, _)| name);
+    let hashes = wrapped.iter().map(|(_, _, text, _)| format!("{:016x}", hash_text(FNV_OFFSET, text)));
+    let docs = functions.iter().map(|function| format!("`fn::{}`", function.name.join("::")));
+    for (i, variant) in variants.iter().enumerate() {
+        if let Some(other) = variants[..i].iter().position(|other| other == variant) {
+            abort_call_site!("`fn::{}` and `fn::{}` both become `{}::{}`", functions[other].name.join("::"), functions[i].name.join("::"), id, variant; help = "rename one of the functions")
+        }
+    }
+    let mut tokens = quote! {
+        #[doc = "One of the included functions, to define it on its own."]
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::marker::Copy, ::std::cmp::PartialEq, ::std::cmp::Eq, ::std::hash::Hash)]
+        pub enum #id {
+            #(#cfgs #[doc = #docs] #variants,)*
+        }
+
+        impl #id {
+            #(#consts)*
+
+            #[doc = "Every included function, in the order they're stored."]
+            pub const ALL: &'static [#id] = &[#(#cfgs #id::#variants),*];
+
+            #[doc = "The name of the function, like `fn::greet`."]
+            pub fn name(self) -> &'static str {
+                match self {
+                    #(#cfgs #id::#variants => #names,)*
+                }
+            }
+
+            #[doc = "The `DEFINE FUNCTION` statement of the function."]
+            pub fn definition(self) -> &'static str {
+                match self {
+                    #(#cfgs #id::#variants => Self::#texts,)*
+                }
+            }
+
+            #[doc = "Hash of the statement of the function, ignoring line endings and trailing whitespace."]
+            pub fn hash(self) -> &'static str {
+                match self {
+                    #(#cfgs #id::#variants => #hashes,)*
+                }
+            }
+        }
+    };
+
+    let (driver, datastore) = args.transform_fn_name("define_function");
+    if let Some(name) = driver {
+        let generics = args.engine.generics();
+        let connection = args.engine.connection();
+        let driver = quote! {
+            #[doc = "Defines a single function using the provided connection, like after changing only that one."]
+            #[doc = ""]
+            #[doc = "The hash of the functions isn't updated, it still stands for the ones last defined together."]
+            pub async fn #name #generics(db: &#krate::Surreal<#connection>, function: #id) -> #krate::Result<#krate::Response> {
+                db.query(function.definition()).await
+            }
+        };
+        tokens.extend(cfg_items(args.driver_cfg.as_deref(), driver));
+    }
+    if let Some(name) = datastore {
+        let datastore = quote! {
+            #[doc = "Defines a single function using the provided datastore and session."]
+            pub async fn #name(ds: &#krate::kvs::Datastore, session: &#krate::dbs::Session, function: #id) -> ::std::result::Result<::std::vec::Vec<#krate::dbs::Response>, #krate::err::Error> {
+                ds.execute(function.definition(), session, ::std::option::Option::None).await
+            }
+        };
+        tokens.extend(cfg_items(args.datastore_cfg.as_deref(), datastore));
+    }
+    if let Some(name) = args.blocking_fn_name("define_function") {
+        let runtime = args.blocking_runtime.params();
+        let generics = args.engine.generics();
+        let connection = args.engine.connection();
+        let call = args.blocking_runtime.block_on(quote! { db.query(function.definition()) });
+        let blocking = quote! {
+            #[doc = "Defines a single function using the provided connection, blocking the current thread until done."]
+            pub fn #name #generics(#runtime db: &#krate::Surreal<#connection>, function: #id) -> #krate::Result<#krate::Response> {
+                #call
+            }
+        };
+        tokens.extend(cfg_items(args.blocking_cfg.as_deref(), blocking));
+    }
+    tokens
+}
+
+/// Generates the type reporting the outcome of each stored statement, and the `define_functions_report` functions returning it.
+///
+/// The functions run the same query as `define_functions`, each statement of which has its own result in the response.
+fn define_report_items(args: &IncludeFnArgs, sources: &[SourceFile]) -> TokenStream2 {
+    let krate = krate();
+    let id = args.function_id_name();
+    let report = args.define_report_name();
+    let stored = args.stored_functions_name();
+    let names: Vec<_> = stored_statements(sources)
+        .into_iter()
+        .map(|(_, function, _, _)| {
+            let cfg = function.cfg();
+            let name = format!("fn::{}", function.name.join("::"));
+            quote! { #cfg #name }
+        })
+        .collect();
+    let mut tokens = quote! {
+        #[doc = "What defining the included functions resulted in, for each `DEFINE FUNCTION` statement, to report exactly which ones failed."]
+        #[derive(::std::fmt::Debug)]
+        pub struct #report<E> {
+            #[doc = "The name of every stored function, like `fn::greet`, and the result of its statement, in the order they're stored."]
+            pub functions: ::std::vec::Vec<(&'static str, ::std::result::Result<(), E>)>,
+        }
+
+        impl<E> #report<E> {
+            #[allow(dead_code)]
+            fn new(names: &[&'static str], mut result: impl FnMut(usize) -> ::std::result::Result<(), E>) -> Self {
+                Self { functions: names.iter().enumerate().map(|(i, name)| (*name, result(i))).collect() }
+            }
+
+            #[doc = "Whether every function was defined."]
+            pub fn is_ok(&self) -> bool {
+                self.functions.iter().all(|(_, result)| result.is_ok())
+            }
+
+            #[doc = "The functions that failed to be defined, along with their error."]
+            pub fn failed(&self) -> impl ::std::iter::Iterator<Item = (&'static str, &E)> + '_ {
+                self.functions.iter().filter_map(|(name, result)| result.as_ref().err().map(|error| (*name, error)))
+            }
+
+            #[doc = "The result of defining a single function, named with or without `fn::`."]
+            pub fn get(&self, name: &str) -> ::std::option::Option<&::std::result::Result<(), E>> {
+                let name = name.strip_prefix("fn::").unwrap_or(name);
+                self.functions.iter().find(|(function, _)| function["fn::".len()..] == *name).map(|(_, result)| result)
+            }
+
+            #[doc = "The result of defining the function of a variant."]
+            pub fn of(&self, function: #id) -> ::std::option::Option<&::std::result::Result<(), E>> {
+                self.get(function.name())
+            }
+        }
+    };
+
+    let (driver, datastore) = args.transform_fn_name("define_functions_report");
+    if let Some(name) = driver {
+        let generics = args.engine.generics();
+        let connection = args.engine.connection();
+        let driver = quote! {
+            #[doc = "Defines all the functions like `define_functions`, returning the outcome of each definition instead of a single `Response`."]
+            #[doc = ""]
+            #[doc = "Only fails if the query couldn't be sent, the functions that failed to be defined are in the report."]
+            pub async fn #name #generics(db: &#krate::Surreal<#connection>) -> #krate::Result<#report<#krate::Error>> {
+                let names: &[&'static str] = &[#(#names),*];
+                let mut errors = db.query(#stored()).await?.take_errors();
+                ::std::result::Result::Ok(#report::new(names, |i| errors.remove(&i).map_or(::std::result::Result::Ok(()), ::std::result::Result::Err)))
+            }
+        };
+        tokens.extend(cfg_items(args.driver_cfg.as_deref(), driver));
+    }
+    if let Some(name) = datastore {
+        let datastore = quote! {
+            #[doc = "Defines all the functions like `define_functions`, returning the outcome of each definition instead of the responses."]
+            pub async fn #name(ds: &#krate::kvs::Datastore, session: &#krate::dbs::Session) -> ::std::result::Result<#report<#krate::err::Error>, #krate::err::Error> {
+                let names: &[&'static str] = &[#(#names),*];
+                let mut responses = ::std::iter::Iterator::map(ds.execute(&#stored(), session, ::std::option::Option::None).await?.into_iter(), |response| response.result);
+                ::std::result::Result::Ok(#report::new(names, |_| responses.next().map_or(::std::result::Result::Ok(()), |result| result.map(|_| ()))))
+            }
+        };
+        tokens.extend(cfg_items(args.datastore_cfg.as_deref(), datastore));
+    }
+    tokens
+}
+
+/// Generates the functions listing the names of the stored functions and returning the statement of a single one.
+fn stored_function_items(args: &IncludeFnArgs, sources: &[SourceFile]) -> TokenStream2 {
+    let (names, single) = args.stored_function_names();
+    let stored = args.stored_functions_name();
+    let id = args.function_id_name();
+    let functions = sources.iter().flat_map(|source| source.functions.iter().map(|(_, function)| function));
+    let listed = functions.map(|function| {
+        let cfg = function.cfg();
+        let name = format!("fn::{}", function.name.join("::"));
+        quote! { #cfg #name }
+    });
+    let names_doc = format!("Names of the functions in `{stored}()`, like `fn::greet`, in the order they're stored.");
+    let single_doc = format!("The `DEFINE FUNCTION` statement of a single function in `{stored}()`, named with or without `fn::`.");
+    quote! {
+        #[doc = #names_doc]
+        pub fn #names() -> &'static [&'static str] {
+            &[#(#listed),*]
+        }
+
+        #[doc = #single_doc]
+        pub fn #single(name: &str) -> ::std::option::Option<&'static str> {
+            let name = name.strip_prefix("fn::").unwrap_or(name);
+            #id::ALL.iter().find(|function| function.name()["fn::".len()..] == *name).map(|function| function.definition())
+        }
+    }
+}
+
+fn add_path_if_surql(path: &Path, out: &mut Vec<PathBuf>) -> Result<(), Box<dyn Error>> {
+    if path.extension().unwrap_or_default() == "surql" {
+        out.push(path.to_path_buf());
+    }
+    Ok(())
+}
+
+fn expand_path(path: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut out = vec![];
+
+    if path.is_dir() {
+        file::track_dir(path);
+        // Sorted so the output doesn't depend on the filesystem
+        let mut entries = path
+            .read_dir()?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort();
+        for path in entries {
+            if path.is_dir() {
+                out.append(&mut expand_path(&path)?);
+            } else {
+                add_path_if_surql(&path, &mut out)?;
+            }
         }
+    } else {
+        add_path_if_surql(path, &mut out)?;
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_tree() {
+        let args = IncludeFnArgs {
+            includes: vec![Origin::File(PathBuf::from("tests/main.surql"))],
+            driver: Some(Alias::AsIs),
+            datastore: Some(Alias::AsIs),
+            driver_cfg: None,
+            datastore_cfg: None,
+            datastore_raw: false,
+            blocking: None,
+            blocking_runtime: BlockingRuntime::Futures,
+            blocking_cfg: None,
+            trait_name: None,
+            dispatch: false,
+            fragments: false,
+            batch: false,
+            paged: false,
+            stats: false,
+            targets: false,
+            tower: false,
+            http: None,
+            grpc: None,
+            graphql: false,
+            instrument: false,
+            metrics: false,
+            tests: false,
+            geo: false,
+            uuid: false,
+            types: TypeMap::default(),
+            chrono: false,
+            checked_args: false,
+            time: false,
+            retry: None,
+            define: Define::Query,
+            timeout: None,
+            wasm: false,
+            migrations: false,
+            verify: false,
+            sync: false,
+            metadata: false,
+            export_schema: false,
+            validate: false,
+            duplicates: Duplicates::Error,
+            continue_on_error: false,
+            modules: Modules::Nested,
+            bindings: Bindings::Typed,
+            docs: Docs::Signature,
+            examples: Examples::Run,
+            name_version: None,
+            stored: None,
+            module: None,
+            changelog: None,
+            version: SurrealVersion::V1,
+            crate_path: None,
+            engine: Engine::Generic,
+            options: vec![],
+        };
+        let (sources, _) = source::load_sources(&args).unwrap();
+        let _ = Function::from(source::functions_of(&sources));
+    }
+
+    #[test]
+    fn test_flat_modules() {
+        let mut args: IncludeFnArgs =
+            syn::parse_str(r#"driver as is; modules as flat; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert_eq!(args.modules, Modules::Flat);
+        let (sources, _) = source::load_sources(&args).unwrap();
+        let tokens = build_mod_tree(source::functions_of(&sources), &sources, &args).to_string();
+        assert!(tokens.contains("pub async fn nested_greet <"), "{tokens}");
+        assert!(!tokens.contains("pub mod"), "{tokens}");
+
+        args.modules = Modules::Nested;
+        let tokens = build_mod_tree(source::functions_of(&sources), &sources, &args).to_string();
+        assert!(tokens.contains("pub mod nested"), "{tokens}");
+    }
+
+    #[test]
+    fn test_stored_function() {
+        let mut args: IncludeFnArgs = syn::parse_str(r#"driver as is; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        let (sources, _) = source::load_sources(&args).unwrap();
+        let tokens = stored_function_items(&args, &sources).to_string();
+        assert!(tokens.contains(r#"& ["fn::greet_but_with_number" , "fn::nested::greet"]"#), "{tokens}");
+        let definition = "DEFINE FUNCTION fn::nested::greet($name: string) {\n    RETURN \"Hello, \" + $name + \"!\";\n};";
+        assert!(!tokens.contains("greet_nested"), "{tokens}");
+        let tokens = function_id_items(&args, &sources).to_string();
+        assert!(tokens.contains(&quote! { const STATEMENT_1: &'static str = #definition; }.to_string()), "{tokens}");
+        assert!(tokens.contains(&quote! { FunctionId::NestedGreet => Self::STATEMENT_1, }.to_string()), "{tokens}");
+
+        let names = |args: &IncludeFnArgs| {
+            let (names, single) = args.stored_function_names();
+            (names.to_string(), single.to_string())
+        };
+        assert_eq!(names(&args), ("stored_function_names".to_string(), "stored_function".to_string()));
+        args.stored = Some(format_ident!("admin_functions"));
+        assert_eq!(names(&args), ("admin_function_names".to_string(), "admin_function".to_string()));
+        args.stored = Some(format_ident!("auth"));
+        assert_eq!(names(&args), ("auth_function_names".to_string(), "auth_function".to_string()));
+    }
+
+    #[test]
+    fn test_function_id() {
+        let mut args: IncludeFnArgs = syn::parse_str(r#"driver as is; datastore as ds_$; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        let (sources, _) = source::load_sources(&args).unwrap();
+        let tokens = function_id_items(&args, &sources).to_string();
+        assert!(tokens.contains("pub enum FunctionId { # [doc = \"`fn::greet_but_with_number`\"] GreetButWithNumber , # [doc = \"`fn::nested::greet`\"] NestedGreet , }"), "{tokens}");
+        assert!(tokens.contains("FunctionId :: NestedGreet => \"fn::nested::greet\""), "{tokens}");
+        assert!(tokens.contains("pub async fn define_function < C : :: surrealdb :: Connection > (db : & :: surrealdb :: Surreal < C > , function : FunctionId)"), "{tokens}");
+        assert!(tokens.contains("pub async fn ds_define_function (ds"), "{tokens}");
+
+        args.stored = Some(format_ident!("admin_stored_functions"));
+        assert_eq!(args.function_id_name(), "AdminStoredFunctionId");
+    }
+
+    #[test]
+    fn test_define_report() {
+        let mut args: IncludeFnArgs = syn::parse_str(r#"driver as is; datastore as ds_$; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        let (sources, _) = source::load_sources(&args).unwrap();
+        let tokens = define_report_items(&args, &sources).to_string();
+        assert!(tokens.contains("pub struct DefineReport < E >"), "{tokens}");
+        assert!(tokens.contains("pub fn of (& self , function : FunctionId)"), "{tokens}");
+        assert!(tokens.contains("-> :: surrealdb :: Result < DefineReport < :: surrealdb :: Error >"), "{tokens}");
+        assert!(tokens.contains("let names : & [& 'static str] = & [\"fn::greet_but_with_number\" , \"fn::nested::greet\"] ;"), "{tokens}");
+        assert!(tokens.contains("pub async fn ds_define_functions_report (ds"), "{tokens}");
+        assert!(tokens.contains(". take_errors ()"), "{tokens}");
+
+        args.stored = Some(format_ident!("admin_stored_functions"));
+        assert_eq!(args.define_report_name(), "AdminStoredFunctionDefineReport");
+    }
+
+    #[test]
+    fn test_parallel_define() {
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; blocking as $_blocking; define as parallel(8); "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert_eq!(args.define, Define::Parallel(8));
+        let (sources, _) = source::load_sources(&args).unwrap();
+        let tokens = bootstrap_for_files(&args, &sources).to_string();
+        assert!(tokens.contains("let statements : & [& str] = & [FunctionId :: STATEMENT_0 , FunctionId :: STATEMENT_1] ;"), "{tokens}");
+        assert_eq!(tokens.matches(":: futures :: stream :: StreamExt :: buffer_unordered (:: futures :: stream :: iter (queries) , 8usize)").count(), 2, "{tokens}");
+        assert!(tokens.contains("last = :: std :: option :: Option :: Some (response ? . check () ?) ;"), "{tokens}");
+        assert!(!tokens.contains(":: surrealdb :: Response > { db . query (stored_functions ())"), "{tokens}");
+
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert_eq!(args.define, Define::Query);
+        let tokens = bootstrap_for_files(&args, &sources).to_string();
+        assert!(!tokens.contains("buffer_unordered"), "{tokens}");
+        assert!(tokens.contains(":: surrealdb :: Response > { db . query (stored_functions ()) . await }"), "{tokens}");
+        // The hash is only stored when asked to
+        assert_eq!(tokens.matches("DEFINE PARAM $functions_hash VALUE").count(), 1, "{tokens}");
+        assert!(tokens.contains("pub async fn store_functions_hash < C : :: surrealdb :: Connection > (db : & :: surrealdb :: Surreal < C >) -> :: surrealdb :: Result < :: surrealdb :: Response > { db . query (:: std :: format ! (\"DEFINE PARAM $functions_hash VALUE \\\"{}\\\"\" , FUNCTIONS_HASH)) . await }"), "{tokens}");
+    }
 
-        if let Some(name) = datastore {
-            let bind = self.params_to_variables();
-            tokens.extend(quote! {
-                #comments
-                pub async fn #name(ds: &::surrealdb::kvs::Datastore, session: &::surrealdb::dbs::Session, #args) -> Result<Vec<::surrealdb::dbs::Response>, ::surrealdb::err::Error> {
-                    #bind
-                    ds.execute(#query, session, Some(variables)).await
-                }
-            });
+    #[test]
+    fn test_export_dump() {
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; "$CARGO_MANIFEST_DIR/tests/export.surql""#).unwrap();
+        let (sources, _) = source::load_sources(&args).unwrap();
+        let statements = stored_statements(&sources);
+        assert_eq!(statements.len(), 3);
+        assert!(statements.iter().all(|(_, _, text, _)| text.starts_with("DEFINE FUNCTION") && !text.contains("DEFINE TABLE")));
+        let tokens = build_mod_tree(source::functions_of(&sources), &sources, &args).to_string();
+        assert!(tokens.contains("pub async fn active <"), "{tokens}");
+    }
+
+    #[test]
+    fn test_sync() {
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; sync; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert!(args.sync);
+        let (sources, _) = source::load_sources(&args).unwrap();
+        let statements = stored_statements(&sources);
+        let hash = format!("{:016x}", hash_text(FNV_OFFSET, &statements[0].2));
+        assert_eq!(hash, format!("{:016x}", hash_text(FNV_OFFSET, &statements[0].2.replace('\n', "  \r\n"))));
+        let tokens = function_id_items(&args, &sources).to_string();
+        assert!(tokens.contains(&format!("FunctionId :: GreetButWithNumber => \"{hash}\"")), "{tokens}");
+
+        let tokens = migration::sync_function(&args).to_string();
+        assert!(tokens.contains("pub async fn sync_functions < C : :: surrealdb :: Connection > (db : & :: surrealdb :: Surreal < C >) -> :: surrealdb :: Result < SyncSummary >"), "{tokens}");
+        assert!(tokens.contains("for function in FunctionId :: ALL"), "{tokens}");
+        assert!(!tokens.contains("DEFINE PARAM"), "{tokens}");
+    }
+
+    #[test]
+    fn test_per_file_modules() {
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; modules as per_file; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert_eq!(args.modules, Modules::PerFile);
+        let (sources, _) = source::load_sources(&args).unwrap();
+        let functions = source::functions_of(&sources);
+        assert_eq!(sources[0].module_name(), "main");
+        assert_eq!(functions[1].module_path(&sources, &args), vec!["main"]);
+        // Aliases are generated next to the function they call
+        assert_eq!(functions.last().unwrap().module_path(&sources, &args), vec!["main"]);
+        let tokens = build_mod_tree(functions, &sources, &args).to_string();
+        assert!(tokens.starts_with("pub mod main {"), "{tokens}");
+        assert!(tokens.contains("pub async fn nested_greet <"), "{tokens}");
+        assert!(tokens.contains("pub async fn greet_but_with_number <"), "{tokens}");
+        assert!(!tokens.contains("pub mod nested"), "{tokens}");
+        assert!(tokens.contains("pub const NESTED_GREET_FN : & str = \"fn::nested::greet\""), "{tokens}");
+        // Aliases don't get one, their name isn't defined in the database
+        assert!(!tokens.contains("GREET_NESTED_FN"), "{tokens}");
+    }
+
+    #[test]
+    fn test_roots() {
+        let args: IncludeFnArgs = syn::parse_str(
+            r#"driver as is; roots ["/nonexistent", $WORKSPACE_ROOT, $CARGO_MANIFEST_DIR]; "tests/main.surql""#,
+        )
+        .unwrap();
+        let expected = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/main.surql");
+        assert_eq!(args.includes, vec![Origin::File(expected)]);
+    }
+
+    #[test]
+    fn test_env_paths() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests");
+        let args: IncludeFnArgs = syn::parse_str(
+            r#"driver as is; if env "CARGO_PKG_NAME" == "surrealdb_functions" { "$CARGO_MANIFEST_DIR/tests/main.surql"; "$CARGO_MANIFEST_DIR/tests/auth.surql" } else { "$CARGO_MANIFEST_DIR/tests/slow.surql" }"#,
+        )
+        .unwrap();
+        assert_eq!(args.includes, vec![Origin::File(dir.join("main.surql")), Origin::File(dir.join("auth.surql"))]);
+
+        let args: IncludeFnArgs = syn::parse_str(
+            r#"driver as is; if env "SURREALDB_FUNCTIONS_UNSET" == "" { "tests/auth.surql" } else if env "SURREALDB_FUNCTIONS_UNSET" != "prod" { "$CARGO_MANIFEST_DIR/tests/slow.surql" }; "$CARGO_MANIFEST_DIR/tests/main.surql""#,
+        )
+        .unwrap();
+        assert_eq!(args.includes, vec![Origin::File(dir.join("slow.surql")), Origin::File(dir.join("main.surql"))]);
+    }
+
+    #[test]
+    fn test_inline() {
+        let args: IncludeFnArgs = syn::parse_str(
+            r##"driver as is; modules as per_file; inline r#"DEFINE FUNCTION fn::tiny($x: int) { RETURN $x + "}"; };"#; "$CARGO_MANIFEST_DIR/tests/main.surql"; inline "DEFINE FUNCTION fn::other() { RETURN 1; };""##,
+        )
+        .unwrap();
+        assert_eq!(args.includes[0], Origin::Inline { index: 1, sql: r#"DEFINE FUNCTION fn::tiny($x: int) { RETURN $x + "}"; };"#.to_string() });
+        assert_eq!(args.includes[2].to_string(), "inline #2");
+        let (sources, _) = source::load_sources(&args).unwrap();
+        assert_eq!(sources[0].stored_statements()[0].1, r#"DEFINE FUNCTION fn::tiny($x: int) { RETURN $x + "}"; };"#);
+        assert_eq!(sources[2].module_name(), "inline_2");
+        let tokens = include_fn_impl(args).to_string();
+        assert!(tokens.contains("pub mod inline_1"), "{tokens}");
+        assert!(tokens.contains("RETURN fn::tiny($__fnarg_x)"), "{tokens}");
+    }
+
+    #[test]
+    fn test_datastore_raw() {
+        let args: IncludeFnArgs =
+            syn::parse_str(r#"datastore as ds_$ raw; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert_eq!(args.datastore, Some(Alias::Prefix("ds_".to_string())));
+        assert!(args.datastore_raw);
+
+        let args: IncludeFnArgs =
+            syn::parse_str(r#"datastore as $_ds; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert_eq!(args.datastore, Some(Alias::Suffix("_ds".to_string())));
+        assert!(!args.datastore_raw);
+    }
+
+    #[test]
+    fn test_unicode_names() {
+        let sql = "DEFINE FUNCTION fn::⟨名前⟩::⟨挨拶👋⟩($⟨名前⟩: string, $⟨名字⟩: string, $⟨🎉⟩: bool, $⟨café⟩: int) { RETURN 1; };\nDEFINE FUNCTION fn::⟨名前⟩::⟨🎉⟩() { RETURN 2; };";
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        assert_eq!(
+            functions[0].custom_function_query(),
+            "RETURN fn::`名前`::`挨拶👋`($`__fnarg_名前`, $`__fnarg_名字`, $`__fnarg_🎉`, $`__fnarg_café`)"
+        );
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; fragments; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        let tokens = build_mod_tree(functions, &[], &args);
+        if let Err(e) = syn::parse2::<syn::File>(tokens.clone()) {
+            panic!("{e}: {tokens}");
         }
-        tokens
+        let tokens = tokens.to_string();
+        let (given, family) = (rust_segment("名前"), rust_segment("名字"));
+        assert!(tokens.contains(&format!("pub mod {given} {{")), "{tokens}");
+        assert!(tokens.contains(&format!("pub async fn {} <", rust_segment("挨拶👋"))), "{tokens}");
+        assert!(tokens.contains(&format!("pub async fn {} <", rust_segment("🎉"))), "{tokens}");
+        assert!(tokens.contains(&format!("{given} : impl")), "{tokens}");
+        assert!(tokens.contains(&format!("{family} : impl")), "{tokens}");
+        assert!(tokens.contains(&format!("{} : impl", rust_segment("🎉"))), "{tokens}");
+        assert!(tokens.contains("cafe : impl"), "{tokens}");
+        assert!(tokens.contains(". bind ((\"__fnarg_名前\" ,"), "{tokens}");
+        // The original names stay in the documentation
+        assert!(tokens.contains("DEFINE FUNCTION fn::`名前`::`挨拶👋`"), "{tokens}");
+
+        assert!(given.starts_with('u') && given.len() == 9, "{given}");
+        assert_ne!(given, family);
+        assert_eq!(rust_segment("Æsir-Øl"), "Aesir_Ol");
+        assert_eq!(rust_segment("1st"), "_1st");
+        assert!(rust_segment("greet_👋").starts_with("greet_u"));
+        assert!(rust_segment("café☕").starts_with("cafe_u"));
     }
-}
 
-fn build_mod_tree(args: &IncludeFnArgs) -> Result<TokenStream2, Box<dyn Error>> {
-    // Takes a list of files, parses them for functions
-    let functions = parse_surrealql_files(args)?;
+    #[test]
+    fn test_blocking() {
+        let args: IncludeFnArgs =
+            syn::parse_str(r#"blocking as sync_$; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert_eq!(args.blocking, Some(Alias::Prefix("sync_".to_string())));
+        assert_eq!(args.blocking_runtime, BlockingRuntime::Futures);
+        assert_eq!(args.blocking_fn_name("greet").unwrap(), "sync_greet");
+
+        let args: IncludeFnArgs = syn::parse_str(
+            r#"driver as is; blocking as $_sync tokio if "sync"; "$CARGO_MANIFEST_DIR/tests/main.surql""#,
+        )
+        .unwrap();
+        assert_eq!(args.blocking_runtime, BlockingRuntime::Tokio);
+        assert_eq!(args.blocking_cfg.as_deref(), Some("sync"));
+        assert_eq!(args.blocking_fn_name("greet").unwrap(), "greet_sync");
+    }
 
-    // Builds a tree of functions
-    let functions = Function::from(functions);
+    #[test]
+    fn test_parse_duration() {
+        let parse = |text: &str| parse_duration(&syn::LitStr::new(text, Span::call_site()));
+        assert_eq!(parse("100ms").unwrap(), Duration::from_millis(100));
+        assert_eq!(parse("5s").unwrap(), Duration::from_secs(5));
+        assert_eq!(parse("1m30s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse("1h").unwrap(), Duration::from_secs(3600));
+        assert_eq!(parse("250us").unwrap(), Duration::from_micros(250));
+        for invalid in ["", "5", "ms", "5 s", "5x", "-5s"] {
+            assert!(parse(invalid).is_err(), "{invalid}");
+        }
+    }
 
-    Ok(functions.to_tokens(args))
-}
+    #[test]
+    fn test_retry() {
+        let args: IncludeFnArgs =
+            syn::parse_str(r#"driver as is; retry; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert_eq!(args.retry, Some(Retry::default()));
 
-fn parse_surrealql_files(
-    paths: &IncludeFnArgs,
-) -> Result<Vec<DefineFunctionStatement>, Box<dyn Error>> {
-    let mut out = vec![];
+        let args: IncludeFnArgs = syn::parse_str(
+            r#"driver as is; retry(times = 5, backoff = "1s"); "$CARGO_MANIFEST_DIR/tests/main.surql""#,
+        )
+        .unwrap();
+        assert_eq!(args.retry, Some(Retry { times: 5, backoff: Duration::from_secs(1) }));
 
-    for path in paths.paths.iter() {
-        out.extend(parse_surrealql_file(path)?);
+        let args: IncludeFnArgs =
+            syn::parse_str(r#"driver as is; retry(backoff = "10ms",); "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert_eq!(args.retry, Some(Retry { times: 3, backoff: Duration::from_millis(10) }));
     }
 
-    Ok(out)
-}
+    #[test]
+    fn test_duplicates() {
+        let dir = std::env::temp_dir().join("surrealdb_functions_duplicates");
+        std::fs::create_dir_all(&dir).unwrap();
+        let a = dir.join("a.surql");
+        let b = dir.join("b.surql");
+        std::fs::write(&a, "DEFINE FUNCTION fn::greet($name: string) { RETURN $name; };").unwrap();
+        std::fs::write(&b, "DEFINE FUNCTION fn::greet() { RETURN 'bob'; };").unwrap();
 
-fn parse_surrealql_file(path: &PathBuf) -> Result<Vec<DefineFunctionStatement>, Box<dyn Error>> {
-    let contents = std::fs::read_to_string(path)?;
-    let (_, fns) = all_consuming(parser::functions)(&contents).map_err(|e| e.to_string())?;
-    Ok(fns)
-}
+        let mut args = IncludeFnArgs {
+            includes: vec![Origin::File(a.clone()), Origin::File(b.clone())],
+            driver: Some(Alias::AsIs),
+            datastore: None,
+            driver_cfg: None,
+            datastore_cfg: None,
+            datastore_raw: false,
+            blocking: None,
+            blocking_runtime: BlockingRuntime::Futures,
+            blocking_cfg: None,
+            trait_name: None,
+            dispatch: false,
+            fragments: false,
+            batch: false,
+            paged: false,
+            stats: false,
+            targets: false,
+            tower: false,
+            http: None,
+            grpc: None,
+            graphql: false,
+            instrument: false,
+            metrics: false,
+            tests: false,
+            geo: false,
+            uuid: false,
+            types: TypeMap::default(),
+            chrono: false,
+            checked_args: false,
+            time: false,
+            retry: None,
+            define: Define::Query,
+            timeout: None,
+            wasm: false,
+            migrations: false,
+            verify: false,
+            sync: false,
+            metadata: false,
+            export_schema: false,
+            validate: false,
+            duplicates: Duplicates::Error,
+            continue_on_error: false,
+            modules: Modules::Nested,
+            bindings: Bindings::Typed,
+            docs: Docs::Signature,
+            examples: Examples::Run,
+            name_version: None,
+            stored: None,
+            module: None,
+            changelog: None,
+            version: SurrealVersion::V1,
+            crate_path: None,
+            engine: Engine::Generic,
+            options: vec![],
+        };
+        let err = source::load_sources(&args).err().unwrap().to_string();
+        assert!(err.starts_with("`fn::greet` is defined in both"), "{err}");
+        assert!(err.contains("a.surql") && err.contains("b.surql"), "{err}");
 
-fn transform_filename_to_const_name(path: &Path) -> Ident {
-    let mut name = path.file_name().unwrap().to_str().unwrap().to_owned();
-    name.retain(|c| c.is_ascii_alphanumeric() || c == '_');
-    let name = name.to_uppercase();
-    Ident::new(&format!("_SURQL_FILE_{name}"), Span::call_site())
-}
+        for duplicates in [Duplicates::First, Duplicates::Last, Duplicates::Override] {
+            args.duplicates = duplicates;
+            let (sources, notes) = source::load_sources(&args).unwrap();
+            assert_eq!(source::functions_of(&sources).len(), 1);
+            assert_eq!(notes.len(), usize::from(duplicates == Duplicates::Override));
+            // The kept definition is the one the database ends up with
+            let stored = stored_statements(&sources);
+            assert_eq!(stored.len(), if duplicates == Duplicates::Last { 2 } else { 1 });
+            assert_eq!(stored.iter().filter(|(_, _, _, wrapped)| *wrapped).count(), 1);
+            let text = stored.iter().map(|(_, _, text, _)| text.as_str()).collect::<Vec<_>>().join("\n");
+            let (kept, other) = if duplicates == Duplicates::First { ("RETURN $name", "RETURN 'bob'") } else { ("RETURN 'bob'", "RETURN $name") };
+            assert!(text.rsplit("DEFINE FUNCTION").next().unwrap().contains(kept), "{text}");
+            assert_eq!(text.contains(other), duplicates == Duplicates::Last, "{text}");
+        }
+    }
 
-fn bootstrap_for_files(args: &IncludeFnArgs) -> Result<TokenStream2, Box<dyn Error>> {
-    let mut consts = TokenStream2::new();
-    let mut consts_names = TokenStream2::new();
+    #[test]
+    fn test_continue_on_error() {
+        let dir = std::env::temp_dir().join("surrealdb_functions_continue_on_error");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.surql");
+        std::fs::write(&path, "DEFINE FUNCTION fn::a() { RETURN 1; };\nDEFINE FUNCTION fn::b($x: <int>) { RETURN $x; };\nDEFINE FUNCTION fn::c() { RETURN 3; };\n").unwrap();
 
-    for path in args.paths.iter() {
-        let name = transform_filename_to_const_name(path);
-        consts.extend(generate_include(&name, path.to_str().unwrap()));
+        let input = format!(r#"driver as is; "{}""#, path.display());
+        let args: IncludeFnArgs = syn::parse_str(&input).unwrap();
+        assert!(source::load_sources(&args).is_err());
 
-        consts_names.extend(quote! {
-            out.push_str(#name);
-        });
+        let input = format!(r#"driver as is; continue_on_error; "{}""#, path.display());
+        let args: IncludeFnArgs = syn::parse_str(&input).unwrap();
+        assert!(args.continue_on_error);
+        let (sources, notes) = source::load_sources(&args).unwrap();
+        let names: Vec<_> = source::functions_of(&sources).iter().map(|function| function.name.join("::")).collect();
+        assert_eq!(names, vec!["a", "c"]);
+        assert_eq!(stored_statements(&sources).len(), 2);
+        assert_eq!(notes.len(), 1);
+        assert!(notes[0].starts_with("skipped the statements that failed to parse: "), "{}", notes[0]);
+        assert!(notes[0].ends_with("a.surql:2:27 `DEFINE FUNCTION fn::b($x: <int>) { RETURN $x; };`"), "{}", notes[0]);
     }
 
-    let (driver, datastore) = args.transform_fn_name("define_functions");
+    #[test]
+    fn test_parse_cache() {
+        let dir = std::env::temp_dir().join("surrealdb_functions_cache");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.surql");
+        std::fs::write(&path, "DEFINE FUNCTION fn::a() { RETURN 1; };\n").unwrap();
+        let input = format!(r#"driver as is; "{}""#, path.display());
+        let args: IncludeFnArgs = syn::parse_str(&input).unwrap();
+        let names = || {
+            let (sources, _) = source::load_sources(&args).unwrap();
+            source::functions_of(&sources).iter().map(|function| function.name.join("::")).collect::<Vec<_>>()
+        };
+        assert_eq!(names(), vec!["a"]);
+        assert_eq!(names(), vec!["a"]);
 
-    let mut tokens = quote! {
-        #consts
+        // A different length invalidates the read contents even within the resolution of the modification time
+        std::fs::write(&path, "DEFINE FUNCTION fn::a() { RETURN 1; };\nDEFINE FUNCTION fn::b() { RETURN 2; };\n").unwrap();
+        assert_eq!(names(), vec!["a", "b"]);
 
-        #[doc = "Returns a string containing all the functions defined in the included files."]
-        pub fn stored_functions() -> String {
-            let mut out = String::new();
-            #consts_names
-            out
+        // A cached strict error stays out of a lenient parse of the same contents
+        std::fs::write(&path, "DEFINE FUNCTION fn::a() { RETURN 1; };\nDEFINE FUNCTION fn::b($x: <int>) { RETURN $x; };\n").unwrap();
+        assert!(source::load_sources(&args).is_err());
+        assert!(source::load_sources(&args).is_err());
+        let input = format!(r#"driver as is; continue_on_error; "{}""#, path.display());
+        let args: IncludeFnArgs = syn::parse_str(&input).unwrap();
+        let (sources, notes) = source::load_sources(&args).unwrap();
+        assert_eq!(source::functions_of(&sources).len(), 1);
+        assert_eq!(notes.len(), 1);
+
+        // Kept on disk for later builds, which read it back instead of parsing the same contents again
+        let contents = "DEFINE FUNCTION fn::c($x: array<int, 2>, $y: \"a\" | { id: record<user> }) -> option<string> { RETURN 1; };\nDEFINE FUNCTION fn::d(";
+        let parsed = parser::statements_lenient(contents);
+        source::write_cached(contents, true, &parsed);
+        assert_eq!(source::read_cached(contents, true), Some(parsed));
+        assert_eq!(source::read_cached(&contents.replace("fn::c", "fn::e"), true), None);
+
+        // Only the cached statements are evicted, never what else is in the directory
+        let cache = dir.join("cache");
+        std::fs::create_dir_all(&cache).unwrap();
+        for name in ["0123-4-strict.json", "0123-4-lenient.json", "notes.json", "0123-4-strict.json.1.tmp"] {
+            std::fs::write(cache.join(name), "").unwrap();
         }
-    };
+        let mut cached = source::cache_files(&cache);
+        cached.sort();
+        assert_eq!(cached, [cache.join("0123-4-lenient.json"), cache.join("0123-4-strict.json")]);
+    }
 
-    if let Some(name) = driver {
-        tokens.extend(quote!{
-            #[doc = "Defines all the functions using the provided connection."]
-            pub async fn #name<C: ::surrealdb::Connection>(db: &::surrealdb::Surreal<C>) -> ::surrealdb::Result<::surrealdb::Response> {
-                db.query(stored_functions()).await
+    #[test]
+    fn test_override_duplicates() {
+        let dir = std::env::temp_dir().join("surrealdb_functions_override");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.surql");
+        std::fs::write(
+            &path,
+            "DEFINE FUNCTION fn::a() { RETURN 1; };\nDEFINE FUNCTION fn::b() { RETURN 2; } ;\nDEFINE FUNCTION fn::a() { RETURN 3; };\n",
+        )
+        .unwrap();
+
+        let args = IncludeFnArgs {
+            includes: vec![Origin::File(path)],
+            driver: Some(Alias::AsIs),
+            datastore: None,
+            driver_cfg: None,
+            datastore_cfg: None,
+            datastore_raw: false,
+            blocking: None,
+            blocking_runtime: BlockingRuntime::Futures,
+            blocking_cfg: None,
+            trait_name: None,
+            dispatch: false,
+            fragments: false,
+            batch: false,
+            paged: false,
+            stats: false,
+            targets: false,
+            tower: false,
+            http: None,
+            grpc: None,
+            graphql: false,
+            instrument: false,
+            metrics: false,
+            tests: false,
+            geo: false,
+            uuid: false,
+            types: TypeMap::default(),
+            chrono: false,
+            checked_args: false,
+            time: false,
+            retry: None,
+            define: Define::Query,
+            timeout: None,
+            wasm: false,
+            migrations: false,
+            verify: false,
+            sync: false,
+            metadata: false,
+            export_schema: false,
+            validate: false,
+            duplicates: Duplicates::Override,
+            continue_on_error: false,
+            modules: Modules::Nested,
+            bindings: Bindings::Typed,
+            docs: Docs::Signature,
+            examples: Examples::Run,
+            name_version: None,
+            stored: None,
+            module: None,
+            changelog: None,
+            version: SurrealVersion::V1,
+            crate_path: None,
+            engine: Engine::Generic,
+            options: vec![],
+        };
+        let (sources, notes) = source::load_sources(&args).unwrap();
+        assert_eq!(notes.len(), 1);
+        let stored: Vec<_> = stored_statements(&sources).into_iter().map(|(name, _, text, _)| (name.to_string(), text)).collect();
+        assert_eq!(
+            stored,
+            vec![
+                ("STATEMENT_0".to_string(), "DEFINE FUNCTION fn::b() { RETURN 2; };".to_string()),
+                ("STATEMENT_1".to_string(), "DEFINE FUNCTION fn::a() { RETURN 3; };".to_string()),
+            ]
+        );
+        let names: Vec<_> = source::functions_of(&sources)
+            .into_iter()
+            .map(|v| v.name.join("::"))
+            .collect();
+        assert_eq!(names, vec!["b", "a"]);
+    }
+
+    #[test]
+    fn test_functions_hash() {
+        let dir = std::env::temp_dir().join("surrealdb_functions_hash");
+        std::fs::create_dir_all(&dir).unwrap();
+        let hash_of = |contents: &str| {
+            let path = dir.join("a.surql");
+            std::fs::write(&path, contents).unwrap();
+            let args = IncludeFnArgs {
+                includes: vec![Origin::File(path)],
+                driver: Some(Alias::AsIs),
+                datastore: None,
+                driver_cfg: None,
+                datastore_cfg: None,
+                datastore_raw: false,
+                blocking: None,
+                blocking_runtime: BlockingRuntime::Futures,
+                blocking_cfg: None,
+                trait_name: None,
+                dispatch: false,
+                fragments: false,
+                batch: false,
+                paged: false,
+                stats: false,
+                targets: false,
+                tower: false,
+                http: None,
+                grpc: None,
+                graphql: false,
+                instrument: false,
+                metrics: false,
+                tests: false,
+                geo: false,
+                uuid: false,
+                types: TypeMap::default(),
+                chrono: false,
+                checked_args: false,
+                time: false,
+                retry: None,
+                define: Define::Query,
+                timeout: None,
+                wasm: false,
+                migrations: false,
+                verify: false,
+                sync: false,
+                metadata: false,
+                export_schema: false,
+                validate: false,
+                duplicates: Duplicates::Error,
+                continue_on_error: false,
+                modules: Modules::Nested,
+                bindings: Bindings::Typed,
+                docs: Docs::Signature,
+                examples: Examples::Run,
+                name_version: None,
+                stored: None,
+                module: None,
+                changelog: None,
+                version: SurrealVersion::V1,
+                crate_path: None,
+                engine: Engine::Generic,
+                options: vec![],
+            };
+            hash_expr(&source::load_sources(&args).unwrap().0).to_string()
+        };
+
+        let hash = hash_of("DEFINE FUNCTION fn::a() {\n    RETURN 1;\n};\n");
+        assert!(hash.contains(&quote! { &["DEFINE FUNCTION fn::a() {\n    RETURN 1;\n};\n"] }.to_string()), "{hash}");
+        assert_eq!(hash, hash_of("\r\nDEFINE FUNCTION fn::a() {  \r\n    RETURN 1;\r\n};"));
+        assert_ne!(hash, hash_of("DEFINE FUNCTION fn::a() {\n    RETURN 2;\n};\n"));
+    }
+
+    #[test]
+    fn test_cfg_items() {
+        let items = quote! {
+            pub fn a() {}
+            pub mod b {
+                pub fn c() {}
             }
-        });
+            const D: &str = "d";
+        };
+        assert_eq!(cfg_items(None, items.clone()).to_string(), items.to_string());
+        let cfg = quote! { #[cfg(feature = "sdk")] }.to_string();
+        let out = cfg_items(Some("sdk"), items).to_string();
+        assert_eq!(out.matches(&cfg).count(), 3);
+        assert!(out.starts_with(&cfg));
     }
 
-    if let Some(name) = datastore {
-        tokens.extend(quote!{
-            #[doc = "Defines all the functions using the provided datastore and session."]
-            pub async fn #name(ds: &::surrealdb::kvs::Datastore, session: &::surrealdb::dbs::Session) -> Result<Vec<::surrealdb::dbs::Response>, ::surrealdb::err::Error> {
-                ds.execute(&stored_functions(), session, None).await
+    #[test]
+    fn test_crate_path() {
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; datastore as ds_$; crate as ::my_company_db::surrealdb; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        let tokens = include_fn_impl(args).to_string();
+        assert!(tokens.contains("pub async fn define_functions < C : :: my_company_db :: surrealdb :: Connection > (db : & :: my_company_db :: surrealdb :: Surreal < C >)"), "{tokens}");
+        assert!(tokens.contains("ds : & :: my_company_db :: surrealdb :: kvs :: Datastore"), "{tokens}");
+        assert!(!tokens.contains("& :: surrealdb ::"), "{tokens}");
+
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        let tokens = include_fn_impl(args).to_string();
+        assert!(tokens.contains("pub async fn define_functions < C : :: surrealdb :: Connection > (db : & :: surrealdb :: Surreal < C >)"), "{tokens}");
+    }
+
+    #[test]
+    fn test_surreal_version() {
+        let sql = "DEFINE FUNCTION fn::a() -> record { RETURN 1; }; DEFINE FUNCTION fn::b() { RETURN 1; };";
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        let output = |function: &DefineFunctionStatement, version| {
+            let mut args: IncludeFnArgs = syn::parse_str(r#"driver as is; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+            args.version = version;
+            function.value_output(&args).to_string()
+        };
+        assert_eq!(output(&functions[0], SurrealVersion::V1), quote! { ::std::option::Option<::surrealdb::sql::Thing> }.to_string());
+        assert_eq!(output(&functions[0], SurrealVersion::V2), quote! { ::std::option::Option<::surrealdb::RecordId> }.to_string());
+        assert_eq!(output(&functions[1], SurrealVersion::V1), quote! { ::surrealdb::sql::Value }.to_string());
+        assert_eq!(output(&functions[1], SurrealVersion::V2), quote! { ::surrealdb::Value }.to_string());
+    }
+
+    #[test]
+    fn test_surql_docs() {
+        let sql = "DEFINE FUNCTION fn::greet($name: string) -> string {\n    RETURN 'Hello ' + $name;\n};";
+        let (_, function) = parser::statements(sql).unwrap().remove(0);
+        let mut args: IncludeFnArgs = syn::parse_str(r#"driver as is; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        let lines = |function: &DefineFunctionStatement, args: &IncludeFnArgs| {
+            let docs = function.surql_docs(args);
+            syn::parse::Parser::parse2(syn::Attribute::parse_outer, docs)
+                .unwrap()
+                .iter()
+                .map(|attr| match &attr.meta {
+                    syn::Meta::NameValue(syn::MetaNameValue { value: syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(line), .. }), .. }) => line.value(),
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            lines(&function, &args),
+            vec!["", "# SurrealQL", "", "| Parameter | Kind |", "|---|---|", "| `$name` | `string` |", "", "Returns `string`.", "", "```surql", "DEFINE FUNCTION fn::greet($name: string) -> string", "```"]
+        );
+        args.docs = Docs::Body;
+        assert_eq!(
+            lines(&function, &args)[9..],
+            ["```surql", "DEFINE FUNCTION fn::greet($name: string) -> string {", "    RETURN 'Hello ' + $name;", "}", "```"]
+        );
+
+        let sql = "DEFINE FUNCTION fn::a(\n    $id: string | int, -- the | separated id\n    $limit: int\n) { RETURN 1; };";
+        let (_, function) = parser::statements(sql).unwrap().remove(0);
+        assert_eq!(
+            lines(&function, &args)[3..7],
+            ["| Parameter | Kind | Description |", "|---|---|---|", "| `$id` | `string \\| int` | the \\| separated id |", "| `$limit` | `int` |  |"]
+        );
+    }
+
+    #[test]
+    fn test_deprecated() {
+        let sql = "-- @deprecated use fn::v2::greet\n-- @alias hello\nDEFINE FUNCTION fn::greet() { RETURN 1; };\n-- @deprecated\nDEFINE FUNCTION fn::old() { RETURN 1; };\nDEFINE FUNCTION fn::v2::greet() { RETURN 1; };";
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        assert_eq!(functions[0].deprecation().as_deref(), Some("use fn::v2::greet"));
+        assert_eq!(functions[0].aliases()[0].deprecation().as_deref(), Some("renamed to `fn::greet`"));
+        assert_eq!(functions[1].deprecation().as_deref(), Some(""));
+        assert_eq!(functions[2].deprecation(), None);
+
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert!(functions[0].attributes(&args).to_string().contains(&quote! { #[deprecated(note = "use fn::v2::greet")] }.to_string()));
+        assert!(functions[1].attributes(&args).to_string().ends_with(&quote! { #[deprecated] }.to_string()));
+    }
+
+    #[test]
+    fn test_cfg_directive() {
+        let dir = std::env::temp_dir().join("surrealdb_functions_cfg");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.surql");
+        std::fs::write(&path, "DEFINE FUNCTION fn::a() { RETURN 1; };\n-- @cfg(feature = \"admin\")\n-- @alias old_ban\nDEFINE FUNCTION fn::ban() { RETURN 2; };\n").unwrap();
+        let args: IncludeFnArgs = syn::parse_str(&format!("driver as is; {:?}", path.display().to_string())).unwrap();
+        let sources = source::load_sources(&args).unwrap().0;
+
+        let stored = bootstrap_for_files(&args, &sources).to_string();
+        assert!(stored.contains(&quote! { &[FunctionId::STATEMENT_0, #[cfg(feature = "admin")] FunctionId::STATEMENT_1] }.to_string()), "{stored}");
+        assert!(stored.contains(&quote! { #[cfg(feature = "admin")] const STATEMENT_1: &'static str = "DEFINE FUNCTION fn::ban() { RETURN 2; };"; }.to_string()), "{stored}");
+
+        let consts = hash_consts(&args, &sources).to_string();
+        assert_eq!(consts.matches("pub const FUNCTIONS_HASH").count(), 1, "{consts}");
+        assert!(consts.contains(&quote! { &["DEFINE FUNCTION fn::a() { RETURN 1; };\n", #[cfg(feature = "admin")] "DEFINE FUNCTION fn::ban() { RETURN 2; };\n"] }.to_string()), "{consts}");
+
+        let functions = source::functions_of(&sources);
+        assert!(functions[0].cfg().is_none());
+        for function in &functions[1..] {
+            assert!(function.attributes(&args).to_string().starts_with(&quote! { #[cfg(feature = "admin")] }.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_rust_name() {
+        let sql = "-- @rust_name fetch_user\nDEFINE FUNCTION fn::users::get($id: string) { RETURN 1; };\nDEFINE FUNCTION fn::users::list() { RETURN 1; };";
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        let mut args: IncludeFnArgs = syn::parse_str(r#"driver as is; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert_eq!(functions[0].rust_name(&args), "fetch_user");
+        assert_eq!(functions[0].method_base_name(), "fetch_user");
+        assert_eq!(functions[1].method_base_name(), "users_list");
+        let tokens = build_mod_tree(functions.clone(), &[], &args).to_string();
+        assert!(tokens.contains("pub async fn fetch_user <"), "{tokens}");
+        assert!(tokens.contains("RETURN fn::users::get($__fnarg_id)"), "{tokens}");
+        args.modules = Modules::Flat;
+        assert_eq!(functions[0].rust_name(&args), "fetch_user");
+        assert_eq!(functions[1].rust_name(&args), "users_list");
+    }
+
+    #[test]
+    fn test_keyword_names() {
+        let keywords = [
+            "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match",
+            "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use",
+            "where", "while", "async", "await", "dyn", "abstract", "become", "box", "do", "final", "macro", "override", "priv", "typeof",
+            "unsized", "virtual", "yield", "try", "gen", "union",
+        ];
+        let sql: String = keywords
+            .iter()
+            .map(|keyword| format!("DEFINE FUNCTION fn::{keyword}::{keyword}(${keyword}: string, $values: option<int>) {{ RETURN ${keyword}; }};\n"))
+            .collect();
+        let functions: Vec<_> = parser::statements(&sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        assert_eq!(functions.len(), keywords.len());
+        for bindings in ["typed", "serde"] {
+            let args: IncludeFnArgs = syn::parse_str(&format!(r#"driver as is; datastore as $_ds; bindings as {bindings}; "$CARGO_MANIFEST_DIR/tests/main.surql""#)).unwrap();
+            let tokens = build_mod_tree(functions.clone(), &[], &args);
+            if let Err(e) = syn::parse2::<syn::File>(tokens.clone()) {
+                panic!("{e}: {tokens}");
             }
-        });
+            let tokens = tokens.to_string();
+            assert!(tokens.contains("pub mod r#type {"), "{tokens}");
+            assert!(tokens.contains("pub async fn r#type <"), "{tokens}");
+            assert!(tokens.contains("pub mod self_ {"), "{tokens}");
+            assert!(tokens.contains("r#gen : impl"), "{tokens}");
+            assert!(tokens.contains("RETURN fn::type::type($__fnarg_type, $__fnarg_values)"), "{tokens}");
+        }
+        assert_eq!(rust_ident("name").to_string(), "name");
+        assert_eq!(rust_ident("in").to_string(), "r#in");
+        assert_eq!(rust_ident("super").to_string(), "super_");
     }
 
-    Ok(tokens)
-}
+    #[test]
+    fn test_escaped_names() {
+        let sql = "-- @rust_param $⟨first name⟩ given_name\nDEFINE FUNCTION fn::⟨café⟩::`1st-greet`($⟨first name⟩: string, $⟨prénom⟩: int) { RETURN 1; };";
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        assert_eq!(functions[0].name, ["café", "1st-greet"]);
+        assert_eq!(functions[0].signature(), "fn::`café`::`1st-greet`($`first name`: string, $`prénom`: int)");
+        assert_eq!(functions[0].custom_function_query(), "RETURN fn::`café`::`1st-greet`($`__fnarg_first name`, $`__fnarg_prénom`)");
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; datastore as $_ds; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        let tokens = build_mod_tree(functions, &[], &args);
+        if let Err(e) = syn::parse2::<syn::File>(tokens.clone()) {
+            panic!("{e}: {tokens}");
+        }
+        let tokens = tokens.to_string();
+        assert!(tokens.contains("pub mod cafe {"), "{tokens}");
+        assert!(tokens.contains("pub async fn _1st_greet < C : :: surrealdb :: Connection > (db : & :: surrealdb :: Surreal < C > , given_name : impl"), "{tokens}");
+        assert!(tokens.contains("prenom : impl"), "{tokens}");
+        assert!(tokens.contains(". bind ((\"__fnarg_first name\" , :: std :: convert :: Into :: into (given_name)))"), "{tokens}");
+    }
 
-fn add_path_if_surql(path: &Path, out: &mut Vec<PathBuf>) -> Result<(), Box<dyn Error>> {
-    if path.extension().unwrap_or_default() == "surql" {
-        out.push(path.to_path_buf());
+    #[test]
+    fn test_unknown_kinds() {
+        let sql = "DEFINE FUNCTION fn::users::avatar($file: file<avatars>, $size: option<int>) -> file<avatars> { RETURN $file; };";
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        let expected = quote! {
+            file: impl ::std::convert::Into < ::surrealdb::sql::Value >,
+            size: ::std::option::Option < i64 >,
+        };
+        assert_eq!(functions[0].params_to_args(&args).to_string(), expected.to_string());
+        assert_eq!(functions[0].value_output(&args).to_string(), quote! { ::surrealdb::sql::Value }.to_string());
+        assert_eq!(
+            kind_notes(&functions),
+            vec![
+                "`$file` of `fn::users::avatar` has the kind `file<avatars>`, `file<avatars>` is unknown to surrealdb_functions so it takes any `Value`",
+                "`fn::users::avatar` returns `file<avatars>`, unknown to surrealdb_functions so `_value` returns a `Value`",
+            ]
+        );
     }
-    Ok(())
-}
 
-fn expand_path(path: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
-    let mut out = vec![];
+    #[test]
+    fn test_paged() {
+        let sql = "DEFINE FUNCTION fn::users::list($team: string) -> array<string> { RETURN []; };\nDEFINE FUNCTION fn::users::search($limit: int) -> array<string> { RETURN []; };\n-- @rust_param $size start\nDEFINE FUNCTION fn::users::page($size: int) -> array<string> { RETURN []; };";
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; paged; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert!(args.paged);
+        assert!(functions[0].returns_array());
+        assert!(!functions[1].returns_array());
+        assert!(!functions[2].returns_array());
 
-    if path.is_dir() {
-        for entry in path.read_dir()? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.is_dir() {
-                out.append(&mut expand_path(&path)?);
-            } else {
-                add_path_if_surql(&path, &mut out)?;
+        let tokens = functions[0].to_tokens(&args).to_string();
+        let expected = quote! {
+            pub async fn list_paged<C: ::surrealdb::Connection>(db: &::surrealdb::Surreal<C>, team: impl ::std::convert::Into<::surrealdb::sql::Strand>, limit: u64, start: u64) -> ::surrealdb::Result<super::Page<::std::string::String>> {
+                let mut items: ::std::vec::Vec<::std::string::String> = db.query("RETURN array::slice(fn::users::list($__fnarg_team), $__start, $__limit)")
+                    .bind(("__fnarg_team", ::std::convert::Into::into(team)))
+                    .bind(("__start", start))
+                    .bind(("__limit", limit.saturating_add(1)))
+                    .await?.check()?.take(0)?;
+                let has_more = items.len() as u64 > limit;
+                items.truncate(limit as usize);
+                ::std::result::Result::Ok(super::Page { items, start, has_more })
+            }
+        };
+        assert!(tokens.contains(&expected.to_string()), "{tokens}");
+        assert!(!functions[1].to_tokens(&args).to_string().contains("_paged"));
+    }
+
+    #[test]
+    fn test_metadata() {
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; metadata; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        let (sources, _) = source::load_sources(&args).unwrap();
+        let tokens = metadata::metadata_const(&source::functions_of(&sources)).to_string();
+        assert!(tokens.contains("name : \"nested::greet\" , params : & [ParamMeta { name : \"name\" , kind : \"string\" , sensitive : false , } ,] , returns : :: std :: option :: Option :: Some (\"string\") , signature : \"fn::nested::greet($name: string) -> string\""), "{tokens}");
+    }
+
+    #[test]
+    fn test_http() {
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; http as axum; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert_eq!(args.http, Some(Http::Axum));
+        let (sources, _) = source::load_sources(&args).unwrap();
+        let functions = source::functions_of(&sources);
+        let tokens = functions[1].to_tokens(&args).to_string();
+        assert!(tokens.contains("pub async fn greet_handler < C : :: surrealdb :: Connection > (:: axum :: extract :: State (db) : :: axum :: extract :: State < :: surrealdb :: Surreal < C >>"), "{tokens}");
+        let bind = quote! {
+            if let ::std::option::Option::Some(value) = body.remove("name") {
+                query = query.bind(("__fnarg_name", value));
             }
+        };
+        assert!(tokens.contains(&bind.to_string()), "{tokens}");
+        // Deprecated aliases aren't served
+        let alias = functions.iter().find(|function| function.alias_of.is_some()).unwrap();
+        assert!(!alias.to_tokens(&args).to_string().contains("_handler"));
+
+        let tokens = http::router_function(&functions, &sources, &args).to_string();
+        let route = quote! {
+            let router = router.route("/fn/nested/greet", ::axum::routing::post(self::nested::greet_handler::<C>));
+        };
+        assert!(tokens.contains(&route.to_string()), "{tokens}");
+        assert!(!tokens.contains("greet_nested"), "{tokens}");
+    }
+
+    #[test]
+    fn test_grpc() {
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; grpc as crate::pb; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        let sql = "DEFINE FUNCTION fn::users::find($userId: string, $filter: option<object>) -> array<object> { RETURN []; };";
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        let tokens = grpc::grpc_service(&functions, args.grpc.as_ref().unwrap(), &args).to_string();
+        assert!(tokens.contains("impl < C : :: surrealdb :: Connection > crate :: pb :: functions_server :: Functions for GrpcFunctions < C >"), "{tokens}");
+        assert!(tokens.contains("async fn users_find (& self , request : :: tonic :: Request < crate :: pb :: UsersFindRequest > ,)"), "{tokens}");
+        let bind = quote! {
+            .bind(("__fnarg_userId", request.user_id))
+            .bind(("__fnarg_filter", request.filter.map(|text| json("filter", text)).transpose()?))
+        };
+        assert!(tokens.contains(&bind.to_string()), "{tokens}");
+        let response = quote! {
+            crate::pb::UsersFindResponse { value: value.unwrap_or(::serde_json::Value::Null).to_string() }
+        };
+        assert!(tokens.contains(&response.to_string()), "{tokens}");
+    }
+
+    #[test]
+    fn test_graphql() {
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; graphql; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert!(args.graphql);
+        let sql = "-- Finds users\n-- @deprecated use fn::users::search\nDEFINE FUNCTION fn::users::find($team_id: int, $tags: option<array<string>>, $filter: object) -> option<array<object>> { RETURN []; };";
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        let tokens = graphql::graphql_object(&functions, &args).to_string();
+        assert!(tokens.contains("# [:: async_graphql :: Object] impl < C : :: surrealdb :: Connection > GraphqlFunctions < C >"), "{tokens}");
+        assert!(tokens.contains("# [doc = \"Finds users\"] # [graphql (deprecation = \"use fn::users::search\")] async fn users_find"), "{tokens}");
+        assert!(tokens.contains("(& self , team_id : i64 , tags : :: std :: option :: Option < :: std :: vec :: Vec < :: std :: string :: String > > , filter : :: async_graphql :: Json < :: serde_json :: Value >)"), "{tokens}");
+        assert!(tokens.contains("-> :: async_graphql :: Result < :: std :: option :: Option < :: async_graphql :: Json < :: serde_json :: Value > > >"), "{tokens}");
+        assert!(tokens.contains(&quote! { .bind(("__fnarg_filter", filter.0)) }.to_string()), "{tokens}");
+    }
+
+    #[test]
+    fn test_block_comment_docs() {
+        let sql = "/* Greets someone.\n\n   Shows up as:\n\n   ```\n   Hello, name!\n   ```\n*/\nDEFINE FUNCTION fn::greet($name: string) { RETURN 'Hello, ' + $name + '!'; };";
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        assert_eq!(functions[0].comments, ["Greets someone.", "", "Shows up as:", "", "```", "Hello, name!", "```"]);
+        let docs = quote! {
+            #[doc = "Greets someone."]
+            #[doc = ""]
+            #[doc = "Shows up as:"]
+            #[doc = ""]
+            #[doc = "```text"]
+            #[doc = "Hello, name!"]
+            #[doc = "```"]
+        };
+        assert_eq!(functions[0].doc_comments().to_string(), docs.to_string());
+    }
+
+    #[test]
+    fn test_examples() {
+        let sql = "/* Greets someone.\n\n   ```rust\n   let greeting = greet(&db, \"a\").await?;\n   ```\n\n   ```rust,ignore\n   todo!()\n   ```\n*/\nDEFINE FUNCTION fn::greet($name: string) { RETURN 'Hello, ' + $name + '!'; };";
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        assert_eq!(functions[0].doc_comments().to_string(), quote! { #[doc = "Greets someone."] }.to_string());
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; examples as no_run; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        let examples = quote! {
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```rust,no_run"]
+            #[doc = "let greeting = greet(&db, \"a\").await?;"]
+            #[doc = "```"]
+            #[doc = ""]
+            #[doc = "```rust,ignore"]
+            #[doc = "todo!()"]
+            #[doc = "```"]
+        };
+        assert_eq!(functions[0].examples(&args).to_string(), examples.to_string());
+        // Only once
+        let tokens = functions[0].to_tokens(&args).to_string();
+        assert_eq!(tokens.matches("# Examples").count(), 1, "{tokens}");
+    }
+
+    #[test]
+    fn test_name_version() {
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; version as v2; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert_eq!(args.name_version.as_deref(), Some("v2"));
+        assert_eq!(args.options, ["driver", "version"]);
+        let tokens = include_fn_impl(args).to_string();
+        assert!(tokens.contains("DEFINE FUNCTION fn::v2::nested::greet($name: string)"), "{tokens}");
+        assert!(tokens.contains("pub mod nested"), "{tokens}");
+        assert!(!tokens.contains("pub mod v2"), "{tokens}");
+        assert!(tokens.contains("DEFINE PARAM $v2_functions_hash VALUE"), "{tokens}");
+        assert!(tokens.contains("pub const GREET_FN : & str = \"fn::v2::nested::greet\""), "{tokens}");
+        // The alias still calls the versioned function
+        assert!(tokens.contains("pub async fn greet_nested <"), "{tokens}");
+        assert!(!tokens.contains("RETURN fn::nested::greet"), "{tokens}");
+        assert!(tokens.contains("RETURN fn::v2::nested::greet($__fnarg_name)"), "{tokens}");
+
+        let dir = std::env::temp_dir().join(format!("surrealdb_functions_version_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("calls.surql");
+        std::fs::write(&path, "DEFINE FUNCTION fn::a() { RETURN fn::b() + 'fn::b' + fn::c(); };\nDEFINE FUNCTION fn::b() { RETURN 1; };").unwrap();
+        let args: IncludeFnArgs = syn::parse_str(&format!("driver as is; version as v2; {:?}", path.display().to_string())).unwrap();
+        let (sources, _) = source::load_sources(&args).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let statements: Vec<_> = sources[0].stored_statements().into_iter().map(|(_, text, _)| text).collect();
+        assert_eq!(statements[0], "DEFINE FUNCTION fn::v2::a() { RETURN fn::v2::b() + 'fn::b' + fn::c(); };");
+        assert_eq!(source::functions_of(&sources)[1].unversioned_name(), ["b"]);
+    }
+
+    #[test]
+    fn test_export_schema() {
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; export_schema; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert!(args.export_schema);
+        let (sources, _) = source::load_sources(&args).unwrap();
+        let tokens = schema::schema_function(&source::functions_of(&sources)).to_string();
+        let greet = r#"{\"name\":\"fn::nested::greet\",\"signature\":\"fn::nested::greet($name: string) -> string\",\"description\":\"Returns a greeting but its nested this time!\",\"deprecated\":false,\"params\":{\"type\":\"object\",\"properties\":{\"name\":{\"x-surrealdb-kind\":\"string\",\"type\":\"string\"}},\"required\":[\"name\"],\"additionalProperties\":false},\"returns\":{\"type\":\"string\"}}"#;
+        assert!(tokens.contains(greet), "{tokens}");
+        assert!(!tokens.contains("greet_nested"), "{tokens}");
+
+        let sql = "DEFINE FUNCTION fn::find($ids: array<record<user | team>, 10>, $limit: option<int>) -> set<string> { RETURN []; };";
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        let tokens = schema::schema_function(&functions).to_string();
+        assert!(tokens.contains(r#"\"ids\":{\"x-surrealdb-kind\":\"array<record<user | team>, 10>\",\"type\":\"array\",\"items\":{\"type\":\"string\",\"pattern\":\"^(user|team):\"},\"maxItems\":10}"#), "{tokens}");
+        assert!(tokens.contains(r#"\"anyOf\":[{\"type\":\"integer\"},{\"type\":\"null\"}]"#), "{tokens}");
+        assert!(tokens.contains(r#"\"required\":[\"ids\"]"#), "{tokens}");
+        assert!(tokens.contains(r#"\"returns\":{\"type\":\"array\",\"items\":{\"type\":\"string\"},\"uniqueItems\":true}"#), "{tokens}");
+    }
+
+    #[test]
+    fn test_stats() {
+        let sql = "DEFINE FUNCTION fn::users::count($team: string) -> int { RETURN 1; };";
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; stats; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert!(args.stats);
+        let tokens = functions[0].to_tokens(&args).to_string();
+        assert!(tokens.contains("pub async fn count_with_stats < C : :: surrealdb :: Connection >"), "{tokens}");
+        assert!(tokens.contains("-> :: surrealdb :: Result < super :: Timed < :: std :: option :: Option < i64 > >>"), "{tokens}");
+        let expected = quote! {
+            let mut response = db.query("RETURN fn::users::count($__fnarg_team)")
+                .bind(("__fnarg_team", ::std::convert::Into::into(team)))
+                .with_stats()
+                .await?;
+            let ::std::option::Option::Some((stats, value)) = response.take(0) else {
+                ::std::unreachable!("a single statement has a single result")
+            };
+            ::std::result::Result::Ok(super::Timed { value: value?, execution_time: stats.execution_time })
+        };
+        assert!(tokens.contains(&expected.to_string()), "{tokens}");
+    }
+
+    #[test]
+    fn test_targets() {
+        let sql = "DEFINE FUNCTION fn::users::count($team: string) -> int { RETURN 1; };";
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; targets; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert!(args.targets);
+        let tokens = functions[0].to_tokens(&args).to_string();
+        assert!(tokens.contains("pub async fn count_in < C : :: surrealdb :: Connection > (db : & :: surrealdb :: Surreal < C > , target : & super :: CallTarget , team :"), "{tokens}");
+        let expected = quote! {
+            let use_statement = target.statement();
+            let index = usize::from(!use_statement.is_empty());
+            db.query(::std::format!("{use_statement}{}", "RETURN fn::users::count($__fnarg_team)"))
+                .bind(("__fnarg_team", ::std::convert::Into::into(team)))
+                .await?.check()?.take(index)
+        };
+        assert!(tokens.contains(&expected.to_string()), "{tokens}");
+        assert!(target::target_type().to_string().contains("pub struct CallTarget"));
+    }
+
+    #[test]
+    fn test_live() {
+        let sql = "-- @live\nDEFINE FUNCTION fn::watch($team: string) {\n    RETURN live select * FROM user WHERE team = $team;\n};\nDEFINE FUNCTION fn::delivered() { RETURN 'LIVE SELECTION'; };";
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        assert_eq!(functions[0].live_statement().as_deref(), Some("live select * FROM user WHERE team = $team"));
+        assert_eq!(functions[1].live_statement(), None);
+
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        let tokens = functions[0].to_tokens(&args).to_string();
+        assert!(tokens.contains("pub async fn watch_live <"), "{tokens}");
+        assert!(tokens.contains("QueryStream < :: surrealdb :: Notification <"), "{tokens}");
+        assert!(tokens.contains("db . query (\"live select * FROM user WHERE team = $__fnarg_team\")"), "{tokens}");
+
+        // Every parameter of the function is referenced under the name it's bound as, the others are kept
+        let sql = "DEFINE FUNCTION fn::watch($team: string, $⟨first name⟩: string) {\n    RETURN LIVE SELECT * FROM user WHERE team = $team AND name = $⟨first name⟩ AND owner = $auth.id AND note != '$team';\n};";
+        let watch = &parser::statements(sql).unwrap()[0].1;
+        let statement = live::bound_statement(watch, &watch.live_statement().unwrap());
+        assert_eq!(statement, "LIVE SELECT * FROM user WHERE team = $__fnarg_team AND name = $`__fnarg_first name` AND owner = $auth.id AND note != '$team'");
+        let bindings = watch.params_to_bindings(&args).to_string();
+        let mut bound: Vec<_> = bindings.split(". bind ((\"").skip(1).map(|binding| binding.split('"').next().unwrap().to_string()).collect();
+        let mut referenced: Vec<_> = parser::scan::params(&statement).into_iter().map(|(_, name)| name).filter(|name| name != "auth").collect();
+        bound.sort();
+        referenced.sort();
+        assert_eq!(bound, referenced);
+
+        // The statement ends at its `;`, not at one in a string
+        let sql = r#"DEFINE FUNCTION fn::chat() { RETURN LIVE SELECT * FROM message WHERE text != "a;}" AND room = '⟨;⟩'; };"#;
+        let chat = &parser::statements(sql).unwrap()[0].1;
+        assert_eq!(chat.live_statement().as_deref(), Some(r#"LIVE SELECT * FROM message WHERE text != "a;}" AND room = '⟨;⟩'"#));
+    }
+
+    #[test]
+    fn test_module_docs() {
+        let sql = "-- @module users: Functions about users.\n-- @module users::admin: Only for admins.\nDEFINE FUNCTION fn::users::admin::ban($id: string) { RETURN 1; };\n-- @module fn::users: Also listed.\nDEFINE FUNCTION fn::users::list() { RETURN 1; };";
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        assert!(functions[0].comments.is_empty());
+        let mut tree = Function::from(functions.clone());
+        tree.document_modules(&functions);
+        let users = &tree.1["users"];
+        assert_eq!(users.2, vec!["Functions about users.", "Also listed."]);
+        assert_eq!(users.1["admin"].2, vec!["Only for admins."]);
+    }
+
+    #[test]
+    fn test_array_items() {
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        let sql = "DEFINE FUNCTION fn::a($tags: set<string>, $ids: array<int, 3>, $any: array) { RETURN 1; };";
+        let (_, function) = parser::statements(sql).unwrap().remove(0);
+        let expected = quote! {
+            tags: impl ::std::iter::IntoIterator < Item = impl ::std::convert::Into < ::surrealdb::sql::Strand > >,
+            ids: [impl ::std::convert::Into < ::surrealdb::sql::Number >; IDS_LEN],
+            any: impl ::std::convert::Into < ::surrealdb::sql::Array >,
+        };
+        assert_eq!(function.params_to_args(&args).to_string(), expected.to_string());
+        assert_eq!(function.generics(None, &args).to_string(), quote! { <const IDS_LEN: usize> }.to_string());
+
+        // The length is checked against the same const generic, for escaped and renamed parameters too
+        let sql = "-- @rust_param $⟨first names⟩ names\nDEFINE FUNCTION fn::b($⟨first names⟩: array<string, 3>, $⟨last name⟩: array<string, 2>) { RETURN 1; };";
+        let (_, function) = parser::statements(sql).unwrap().remove(0);
+        assert_eq!(
+            function.generics(None, &args).to_string(),
+            quote! { <const NAMES_LEN: usize, const LAST_NAME_LEN: usize> }.to_string()
+        );
+        let bindings = function.params_to_bindings(&args).to_string();
+        assert!(bindings.contains("assert ! (NAMES_LEN <= 3"), "{bindings}");
+        assert!(bindings.contains("assert ! (LAST_NAME_LEN <= 2"), "{bindings}");
+        assert!(!bindings.contains("FIRST_NAMES_LEN"), "{bindings}");
+        let tokens = build_mod_tree(vec![function], &[], &args);
+        if let Err(e) = syn::parse2::<syn::File>(tokens.clone()) {
+            panic!("{e}: {tokens}");
         }
-    } else {
-        add_path_if_surql(path, &mut out)?;
     }
 
-    Ok(out)
-}
+    #[test]
+    fn test_geo() {
+        let sql = "DEFINE FUNCTION fn::a($at: point, $area: geometry<polygon>, $route: option<geometry<line>>, $any: geometry<point | line>) { RETURN 1; };";
+        let (_, function) = parser::statements(sql).unwrap().remove(0);
+        let mut args: IncludeFnArgs = syn::parse_str(r#"driver as is; geo; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert!(args.geo);
+        let expected = quote! {
+            at: impl ::std::convert::Into < ::geo::Point<f64> >,
+            area: impl ::std::convert::Into < ::geo::Polygon<f64> >,
+            route: ::std::option::Option < ::geo::LineString<f64> >,
+            any: impl ::std::convert::Into < ::surrealdb::sql::Geometry >,
+        };
+        assert_eq!(function.params_to_args(&args).to_string(), expected.to_string());
+        let bindings = function.params_to_bindings(&args).to_string();
+        assert!(bindings.contains("Geometry as :: std :: convert :: From < :: geo :: Point < f64 >"), "{bindings}");
+        assert!(bindings.contains(". bind ((\"__fnarg_any\" , :: std :: convert :: Into :: into (any)))"), "{bindings}");
 
-fn generate_include(name: &Ident, path: &str) -> TokenStream2 {
-    quote! {
-        const #name : & 'static str = include_str ! (#path) ;
+        args.geo = false;
+        let tokens = function.params_to_args(&args).to_string();
+        assert!(!tokens.contains("geo ::"), "{tokens}");
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_serde_bindings() {
+        let sql = "DEFINE FUNCTION fn::a($user: object, $ids: array<int, 3>, $note: option<string>) { RETURN 1; };";
+        let (_, function) = parser::statements(sql).unwrap().remove(0);
+        let args: IncludeFnArgs =
+            syn::parse_str(r#"driver as is; datastore as ds_$; bindings as serde; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert_eq!(args.bindings, Bindings::Serde);
+        let expected = quote! {
+            user: impl ::serde::Serialize,
+            ids: impl ::serde::Serialize,
+            note: impl ::serde::Serialize,
+        };
+        assert_eq!(function.params_to_args(&args).to_string(), expected.to_string());
+        assert!(function.generics(None, &args).is_empty());
+        assert_eq!(function.params_to_bindings(&args).to_string(), quote! { .bind(("__fnarg_user", user)) .bind(("__fnarg_ids", ids)) .bind(("__fnarg_note", note)) }.to_string());
+        let variables = function.params_to_variables(&args).to_string();
+        assert!(variables.contains(&quote! { ::surrealdb::sql::to_value(note)? }.to_string()), "{variables}");
+        let sample = function.args[2].1.sample_tokens(&args).to_string();
+        assert_eq!(sample, quote! { ::std::option::Option::<::surrealdb::sql::Value>::None }.to_string());
+    }
 
     #[test]
-    fn test_module_tree() {
-        let paths = vec![PathBuf::from("tests/main.surql")];
-        let args = IncludeFnArgs {
-            paths: paths.iter().cloned().collect(),
-            driver: Some(Alias::AsIs),
-            datastore: Some(Alias::AsIs),
+    fn test_query_function() {
+        let sql = "DEFINE FUNCTION fn::a::b($name: string, $count: option<int>) { RETURN 1; };";
+        let (_, function) = parser::statements(sql).unwrap().remove(0);
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        let tokens = fragment::query_function(&function, &format_ident!("b_query"), &args).to_string();
+        assert!(tokens.contains("pub fn b_query (name : impl :: std :: convert :: Into < :: surrealdb :: sql :: Strand >"), "{tokens}");
+        assert!(tokens.contains("-> (:: std :: string :: String , :: std :: collections :: BTreeMap < :: std :: string :: String , :: surrealdb :: sql :: Value >)"), "{tokens}");
+        assert!(tokens.contains("bindings . insert (:: std :: string :: ToString :: to_string (\"__fnarg_count\")"), "{tokens}");
+        assert!(tokens.contains("(:: std :: string :: ToString :: to_string (\"RETURN fn::a::b($__fnarg_name, $__fnarg_count)\") , bindings)"), "{tokens}");
+
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; bindings as serde; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        let tokens = fragment::query_function(&function, &format_ident!("b_query"), &args).to_string();
+        assert!(tokens.contains("-> :: surrealdb :: Result < (:: std :: string :: String"), "{tokens}");
+    }
+
+    #[test]
+    fn test_binding_names() {
+        // `$value` and `$auth` are protected, binding them as is fails
+        let sql = "DEFINE FUNCTION fn::a($value: int, $auth: string) { RETURN $value; };";
+        let (_, function) = parser::statements(sql).unwrap().remove(0);
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert_eq!(function.custom_function_query(), "RETURN fn::a($__fnarg_value, $__fnarg_auth)");
+        let bindings = function.params_to_bindings(&args).to_string();
+        assert!(bindings.starts_with(". bind ((\"__fnarg_value\" ,"), "{bindings}");
+        assert!(bindings.contains(". bind ((\"__fnarg_auth\" , :: std :: convert :: Into :: into (auth)))"), "{bindings}");
+        assert!(function.params_to_variables(&args).to_string().contains("to_string (\"__fnarg_auth\")"));
+    }
+
+    #[test]
+    fn test_uuid() {
+        let sql = "DEFINE FUNCTION fn::a($id: uuid, $parent: option<uuid>) { RETURN 1; };";
+        let (_, function) = parser::statements(sql).unwrap().remove(0);
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; uuid; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert!(args.uuid);
+        let expected = quote! {
+            id: impl ::std::convert::Into < ::uuid::Uuid >,
+            parent: ::std::option::Option < ::uuid::Uuid >,
+        };
+        assert_eq!(function.params_to_args(&args).to_string(), expected.to_string());
+        let bindings = function.params_to_bindings(&args).to_string();
+        assert!(bindings.contains("Uuid as :: std :: convert :: From < :: uuid :: Uuid >> :: from (item)"), "{bindings}");
+        assert_eq!(function.args[0].1.sample_tokens(&args).to_string(), quote! { <::uuid::Uuid as ::std::default::Default>::default() }.to_string());
+    }
+
+    #[test]
+    fn test_type_map() {
+        let sql = "DEFINE FUNCTION fn::a::b($user: record<user>, $ttl: duration, $ids: array<int, 3>, $tag: string, $note: option<record<user>>) { RETURN 1; };";
+        let (_, function) = parser::statements(sql).unwrap().remove(0);
+        let args: IncludeFnArgs = syn::parse_str(
+            r#"driver as is; map record<user> => crate::UserId; map array<int,3> => crate::Ids; map fn::a::b($tag) => crate::Tag; "$CARGO_MANIFEST_DIR/tests/main.surql""#,
+        )
+        .unwrap();
+        let expected = quote! {
+            user: impl ::std::convert::Into < crate::UserId >,
+            ttl: impl ::std::convert::Into < ::surrealdb::sql::Duration >,
+            ids: impl ::std::convert::Into < crate::Ids >,
+            tag: impl ::std::convert::Into < crate::Tag >,
+            note: ::std::option::Option < crate::UserId >,
+        };
+        assert_eq!(function.params_to_args(&args).to_string(), expected.to_string());
+        assert!(function.generics(None, &args).is_empty());
+        let bindings = function.params_to_bindings(&args).to_string();
+        for converted in ["Thing as :: std :: convert :: From < crate :: UserId >", "Array as :: std :: convert :: From < crate :: Ids >", "String as :: std :: convert :: From < crate :: Tag >"] {
+            assert!(bindings.contains(converted), "{bindings}");
+        }
+        assert_eq!(function.param_sample("tag", &function.args[3].1, &args).to_string(), quote! { <crate::Tag as ::std::default::Default>::default() }.to_string());
+        args.types.check(&[function]);
+    }
+
+    #[test]
+    fn test_literal_types() {
+        let sql = r#"DEFINE FUNCTION fn::tasks::list($order: "asc" | "desc", $page: option<{ size: int, "after-id": option<record<task>> }>, $range: [int, int], $limit: int | "all") { RETURN 1; };"#;
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        let args: IncludeFnArgs = syn::parse_str(r#"driver as is; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        let expected = quote! {
+            order: impl ::std::convert::Into < TasksListOrder >,
+            page: ::std::option::Option < TasksListPage >,
+            range: impl ::std::convert::Into < TasksListRange >,
+            limit: impl ::std::convert::Into < ::surrealdb::sql::Value >,
+        };
+        assert_eq!(functions[0].params_to_args(&args).to_string(), expected.to_string());
+        let types = literal::literal_types(&functions, &args).to_string();
+        for item in [
+            "pub enum TasksListOrder { Asc , Desc }",
+            "TasksListOrder :: Desc => < :: surrealdb :: sql :: Value as :: std :: convert :: From < & str >> :: from (\"desc\")",
+            "pub struct TasksListPage { pub size : i64 , pub after_id : :: std :: option :: Option < :: surrealdb :: sql :: Thing > }",
+            "pub struct TasksListRange (pub i64 , pub i64) ;",
+        ] {
+            assert!(types.contains(item), "{types}");
+        }
+        assert!(!types.contains("TasksListLimit"), "{types}");
+        let tokens = build_mod_tree(functions.clone(), &[], &args).to_string();
+        assert!(tokens.contains("pub mod tasks { use super :: { TasksListOrder , TasksListPage , TasksListRange } ;"), "{tokens}");
+        assert_eq!(functions[0].param_sample("order", &functions[0].args[0].1, &args).to_string(), quote! { TasksListOrder::Asc }.to_string());
+    }
+
+    #[test]
+    fn test_output_types() {
+        let sql = r#"DEFINE FUNCTION fn::scores::top() -> array<{ id: record<user>, score: float, "type": string, tags: option<array<string>>, meta: object }> { RETURN []; };"#;
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        let mut args: IncludeFnArgs = syn::parse_str(r#"driver as is; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert_eq!(functions[0].value_output(&args).to_string(), quote! { ::std::vec::Vec<ScoresTopOutput> }.to_string());
+        let types = literal::literal_types(&functions, &args).to_string();
+        let expected = quote! {
+            #[derive(Debug, Clone, PartialEq, ::serde::Deserialize)]
+            pub struct ScoresTopOutput {
+                pub id: ::surrealdb::sql::Thing,
+                pub score: f64,
+                pub r#type: ::std::string::String,
+                pub tags: ::std::option::Option<::std::vec::Vec<::std::string::String> >,
+                pub meta: ::surrealdb::sql::Value
+            }
+        };
+        assert!(types.contains(&expected.to_string()), "{types}");
+        let tokens = build_mod_tree(functions.clone(), &[], &args).to_string();
+        assert!(tokens.contains("pub mod scores { use super :: { ScoresTopOutput } ;"), "{tokens}");
+
+        args.driver = None;
+        assert_eq!(functions[0].value_output(&args).to_string(), quote! { ::surrealdb::sql::Value }.to_string());
+        assert!(literal::literal_types(&functions, &args).is_empty());
+    }
+
+    #[test]
+    fn test_datetimes() {
+        let sql = "DEFINE FUNCTION fn::at::a($at: datetime, $until: option<datetime>) { RETURN 1; };";
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        let mut args: IncludeFnArgs = syn::parse_str(r#"driver as is; chrono; time; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert!(args.chrono && args.time);
+        let expected = quote! {
+            at: impl IntoDatetime,
+            until: ::std::option::Option < ::surrealdb::sql::Datetime >,
+        };
+        assert_eq!(functions[0].params_to_args(&args).to_string(), expected.to_string());
+        let bindings = functions[0].params_to_bindings(&args).to_string();
+        assert!(bindings.contains("IntoDatetime :: into_datetime (at)"), "{bindings}");
+        let tokens = build_mod_tree(functions.clone(), &[], &args).to_string();
+        assert!(tokens.contains("pub mod at { use super :: IntoDatetime ;"), "{tokens}");
+        let trait_items = datetime::datetime_trait(&args).to_string();
+        assert!(trait_items.contains(":: chrono :: DateTime < Tz >") && trait_items.contains(":: time :: OffsetDateTime"), "{trait_items}");
+
+        args.chrono = false;
+        args.time = false;
+        assert!(!build_mod_tree(functions, &[], &args).to_string().contains("IntoDatetime"));
+    }
+
+    #[test]
+    fn test_checked_args() {
+        let sql = "DEFINE FUNCTION fn::jobs::delay($name: string, $wait: duration, $times: option<int>, $tags: array<string>) { RETURN 1; };";
+        let functions: Vec<_> = parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect();
+        let mut args: IncludeFnArgs = syn::parse_str(r#"driver as is; checked_args; "$CARGO_MANIFEST_DIR/tests/main.surql""#).unwrap();
+        assert!(args.checked_args);
+        let expected = quote! {
+            name: impl IntoString,
+            wait: impl IntoDuration,
+            times: ::std::option::Option < i64 >,
+            tags: impl ::std::iter::IntoIterator < Item = impl IntoString >,
         };
-        let functions = parse_surrealql_files(&args).unwrap();
-        let _ = Function::from(functions);
+        assert_eq!(functions[0].params_to_args(&args).to_string(), expected.to_string());
+        let bindings = functions[0].params_to_bindings(&args).to_string();
+        assert!(bindings.contains("IntoDuration :: into_duration (wait)"), "{bindings}");
+        assert!(bindings.contains("IntoString :: into_strand (item)"), "{bindings}");
+        let tokens = build_mod_tree(functions.clone(), &[], &args).to_string();
+        assert!(tokens.contains("pub mod jobs { # [allow (unused_imports)] use super :: { IntoBool , IntoString ,"), "{tokens}");
+        let traits = checked::checked_traits().to_string();
+        assert!(traits.contains("# [diagnostic :: on_unimplemented (message = \"`{Self}` can't be passed to a `duration` parameter\" , label = \"not a `duration`\""), "{traits}");
+        assert!(traits.contains("impl IntoInt for u64"), "{traits}");
+        assert!(!traits.contains("impl IntoFloat for i32"), "{traits}");
+
+        args.checked_args = false;
+        assert!(!build_mod_tree(functions, &[], &args).to_string().contains("IntoString"));
+    }
+
+    #[test]
+    fn test_sensitive() {
+        let sql = "-- @sensitive $password, $token\n-- @alias login_old\nDEFINE FUNCTION fn::login($user: string, $password: string, $token: string) { RETURN true; };";
+        let (_, function) = parser::statements(sql).unwrap().remove(0);
+        assert_eq!(function.sensitive(), vec!["password", "token"]);
+        assert_eq!(function.aliases()[0].sensitive(), vec!["password", "token"]);
+    }
+
+    #[test]
+    fn test_expand_path_sorted() {
+        let dir = std::env::temp_dir().join("surrealdb_functions_sorted");
+        std::fs::create_dir_all(dir.join("a")).unwrap();
+        for file in ["c.surql", "a/b.surql", "a.surql", "ignored.txt"] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+        let paths = expand_path(&dir).unwrap();
+        assert_eq!(
+            paths,
+            vec![dir.join("a/b.surql"), dir.join("a.surql"), dir.join("c.surql")]
+        );
     }
 }
@@ -0,0 +1,434 @@
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use proc_macro_error::abort_call_site;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+
+use crate::{
+    external_value,
+    parser::{
+        kind::{Kind, Literal},
+        DefineFunctionStatement,
+    },
+    Bindings, Function, IncludeFnArgs, KindTokens, SurrealVersion, Wrappers,
+};
+
+/// What a parameter of a literal kind is taken as: an enum of its values, or a struct of its fields.
+pub(crate) enum Shape<'a> {
+    Enum(Vec<&'a Literal>),
+    Struct(&'a [(String, Kind)]),
+    Tuple(&'a [Kind]),
+}
+
+/// Types generated for literal kinds.
+pub(crate) trait LiteralKind {
+    /// Shape of the type generated for a parameter of this kind, also when it's wrapped in `option<T>`.
+    ///
+    /// Unions mixing literals with other kinds have no type of their own, they stay a `Value`.
+    fn literal_shape(&self) -> Option<Shape<'_>>;
+
+    /// A `Value` of this kind, for the items of literal kinds in the generated tests.
+    fn value_sample(&self, args: &IncludeFnArgs) -> TokenStream2;
+}
+
+impl LiteralKind for Kind {
+    fn literal_shape(&self) -> Option<Shape<'_>> {
+        match self {
+            Kind::Option(nested) => nested.literal_shape(),
+            Kind::Literal(Literal::Object(fields)) => Some(Shape::Struct(fields)),
+            Kind::Literal(Literal::Array(items)) => Some(Shape::Tuple(items)),
+            Kind::Literal(value) => Some(Shape::Enum(vec![value])),
+            Kind::Either(kinds) => kinds
+                .iter()
+                .map(|kind| match kind {
+                    Kind::Literal(value @ (Literal::String(_) | Literal::Number(_) | Literal::Bool(_))) => Some(value),
+                    _ => None,
+                })
+                .collect::<Option<_>>()
+                .map(Shape::Enum),
+            _ => None,
+        }
+    }
+
+    fn value_sample(&self, args: &IncludeFnArgs) -> TokenStream2 {
+        let krate = crate::krate();
+        match self {
+            Kind::Literal(Literal::Array(items)) => {
+                let items = items.iter().map(|kind| kind.value_sample(args));
+                quote! {
+                    #krate::sql::Value::Array(<#krate::sql::Array as ::std::convert::From<::std::vec::Vec<#krate::sql::Value>>>::from(::std::vec![#(#items),*]))
+                }
+            }
+            Kind::Literal(Literal::Object(fields)) => {
+                let fields = fields.iter().map(|(key, kind)| {
+                    let value = kind.value_sample(args);
+                    quote! { (::std::string::String::from(#key), #value) }
+                });
+                quote! {
+                    #krate::sql::Value::Object(<#krate::sql::Object as ::std::convert::From<::std::collections::BTreeMap<::std::string::String, #krate::sql::Value>>>::from(::std::collections::BTreeMap::from([#(#fields),*])))
+                }
+            }
+            Kind::Literal(value) => literal_value(value),
+            Kind::Either(kinds) => kinds.first().map_or_else(|| quote! { #krate::sql::Value::None }, |kind| kind.value_sample(args)),
+            Kind::Option(_) | Kind::Any | Kind::Unknown(_) => quote! { #krate::sql::Value::None },
+            _ if self.items().is_some() => quote! { #krate::sql::Value::Array(::std::default::Default::default()) },
+            _ => {
+                let sample = self.sample_tokens(args);
+                if let Some((external, sql)) = self.external_type(args) {
+                    external_value(&external, &sql, sample)
+                } else {
+                    let ty = self.surreal_tokens(args);
+                    quote! { <#krate::sql::Value as ::std::convert::From<#ty>>::from(#sample) }
+                }
+            }
+        }
+    }
+}
+
+/// The types generated for the literal kinds of a function.
+pub(crate) trait LiteralTypes {
+    /// Name of the type generated for the parameter `name`, if it's of a literal kind and isn't mapped.
+    ///
+    /// Aliases share the type of the function they are an alias of.
+    fn literal_type(&self, name: &str, kind: &Kind, args: &IncludeFnArgs) -> Option<Ident>;
+
+    /// An argument for the parameter `name` of a literal kind, for the generated tests.
+    fn literal_sample(&self, ty: &Ident, kind: &Kind, args: &IncludeFnArgs) -> TokenStream2;
+
+    /// Name of the struct generated for what the function returns, if it declares an object literal,
+    /// also in an `option`, `array` or `set`.
+    ///
+    /// Only the `_value` wrappers of the driver and blocking functions deserialize into it.
+    fn output_type(&self, args: &IncludeFnArgs) -> Option<Ident>;
+
+    /// The function called, in camel case, which the generated types are named after.
+    fn type_prefix(&self) -> String;
+}
+
+impl LiteralTypes for DefineFunctionStatement {
+    fn literal_type(&self, name: &str, kind: &Kind, args: &IncludeFnArgs) -> Option<Ident> {
+        if args.bindings == Bindings::Serde || self.mapped_param(name, kind, args).is_some() {
+            return None;
+        }
+        kind.literal_shape()?;
+        let inner = match kind {
+            Kind::Option(nested) => nested,
+            kind => kind,
+        };
+        if kind.external_type(args).is_some() || inner.external_type(args).is_some() {
+            return None;
+        }
+        Some(format_ident!("{}{}", self.type_prefix(), camel_case(name)))
+    }
+
+    fn literal_sample(&self, ty: &Ident, kind: &Kind, args: &IncludeFnArgs) -> TokenStream2 {
+        if let Kind::Option(_) = kind {
+            return quote! { ::std::option::Option::None };
+        }
+        match kind.literal_shape() {
+            Some(Shape::Enum(values)) => {
+                let variant = variant_name(values[0]);
+                quote! { #ty::#variant }
+            }
+            Some(Shape::Struct(fields)) => {
+                let fields = fields.iter().map(|(key, kind)| {
+                    let field = field_name(key);
+                    let value = field_sample(kind, args);
+                    quote! { #field: #value }
+                });
+                quote! { #ty { #(#fields),* } }
+            }
+            Some(Shape::Tuple(items)) => {
+                let items = items.iter().map(|kind| field_sample(kind, args));
+                quote! { #ty(#(#items),*) }
+            }
+            None => unreachable!(),
+        }
+    }
+
+    fn output_type(&self, args: &IncludeFnArgs) -> Option<Ident> {
+        if args.driver.is_none() && args.blocking.is_none() {
+            return None;
+        }
+        let Kind::Literal(Literal::Object(_)) = returned_item(self.returns.as_ref()?) else {
+            return None;
+        };
+        Some(format_ident!("{}Output", self.type_prefix()))
+    }
+
+    fn type_prefix(&self) -> String {
+        let name = self.alias_of.as_ref().unwrap_or(&self.name);
+        // Transliterated first, so `café` doesn't lose its last letter
+        let name: Vec<_> = name[usize::from(self.version.is_some())..].iter().map(|segment| crate::rust_segment(segment)).collect();
+        crate::rust_segment(&camel_case(&name.join("_")))
+    }
+}
+
+/// The kind of the returned items, unwrapping an `option`, `array` or `set`.
+pub(crate) fn returned_item(kind: &Kind) -> &Kind {
+    match kind {
+        Kind::Option(kind) | Kind::Array(kind, _) | Kind::Set(kind, _) => kind,
+        kind => kind,
+    }
+}
+
+impl Function {
+    /// Names of the literal types taken by the functions of this module and the modules in it, with their `@cfg`.
+    pub(crate) fn literal_types(&self, args: &IncludeFnArgs) -> Vec<(Ident, Option<TokenStream2>)> {
+        let mut out = literal_names(&self.0, args);
+        for nested in self.1.values() {
+            out.extend(nested.literal_types(args));
+        }
+        out.sort_by_key(|(name, _)| name.to_string());
+        out.dedup_by_key(|(name, _)| name.to_string());
+        out
+    }
+}
+
+/// Names of the literal types taken by `functions`, along with the `@cfg` of the function they're generated for.
+pub(crate) fn literal_names(functions: &[DefineFunctionStatement], args: &IncludeFnArgs) -> Vec<(Ident, Option<TokenStream2>)> {
+    let mut out: Vec<_> = functions
+        .iter()
+        .flat_map(|function| {
+            function
+                .args
+                .iter()
+                .filter_map(|(name, kind)| Some((function.literal_type(name, kind, args)?, function.cfg())))
+                .chain(function.output_type(args).map(|name| (name, function.cfg())))
+        })
+        .collect();
+    out.sort_by_key(|(name, _)| name.to_string());
+    out.dedup_by_key(|(name, _)| name.to_string());
+    out
+}
+
+/// Imports the literal types `names` from the parent module, where they are generated.
+pub(crate) fn imports(names: &[(Ident, Option<TokenStream2>)]) -> TokenStream2 {
+    let (conditional, always): (Vec<_>, Vec<_>) = names.iter().partition(|(_, cfg)| cfg.is_some());
+    let mut out: TokenStream2 = conditional.iter().map(|(name, cfg)| quote! { #cfg use super::#name; }).collect();
+    if !always.is_empty() {
+        let names = always.iter().map(|(name, _)| name);
+        out.extend(quote! { use super::{#(#names),*}; });
+    }
+    out
+}
+
+/// Generates an enum or struct for every parameter of a literal kind, converting into the `Value` it stands for.
+pub(crate) fn literal_types(functions: &[DefineFunctionStatement], args: &IncludeFnArgs) -> TokenStream2 {
+    let mut out = TokenStream2::new();
+    for function in functions.iter().filter(|function| function.alias_of.is_none()) {
+        for (name, kind) in &function.args {
+            let Some(ty) = function.literal_type(name, kind, args) else {
+                continue;
+            };
+            let doc = format!("Argument for `${name}` of `fn::{}`, of kind `{kind}`.", function.name.join("::"));
+            let item = match kind.literal_shape().unwrap() {
+                Shape::Enum(values) => enum_type(&ty, &values, function, name),
+                Shape::Struct(fields) => struct_type(&ty, fields, args),
+                Shape::Tuple(items) => tuple_type(&ty, items, args),
+            };
+            let item = quote! {
+                #[doc = #doc]
+                #item
+            };
+            out.extend(crate::attributed_items(function.cfg(), item));
+        }
+        if let Some(ty) = function.output_type(args) {
+            if let Some((name, _)) = function.args.iter().find(|(name, kind)| function.literal_type(name, kind, args).as_ref() == Some(&ty)) {
+                abort_call_site!("the argument type for `${}` of `fn::{}` and its output type both become `{}`", name, function.name.join("::"), ty; help = "map the parameter to a type of your own with `map fn::{}(${}) => <type>`", function.name.join("::"), name)
+            }
+            let Some(Kind::Literal(Literal::Object(fields))) = function.returns.as_ref().map(returned_item) else {
+                unreachable!()
+            };
+            let doc = format!("What `fn::{}` returns, of kind `{}`.", function.name.join("::"), function.returns.as_ref().unwrap());
+            let item = output_type(&ty, fields, args.version);
+            let item = quote! {
+                #[doc = #doc]
+                #item
+            };
+            out.extend(crate::attributed_items(function.cfg(), item));
+        }
+    }
+    out
+}
+
+/// A struct deserializing the object returned by a function, with the fields of its kind.
+fn output_type(ty: &Ident, fields: &[(String, Kind)], version: SurrealVersion) -> TokenStream2 {
+    let fields = fields.iter().map(|(key, kind)| {
+        let name = field_name(key);
+        let ty = output_field_type(kind, version);
+        // Raw identifiers are renamed by serde already
+        let rename = (name.unraw() != key.as_str()).then(|| quote! { #[serde(rename = #key)] });
+        quote! { #rename pub #name: #ty }
+    });
+    quote! {
+        #[derive(Debug, Clone, PartialEq, ::serde::Deserialize)]
+        pub struct #ty {
+            #(#fields),*
+        }
+    }
+}
+
+/// Type of a field of a returned object, a `Value` for kinds without a Rust type of their own.
+fn output_field_type(kind: &Kind, version: SurrealVersion) -> TokenStream2 {
+    match kind {
+        Kind::Option(kind) => {
+            let kind = output_field_type(kind, version);
+            quote! { ::std::option::Option<#kind> }
+        }
+        Kind::Array(kind, _) | Kind::Set(kind, _) => {
+            let kind = output_field_type(kind, version);
+            quote! { ::std::vec::Vec<#kind> }
+        }
+        kind => kind.output_tokens(version).unwrap_or_else(|| version.value_tokens()),
+    }
+}
+
+fn enum_type(ty: &Ident, values: &[&Literal], function: &DefineFunctionStatement, param: &str) -> TokenStream2 {
+    let krate = crate::krate();
+    let variants: Vec<_> = values.iter().map(|value| variant_name(value)).collect();
+    for (i, variant) in variants.iter().enumerate() {
+        if variants[..i].contains(variant) {
+            abort_call_site!("two values of `${}` of `fn::{}` become the variant `{}`", param, function.name.join("::"), variant; help = "map the parameter to a type of your own with `map fn::{}(${}) => <type>`", function.name.join("::"), param)
+        }
+    }
+    let values = values.iter().map(|value| literal_value(value));
+    quote! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum #ty {
+            #(#variants),*
+        }
+
+        impl ::std::convert::From<#ty> for #krate::sql::Value {
+            fn from(value: #ty) -> Self {
+                match value {
+                    #(#ty::#variants => #values),*
+                }
+            }
+        }
+    }
+}
+
+fn struct_type(ty: &Ident, fields: &[(String, Kind)], args: &IncludeFnArgs) -> TokenStream2 {
+    let krate = crate::krate();
+    let names: Vec<_> = fields.iter().map(|(key, _)| field_name(key)).collect();
+    let types = fields.iter().map(|(_, kind)| field_type(kind, args));
+    let keys = fields.iter().map(|(key, _)| key);
+    let values = fields.iter().zip(&names).map(|((_, kind), name)| field_value(kind, quote! { value.#name }, args));
+    quote! {
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct #ty {
+            #(pub #names: #types),*
+        }
+
+        impl ::std::convert::From<#ty> for #krate::sql::Value {
+            fn from(value: #ty) -> Self {
+                let mut object = ::std::collections::BTreeMap::<::std::string::String, #krate::sql::Value>::new();
+                #(object.insert(::std::string::String::from(#keys), #values);)*
+                #krate::sql::Value::Object(#krate::sql::Object::from(object))
+            }
+        }
+    }
+}
+
+fn tuple_type(ty: &Ident, items: &[Kind], args: &IncludeFnArgs) -> TokenStream2 {
+    let krate = crate::krate();
+    let types = items.iter().map(|kind| field_type(kind, args));
+    let values = items.iter().enumerate().map(|(i, kind)| {
+        let index = syn::Index::from(i);
+        field_value(kind, quote! { value.#index }, args)
+    });
+    quote! {
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct #ty(#(pub #types),*);
+
+        impl ::std::convert::From<#ty> for #krate::sql::Value {
+            fn from(value: #ty) -> Self {
+                #krate::sql::Value::Array(#krate::sql::Array::from(::std::vec![#(#values),*]))
+            }
+        }
+    }
+}
+
+/// Type of a field of a literal object or array, keeping `option<T>` an `Option`.
+fn field_type(kind: &Kind, args: &IncludeFnArgs) -> TokenStream2 {
+    match kind {
+        Kind::Option(nested) => {
+            let nested = nested.concrete_tokens(args);
+            quote! { ::std::option::Option<#nested> }
+        }
+        _ => kind.concrete_tokens(args),
+    }
+}
+
+fn field_value(kind: &Kind, value: TokenStream2, args: &IncludeFnArgs) -> TokenStream2 {
+    let krate = crate::krate();
+    if let Kind::Option(_) = kind {
+        return kind.value_tokens(value, args);
+    }
+    if let Some((external, sql)) = kind.external_type(args) {
+        return external_value(&external, &sql, value);
+    }
+    let ty = kind.surreal_tokens(args);
+    quote! { <#krate::sql::Value as ::std::convert::From<#ty>>::from(#value) }
+}
+
+fn field_sample(kind: &Kind, args: &IncludeFnArgs) -> TokenStream2 {
+    let krate = crate::krate();
+    if kind.external_type(args).is_some() {
+        return kind.sample_tokens(args);
+    }
+    match kind {
+        Kind::Option(_) => quote! { ::std::option::Option::None },
+        Kind::Set(_, _) | Kind::Array(_, _) => quote! { <#krate::sql::Array as ::std::default::Default>::default() },
+        Kind::Any | Kind::Either(_) | Kind::Literal(_) => kind.value_sample(args),
+        _ => kind.sample_tokens(args),
+    }
+}
+
+/// The `Value` a string, number or bool literal stands for.
+fn literal_value(value: &Literal) -> TokenStream2 {
+    let krate = crate::krate();
+    match value {
+        Literal::String(value) => quote! { <#krate::sql::Value as ::std::convert::From<&str>>::from(#value) },
+        Literal::Number(value) => {
+            if let Ok(value) = value.parse::<i64>() {
+                quote! { <#krate::sql::Value as ::std::convert::From<i64>>::from(#value) }
+            } else {
+                let value: f64 = value.parse().unwrap_or_default();
+                quote! { <#krate::sql::Value as ::std::convert::From<f64>>::from(#value) }
+            }
+        }
+        Literal::Bool(value) => quote! { <#krate::sql::Value as ::std::convert::From<bool>>::from(#value) },
+        Literal::Array(_) | Literal::Object(_) => unreachable!(),
+    }
+}
+
+/// `"in-progress"` becomes `InProgress`, `-1.5` becomes `Neg1_5` and `true` becomes `True`.
+fn variant_name(value: &Literal) -> Ident {
+    let name = match value {
+        Literal::String(value) => camel_case(value),
+        Literal::Number(value) => value.replace('-', "Neg").replace('.', "_"),
+        Literal::Bool(value) => camel_case(&value.to_string()),
+        Literal::Array(_) | Literal::Object(_) => unreachable!(),
+    };
+    match name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        true => Ident::new(&name, Span::call_site()),
+        false => format_ident!("V{}", name),
+    }
+}
+
+/// Field named after an object key, `first-seen` becomes `first_seen` and `type` becomes `r#type`.
+fn field_name(key: &str) -> Ident {
+    crate::rust_ident(key)
+}
+
+pub(crate) fn camel_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut part = part.chars();
+            let first = part.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first).chain(part).collect::<String>()
+        })
+        .collect()
+}
@@ -0,0 +1,93 @@
+use proc_macro2::{Ident, TokenStream as TokenStream2};
+use proc_macro_error::abort_call_site;
+use quote::quote;
+
+use crate::{
+    binding_name,
+    parser::{self, scan, DefineFunctionStatement},
+    IncludeFnArgs, Wrappers,
+};
+
+/// The `LIVE SELECT` a function gets a `_live` wrapper for.
+pub(crate) trait Live {
+    /// The `LIVE SELECT` statement of the function body, if the function is marked `-- @live` or its body has one.
+    ///
+    /// Functions can't stream their notifications, so the `_live` wrapper runs the statement itself.
+    fn live_statement(&self) -> Option<String>;
+}
+
+impl Live for DefineFunctionStatement {
+    fn live_statement(&self) -> Option<String> {
+        let marked = self.directives.iter().any(|directive| directive.name == "live");
+        let statement = self.definition.as_deref().and_then(live_select);
+        if marked && statement.is_none() {
+            abort_call_site!(format!("`fn::{}` is marked `@live` but its body has no `LIVE SELECT`", self.name.join("::")); help = "the `_live` wrapper runs the `LIVE SELECT` of the body, like `RETURN LIVE SELECT * FROM user WHERE team = $team;`")
+        }
+        statement
+    }
+}
+
+/// The first `LIVE SELECT` in `definition`, up to the end of its statement.
+fn live_select(definition: &str) -> Option<String> {
+    // The signature can't hold one, so the whole statement is searched, up to the closing brace of the body
+    let body = &definition[..definition.rfind('}')?];
+    let upper = body.to_ascii_uppercase();
+    let start = upper.match_indices("LIVE").map(|(i, _)| i).find(|&i| {
+        let before = upper[..i].chars().next_back();
+        let after = &upper[i + 4..];
+        !before.is_some_and(|c| c.is_alphanumeric() || c == '_')
+            && after.starts_with(char::is_whitespace)
+            && after.trim_start().strip_prefix("SELECT").is_some_and(|rest| !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_'))
+    })?;
+    let statement = &body[start..];
+    // Up to the `;` ending it, not one in a string of its condition
+    let end = scan::statement(statement).map_or(statement.len(), |(_, text)| text.len());
+    Some(statement[..end].trim().to_string())
+}
+
+/// `statement` with the parameters of `function` renamed to the names they're bound as, other parameters like `$auth` are kept.
+pub(crate) fn bound_statement(function: &DefineFunctionStatement, statement: &str) -> String {
+    let mut out = String::new();
+    let mut last = 0;
+    for (range, name) in scan::params(statement) {
+        if function.args.iter().any(|(param, _)| **param == name) {
+            out.push_str(&statement[last..range.start]);
+            out.push('$');
+            out.push_str(&parser::ident::Ident::from(binding_name(&name)).to_string());
+            last = range.end;
+        }
+    }
+    out.push_str(&statement[last..]);
+    out
+}
+
+/// Generates a `_live` wrapper, starting the `LIVE SELECT` of the function with its parameters bound
+/// and returning the stream of its notifications.
+pub(crate) fn live_function(
+    function: &DefineFunctionStatement,
+    statement: &str,
+    name: &Ident,
+    args: &IncludeFnArgs,
+) -> TokenStream2 {
+    let krate = crate::krate();
+    let comments = function.attributes(args);
+    let params = function.params_to_args(args);
+    let generics = function.generics(args.engine.param(), args);
+    let connection = args.engine.connection();
+    let bind = function.params_to_bindings(args);
+    let value = args.version.value_tokens();
+    // The parameters are bound under their prefixed names, like the call of the function
+    let statement = bound_statement(function, statement);
+    quote! {
+        #comments
+        #[doc = ""]
+        #[doc = "Starts the `LIVE SELECT` of the function body with the parameters bound, streaming its notifications."]
+        #[doc = "Dropping the stream kills the live query."]
+        pub async fn #name #generics(db: &#krate::Surreal<#connection>, #params) -> #krate::Result<#krate::method::QueryStream<#krate::Notification<#value>>> {
+            db.query(#statement)
+                #bind
+                .await?
+                .stream(0)
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
+use proc_macro_error::{abort, abort_call_site};
+use quote::ToTokens;
+use syn::{ext::IdentExt, parse::Parse};
+
+use crate::parser::{kind::Kind, DefineFunctionStatement};
+
+/// Rust types taken instead of the default ones, from `map <kind> => <type>` and `map fn::name($param) => <type>`.
+#[derive(Debug, Default)]
+pub(crate) struct TypeMap {
+    kinds: Vec<(Kind, TokenStream2)>,
+    /// Keyed by the function name without `fn::` and the parameter name without `$`.
+    params: Vec<((String, String), TokenStream2)>,
+}
+
+/// A single `map ... => <type>` entry, after the `map`.
+#[derive(Debug)]
+pub(crate) enum Mapping {
+    Kind(Kind, TokenStream2),
+    Param { function: String, param: String, ty: TokenStream2 },
+}
+
+impl Parse for Mapping {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        if input.peek(syn::Token![fn]) {
+            input.parse::<syn::Token![fn]>()?;
+            let mut function = vec![];
+            while input.peek(syn::Token![::]) {
+                input.parse::<syn::Token![::]>()?;
+                function.push(syn::Ident::parse_any(input)?.to_string());
+            }
+            let content;
+            syn::parenthesized!(content in input);
+            let dollar: syn::Token![$] = content.parse()?;
+            let param: syn::Ident = content.parse()?;
+            input.parse::<syn::Token![=>]>()?;
+            let ty: syn::Type = input.parse()?;
+            if function.is_empty() {
+                abort!(dollar, "missing function name"; help = "expected `map fn::name($param) => <type>`")
+            }
+            return Ok(Self::Param {
+                function: function.join("::"),
+                param: param.to_string(),
+                ty: ty.into_token_stream(),
+            });
+        }
+
+        // The kind is written like in the .surql files, so it's reassembled and run through the same parser
+        let mut text = String::new();
+        let start = input.span();
+        while !input.is_empty() && !input.peek(syn::Token![=>]) {
+            text.push_str(&input.parse::<TokenTree>()?.to_string());
+        }
+        input.parse::<syn::Token![=>]>()?;
+        let ty: syn::Type = input.parse()?;
+        match crate::parser::kind::kind(&text) {
+            Ok(("", kind)) => Ok(Self::Kind(kind, ty.into_token_stream())),
+            _ => abort!(start, "invalid kind `{}`", text; help = "expected `map <kind> => <type>`, like `map record<user> => crate::UserId`"),
+        }
+    }
+}
+
+impl TypeMap {
+    pub(crate) fn push(&mut self, mapping: Mapping) {
+        match mapping {
+            Mapping::Kind(kind, ty) => self.kinds.push((kind, ty)),
+            Mapping::Param { function, param, ty } => self.params.push(((function, param), ty)),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.kinds.is_empty() && self.params.is_empty()
+    }
+
+    /// The type mapped for parameters of `kind`.
+    pub(crate) fn kind(&self, kind: &Kind) -> Option<&TokenStream2> {
+        self.kinds.iter().find(|(mapped, _)| mapped == kind).map(|(_, ty)| ty)
+    }
+
+    /// The type mapped for the parameter `param` of `function`, named without `fn::`.
+    pub(crate) fn param(&self, function: &str, param: &str) -> Option<&TokenStream2> {
+        self.params
+            .iter()
+            .find(|((mapped, name), _)| mapped == function && name == param)
+            .map(|(_, ty)| ty)
+    }
+
+    /// Fails if a parameter is mapped that none of the functions have, like after renaming it.
+    pub(crate) fn check(&self, functions: &[DefineFunctionStatement]) {
+        for ((function, param), _) in &self.params {
+            let found = functions
+                .iter()
+                .any(|f| f.name.join("::") == *function && f.args.iter().any(|(arg, _)| arg.0 == *param));
+            if !found {
+                abort_call_site!("`map fn::{}(${})` doesn't name a parameter of the included functions", function, param; help = "check the function and parameter names, or remove the mapping")
+            }
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+
+use crate::{parser::DefineFunctionStatement, Wrappers};
+
+/// Generates `FUNCTIONS`, describing every included function for UIs and tooling.
+pub(crate) fn metadata_const(functions: &[DefineFunctionStatement]) -> TokenStream2 {
+    let functions = functions.iter().map(|function| {
+        let name = function.name.join("::");
+        let sensitive = function.sensitive();
+        let params = function.args.iter().map(|(param, kind)| {
+            let name = param.to_raw();
+            let kind = kind.to_string();
+            let sensitive = sensitive.contains(&param.0);
+            quote! {
+                ParamMeta {
+                    name: #name,
+                    kind: #kind,
+                    sensitive: #sensitive,
+                },
+            }
+        });
+        let returns = if let Some(kind) = &function.returns {
+            let kind = kind.to_string();
+            quote! { ::std::option::Option::Some(#kind) }
+        } else {
+            quote! { ::std::option::Option::None }
+        };
+        let signature = function.signature();
+        let deprecated = function.deprecation().is_some();
+        let cfg = function.cfg();
+        quote! {
+            #cfg
+            FunctionMeta {
+                name: #name,
+                params: &[#(#params)*],
+                returns: #returns,
+                signature: #signature,
+                deprecated: #deprecated,
+            },
+        }
+    });
+
+    quote! {
+        #[doc = "A parameter of one of the included functions."]
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::marker::Copy, ::std::cmp::PartialEq, ::std::cmp::Eq)]
+        pub struct ParamMeta {
+            #[doc = "Name of the parameter, without the `$`."]
+            pub name: &'static str,
+            pub kind: &'static str,
+            #[doc = "Marked with `-- @sensitive`, so its value should never be shown or logged."]
+            pub sensitive: bool,
+        }
+
+        #[doc = "One of the included functions, as declared in the .surql files."]
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::marker::Copy, ::std::cmp::PartialEq, ::std::cmp::Eq)]
+        pub struct FunctionMeta {
+            #[doc = "Name of the function, without the `fn::` prefix."]
+            pub name: &'static str,
+            pub params: &'static [ParamMeta],
+            #[doc = "The kind declared with `-> kind` or `-- @returns kind`."]
+            pub returns: ::std::option::Option<&'static str>,
+            #[doc = "The whole signature, like `fn::greet($name: string) -> string`, as checked by `load_functions_from`."]
+            pub signature: &'static str,
+            #[doc = "Marked with `-- @deprecated` or generated from an `-- @alias` directive."]
+            pub deprecated: bool,
+        }
+
+        #[doc = "Every included function, in the order they are included."]
+        pub const FUNCTIONS: &[FunctionMeta] = &[#(#functions)*];
+    }
+}
@@ -0,0 +1,56 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+
+use crate::{parser::DefineFunctionStatement, IncludeFnArgs};
+
+/// Generates `FunctionCallObserver` and `set_function_observer`, where calls are reported once registered.
+pub(crate) fn observer_items() -> TokenStream2 {
+    quote! {
+        #[doc = "Told about every call of the included functions, to record call counts and latencies."]
+        #[doc = ""]
+        #[doc = "Implemented for closures taking the same arguments as `observe`."]
+        pub trait FunctionCallObserver: ::std::marker::Send + ::std::marker::Sync + 'static {
+            #[doc = "Called once `function` (without the `fn::` prefix) returns, with how long it took and whether it succeeded."]
+            fn observe(&self, function: &str, elapsed: ::std::time::Duration, succeeded: bool);
+        }
+
+        impl<F> FunctionCallObserver for F
+        where
+            F: ::std::ops::Fn(&str, ::std::time::Duration, bool) + ::std::marker::Send + ::std::marker::Sync + 'static,
+        {
+            fn observe(&self, function: &str, elapsed: ::std::time::Duration, succeeded: bool) {
+                self(function, elapsed, succeeded)
+            }
+        }
+
+        static FUNCTION_OBSERVER: ::std::sync::OnceLock<::std::boxed::Box<dyn FunctionCallObserver>> = ::std::sync::OnceLock::new();
+
+        #[doc = "Registers the observer told about every call, returns `false` if one was already registered, which is kept."]
+        pub fn set_function_observer(observer: impl FunctionCallObserver) -> bool {
+            FUNCTION_OBSERVER.set(::std::boxed::Box::new(observer)).is_ok()
+        }
+    }
+}
+
+/// Wraps `body`, evaluating to the `Result` of calling `function`, to report the call to the registered observer.
+///
+/// `root` leads to the module `FUNCTION_OBSERVER` is generated in.
+pub(crate) fn observed(
+    args: &IncludeFnArgs,
+    function: &DefineFunctionStatement,
+    root: &TokenStream2,
+    body: TokenStream2,
+) -> TokenStream2 {
+    if !args.metrics {
+        return body;
+    }
+    let name = function.name.join("::");
+    quote! {
+        let started = ::std::time::Instant::now();
+        let result = async { #body }.await;
+        if let ::std::option::Option::Some(observer) = #root FUNCTION_OBSERVER.get() {
+            observer.observe(#name, started.elapsed(), result.is_ok());
+        }
+        result
+    }
+}
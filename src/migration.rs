@@ -0,0 +1,251 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+
+use crate::{source::SourceFile, IncludeFnArgs, Wrappers};
+
+/// `(name, statement)` of every function the wrappers are generated from.
+fn definitions(sources: &[SourceFile]) -> TokenStream2 {
+    let definitions = sources.iter().flat_map(|source| {
+        source.functions.iter().map(|(range, function)| {
+            let name = function.name.join("::");
+            let statement = &source.contents[range.clone()];
+            let cfg = function.cfg();
+            quote! { #cfg (#name, #statement), }
+        })
+    });
+    quote! {
+        const DEFINITIONS: &[(&str, &str)] = &[#(#definitions)*];
+    }
+}
+
+/// Generates `defined_functions(db)`, reading the definition of every function in the database from `INFO FOR DB`.
+pub(crate) fn database_functions(args: &IncludeFnArgs) -> TokenStream2 {
+    let krate = crate::krate();
+    let (name, _) = args.transform_fn_name("defined_functions");
+    let name = name.unwrap();
+    let value = args.version.value_tokens();
+    let info = args.version.into_sql(quote! { info });
+    let generics = args.engine.generics();
+    let connection = args.engine.connection();
+
+    quote! {
+        async fn #name #generics(db: &#krate::Surreal<#connection>) -> #krate::Result<::std::collections::BTreeMap<::std::string::String, ::std::string::String>> {
+            let mut response = db.query("INFO FOR DB").await?.check()?;
+            let info: #value = response.take(0)?;
+            // Read the same way as the `surrealdb-functions` tool does
+            ::surrealdb_functions_runtime::function_definitions(#info)
+        }
+    }
+}
+
+/// Tokens comparing the `current` definition from the database with the included `statement`, once parsed.
+fn same_definition() -> TokenStream2 {
+    let krate = crate::krate();
+    quote! {
+        ::std::matches!(
+            (#krate::sql::parse(&current), #krate::sql::parse(statement)),
+            (::std::result::Result::Ok(current), ::std::result::Result::Ok(statement)) if current == statement
+        )
+    }
+}
+
+/// Generates `plan_migration(db)`, comparing the functions defined in the database with the included ones.
+pub(crate) fn migration_functions(args: &IncludeFnArgs, sources: &[SourceFile]) -> TokenStream2 {
+    let krate = crate::krate();
+    let (name, _) = args.transform_fn_name("plan_migration");
+    let name = name.unwrap();
+    let (defined, _) = args.transform_fn_name("defined_functions");
+    let definitions = definitions(sources);
+    let same_definition = same_definition();
+    let generics = args.engine.generics();
+    let connection = args.engine.connection();
+
+    quote! {
+        #[doc = "What has to be done to a function for the database to match the included files."]
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::marker::Copy, ::std::cmp::PartialEq, ::std::cmp::Eq)]
+        pub enum MigrationAction {
+            #[doc = "The function isn't defined in the database yet."]
+            Create,
+            #[doc = "The function is defined in the database, but differently."]
+            Update,
+            #[doc = "The function is defined in the database, but not in the included files."]
+            Remove,
+        }
+
+        #[doc = "One function to create, update or remove, along with the statement doing it."]
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::cmp::PartialEq, ::std::cmp::Eq)]
+        pub struct MigrationStep {
+            pub action: MigrationAction,
+            #[doc = "Name of the function, without the `fn::` prefix."]
+            pub name: ::std::string::String,
+            pub statement: ::std::string::String,
+        }
+
+        #[doc = "The steps bringing the functions of a database in line with the included files, returned by `plan_migration`."]
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::default::Default, ::std::cmp::PartialEq, ::std::cmp::Eq)]
+        pub struct MigrationPlan {
+            pub steps: ::std::vec::Vec<MigrationStep>,
+        }
+
+        impl MigrationPlan {
+            #[doc = "Whether the database already matches the included files."]
+            pub fn is_empty(&self) -> bool {
+                self.steps.is_empty()
+            }
+
+            #[doc = "Runs every step in a single transaction."]
+            pub async fn apply #generics(&self, db: &#krate::Surreal<#connection>) -> #krate::Result<#krate::Response> {
+                let mut query = ::std::string::ToString::to_string("BEGIN TRANSACTION;\n");
+                for step in &self.steps {
+                    query.push_str(&step.statement);
+                    query.push_str(";\n");
+                }
+                query.push_str("COMMIT TRANSACTION;");
+                db.query(query).await
+            }
+        }
+
+        #[doc = "Compares the functions defined in the database (from `INFO FOR DB`) with the included ones."]
+        #[doc = ""]
+        #[doc = "Definitions are compared once parsed, so formatting doesn't matter. Every function of the database missing from the"]
+        #[doc = "included files is planned for removal, including the ones defined by something else."]
+        pub async fn #name #generics(db: &#krate::Surreal<#connection>) -> #krate::Result<MigrationPlan> {
+            #definitions
+
+            let mut existing = #defined(db).await?;
+            let mut plan = <MigrationPlan as ::std::default::Default>::default();
+            for (name, statement) in DEFINITIONS {
+                let action = match existing.remove(*name) {
+                    ::std::option::Option::None => MigrationAction::Create,
+                    ::std::option::Option::Some(current) if #same_definition => continue,
+                    ::std::option::Option::Some(_) => MigrationAction::Update,
+                };
+                plan.steps.push(MigrationStep {
+                    action,
+                    name: ::std::string::ToString::to_string(*name),
+                    statement: ::std::string::ToString::to_string(*statement),
+                });
+            }
+            for name in ::std::collections::BTreeMap::into_keys(existing) {
+                plan.steps.push(MigrationStep {
+                    action: MigrationAction::Remove,
+                    statement: ::std::format!("REMOVE FUNCTION fn::{name}"),
+                    name,
+                });
+            }
+            ::std::result::Result::Ok(plan)
+        }
+    }
+}
+
+/// Generates `verify_functions(db)`, reporting the included functions missing from the database or defined differently.
+pub(crate) fn verify_function(args: &IncludeFnArgs, sources: &[SourceFile]) -> TokenStream2 {
+    let krate = crate::krate();
+    let (name, _) = args.transform_fn_name("verify_functions");
+    let name = name.unwrap();
+    let (defined, _) = args.transform_fn_name("defined_functions");
+    let definitions = definitions(sources);
+    let same_definition = same_definition();
+    let generics = args.engine.generics();
+    let connection = args.engine.connection();
+
+    quote! {
+        #[doc = "The included functions that aren't defined in a database as they are in the included files, returned by `verify_functions`."]
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::default::Default, ::std::cmp::PartialEq, ::std::cmp::Eq)]
+        pub struct VerificationReport {
+            #[doc = "Functions that aren't defined at all, without the `fn::` prefix."]
+            pub missing: ::std::vec::Vec<::std::string::String>,
+            #[doc = "Functions defined differently than in the included files, without the `fn::` prefix."]
+            pub stale: ::std::vec::Vec<::std::string::String>,
+        }
+
+        impl VerificationReport {
+            #[doc = "Whether every function can be called, even if some are outdated."]
+            pub fn is_ok(&self) -> bool {
+                self.missing.is_empty()
+            }
+
+            #[doc = "Whether every function is defined exactly as in the included files."]
+            pub fn is_current(&self) -> bool {
+                self.missing.is_empty() && self.stale.is_empty()
+            }
+        }
+
+        #[doc = "Checks that every included function is defined in the database (from `INFO FOR DB`), and whether its definition matches."]
+        #[doc = ""]
+        #[doc = "Meant as a health check on startup, check `is_ok` for the functions to exist or `is_current` for them to be up to date."]
+        pub async fn #name #generics(db: &#krate::Surreal<#connection>) -> #krate::Result<VerificationReport> {
+            #definitions
+
+            let mut existing = #defined(db).await?;
+            let mut report = <VerificationReport as ::std::default::Default>::default();
+            for (name, statement) in DEFINITIONS {
+                match existing.remove(*name) {
+                    ::std::option::Option::None => report.missing.push(::std::string::ToString::to_string(*name)),
+                    ::std::option::Option::Some(current) if #same_definition => {}
+                    ::std::option::Option::Some(_) => report.stale.push(::std::string::ToString::to_string(*name)),
+                }
+            }
+            ::std::result::Result::Ok(report)
+        }
+    }
+}
+
+/// Generates `sync_functions(db)`, only defining the functions whose hash changed since they were last synced.
+pub(crate) fn sync_function(args: &IncludeFnArgs) -> TokenStream2 {
+    let krate = crate::krate();
+    let (name, _) = args.transform_fn_name("sync_functions");
+    let name = name.unwrap();
+    let id = args.function_id_name();
+    let generics = args.engine.generics();
+    let connection = args.engine.connection();
+
+    quote! {
+        #[doc = "The functions `sync_functions` defined and skipped, named like `fn::greet`."]
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::default::Default, ::std::cmp::PartialEq, ::std::cmp::Eq)]
+        pub struct SyncSummary {
+            #[doc = "Functions defined because they were new, or their hash changed."]
+            pub defined: ::std::vec::Vec<&'static str>,
+            #[doc = "Functions skipped because the same hash was stored already."]
+            pub unchanged: ::std::vec::Vec<&'static str>,
+        }
+
+        impl SyncSummary {
+            #[doc = "Whether nothing had to be defined."]
+            pub fn is_unchanged(&self) -> bool {
+                self.defined.is_empty()
+            }
+        }
+
+        #[doc = "Defines the included functions whose hash differs from the one stored in the `_surql_functions_meta` table,"]
+        #[doc = "storing their new hash, in a single transaction."]
+        #[doc = ""]
+        #[doc = "Unchanged functions aren't sent again, which adds up with many functions on a remote instance. Only the hashes are"]
+        #[doc = "compared, a function removed or edited by something else is only defined again once its included statement changes."]
+        pub async fn #name #generics(db: &#krate::Surreal<#connection>) -> #krate::Result<SyncSummary> {
+            let mut response = db.query("SELECT VALUE [name, hash] FROM _surql_functions_meta").await?.check()?;
+            let stored: ::std::vec::Vec<(::std::string::String, ::std::string::String)> = response.take(0)?;
+            let stored: ::std::collections::BTreeMap<_, _> = ::std::iter::IntoIterator::into_iter(stored).collect();
+
+            let mut summary = <SyncSummary as ::std::default::Default>::default();
+            let mut query = ::std::string::ToString::to_string("BEGIN TRANSACTION;\n");
+            for function in #id::ALL {
+                let (name, hash) = (function.name(), function.hash());
+                if stored.get(name).is_some_and(|stored| stored == hash) {
+                    summary.unchanged.push(name);
+                    continue;
+                }
+                query.push_str(function.definition());
+                query.push_str(&::std::format!(
+                    "\nDELETE _surql_functions_meta WHERE name = '{name}';\nCREATE _surql_functions_meta SET name = '{name}', hash = '{hash}';\n"
+                ));
+                summary.defined.push(name);
+            }
+            if !summary.is_unchanged() {
+                query.push_str("COMMIT TRANSACTION;");
+                db.query(query).await?.check()?;
+            }
+            ::std::result::Result::Ok(summary)
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use proc_macro2::{Ident, TokenStream as TokenStream2};
+use quote::quote;
+
+use crate::{
+    parser::{kind::Kind, DefineFunctionStatement},
+    IncludeFnArgs, Wrappers,
+};
+
+/// Whether a function gets a `_paged` wrapper.
+pub(crate) trait Paged {
+    /// Whether the function declares it returns an `array` or a `set`, and doesn't take a `$limit` or `$start` of its own,
+    /// nor a parameter named `limit` or `start` in Rust, which would collide with the arguments of the wrapper.
+    fn returns_array(&self) -> bool;
+}
+
+impl Paged for DefineFunctionStatement {
+    fn returns_array(&self) -> bool {
+        matches!(self.returns, Some(Kind::Array(..) | Kind::Set(..)))
+            && !self.args.iter().any(|(name, _)| {
+                matches!(name.as_str(), "limit" | "start") || matches!(self.param_name(name).as_str(), "limit" | "start")
+            })
+    }
+}
+
+/// Generates the `Page` type returned by the `_paged` functions.
+pub(crate) fn page_type() -> TokenStream2 {
+    quote! {
+        #[doc = "A page of the items returned by a function, from a `_paged` function."]
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::cmp::PartialEq)]
+        pub struct Page<T> {
+            pub items: ::std::vec::Vec<T>,
+            #[doc = "Index of the first item, the `start` the page was asked for."]
+            pub start: u64,
+            #[doc = "Whether the function returned more items after this page."]
+            pub has_more: bool,
+        }
+
+        impl<T> Page<T> {
+            #[doc = "The `start` of the next page, if there is one."]
+            pub fn next_start(&self) -> ::std::option::Option<u64> {
+                self.has_more.then(|| self.start + self.items.len() as u64)
+            }
+        }
+    }
+}
+
+/// Generates `name`, returning at most `limit` of the items returned by `function` from `start` on.
+///
+/// One more item is asked for, to tell whether there is a next page.
+pub(crate) fn paged_function(function: &DefineFunctionStatement, name: &Ident, args: &IncludeFnArgs) -> TokenStream2 {
+    let krate = crate::krate();
+    let Some(Kind::Array(kind, _) | Kind::Set(kind, _)) = &function.returns else {
+        unreachable!()
+    };
+    let item = function.item_output(kind, args).unwrap_or_else(|| args.version.value_tokens());
+    let call = function.custom_function_query();
+    let query = format!("RETURN array::slice({}, $__start, $__limit)", call.trim_start_matches("RETURN "));
+    let comments = function.attributes(args);
+    let params = function.params_to_args(args);
+    let generics = function.generics(args.engine.param(), args);
+    let connection = args.engine.connection();
+    let bind = function.params_to_bindings(args);
+    let root = function.root_path(args);
+    let timed_out = quote! { #krate::Error::Db(#krate::error::Db::QueryTimedout) };
+    let send = args.awaited(
+        quote! {
+            db.query(#query)
+                #bind
+                .bind(("__start", start))
+                .bind(("__limit", limit.saturating_add(1)))
+        },
+        timed_out,
+    );
+    quote! {
+        #comments
+        #[doc = ""]
+        #[doc = "Takes at most `limit` of the items returned by the function, skipping the first `start`."]
+        pub async fn #name #generics(db: &#krate::Surreal<#connection>, #params limit: u64, start: u64) -> #krate::Result<#root Page<#item>> {
+            let mut items: ::std::vec::Vec<#item> = #send?.check()?.take(0)?;
+            let has_more = items.len() as u64 > limit;
+            items.truncate(limit as usize);
+            ::std::result::Result::Ok(#root Page { items, start, has_more })
+        }
+    }
+}
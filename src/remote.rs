@@ -0,0 +1,47 @@
+use std::error::Error;
+
+use sha2::{Digest, Sha256};
+
+/// Downloads a file, making sure it's exactly the pinned version.
+pub(crate) fn fetch(url: &str, sha256: &str) -> Result<String, Box<dyn Error>> {
+    let contents = ureq::get(url)
+        .call()
+        .map_err(|e| format!("{url}: {e}"))?
+        .into_string()
+        .map_err(|e| format!("{url}: {e}"))?;
+    verify(url, &contents, sha256)?;
+    Ok(contents)
+}
+
+fn verify(url: &str, contents: &str, sha256: &str) -> Result<(), String> {
+    let actual: String = Sha256::digest(contents.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    if actual.eq_ignore_ascii_case(sha256) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{url}: contents don't match the pinned checksum, expected sha256 \"{sha256}\" but got \"{actual}\""
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELLO: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    #[test]
+    fn verify_matching() {
+        assert!(verify("https://example.com", "hello", HELLO).is_ok());
+        assert!(verify("https://example.com", "hello", &HELLO.to_uppercase()).is_ok());
+    }
+
+    #[test]
+    fn verify_mismatch() {
+        let err = verify("https://example.com", "hello!", HELLO).unwrap_err();
+        assert!(err.starts_with("https://example.com: contents don't match"), "{err}");
+    }
+}
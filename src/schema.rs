@@ -0,0 +1,143 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+
+use crate::{
+    parser::{
+        kind::{Kind, Literal},
+        DefineFunctionStatement,
+    },
+    Wrappers,
+};
+
+/// Generates `functions_schema()`, describing every stored function as an operation taking its parameters as a JSON object.
+pub(crate) fn schema_function(functions: &[DefineFunctionStatement]) -> TokenStream2 {
+    let functions = functions.iter().filter(|function| function.alias_of.is_none()).map(|function| {
+        let json = function_schema(function);
+        let cfg = function.cfg();
+        quote! { #cfg #json }
+    });
+    quote! {
+        #[doc = "A JSON document describing every included function, with a JSON Schema of its parameters and of what it returns."]
+        #[doc = ""]
+        #[doc = "Meant for generating the documentation of an HTTP API calling the functions, like `{\"functions\": [{\"name\": \"fn::greet\", ...}]}`."]
+        pub fn functions_schema() -> ::std::string::String {
+            let functions: &[&str] = &[#(#functions),*];
+            ::std::format!("{{\"functions\":[{}]}}", functions.join(","))
+        }
+    }
+}
+
+/// The JSON object describing a single function.
+fn function_schema(function: &DefineFunctionStatement) -> String {
+    let sensitive = function.sensitive();
+    let names: Vec<_> = function.args.iter().map(|(param, _)| param.to_raw()).collect();
+    let params = function.args.iter().zip(&names).map(|((param, kind), name)| {
+        let mut members = vec![format!("\"x-surrealdb-kind\":{}", string(&kind.to_string()))];
+        if let Some((_, comment)) = function.arg_comments.iter().find(|(arg, _)| arg == param) {
+            members.push(format!("\"description\":{}", string(comment)));
+        }
+        if sensitive.contains(&param.0) {
+            members.push(r#""writeOnly":true"#.to_string());
+        }
+        members.extend(kind_members(kind));
+        (name.as_str(), format!("{{{}}}", members.join(",")), kind)
+    });
+    let params = format!("{{{}}}", object_members(params).join(","));
+    let returns = function.returns.as_ref().map_or_else(|| "{}".to_string(), kind_schema);
+    let description: Vec<_> = function.comments.iter().map(|line| line.trim()).collect();
+    format!(
+        "{{\"name\":{},\"signature\":{},\"description\":{},\"deprecated\":{},\"params\":{params},\"returns\":{returns}}}",
+        string(&format!("fn::{}", function.name.join("::"))),
+        string(&function.signature()),
+        string(description.join("\n").trim()),
+        function.deprecation().is_some(),
+    )
+}
+
+/// The JSON Schema of the JSON a value of `kind` is sent and returned as.
+fn kind_schema(kind: &Kind) -> String {
+    format!("{{{}}}", kind_members(kind).join(","))
+}
+
+/// The members of the JSON Schema object of `kind`, like `"type":"string"`.
+fn kind_members(kind: &Kind) -> Vec<String> {
+    let members: &[&str] = match kind {
+        Kind::Any | Kind::Unknown(_) | Kind::Function | Kind::Range | Kind::References(..) => &[],
+        Kind::Bool => &[r#""type":"boolean""#],
+        Kind::Int => &[r#""type":"integer""#],
+        Kind::Float | Kind::Number | Kind::Decimal => &[r#""type":"number""#],
+        Kind::String | Kind::Regex | Kind::Duration => &[r#""type":"string""#],
+        Kind::Datetime => &[r#""type":"string""#, r#""format":"date-time""#],
+        Kind::Uuid => &[r#""type":"string""#, r#""format":"uuid""#],
+        Kind::Bytes => &[r#""type":"string""#, r#""contentEncoding":"base64""#],
+        Kind::Object | Kind::Point | Kind::Geometry(_) => &[r#""type":"object""#],
+        Kind::Record(tables) => {
+            let mut out = vec![r#""type":"string""#.to_string()];
+            if !tables.is_empty() {
+                let tables: Vec<_> = tables.iter().map(|table| table.0.as_str()).collect();
+                out.push(format!("\"pattern\":{}", string(&format!("^({}):", tables.join("|")))));
+            }
+            return out;
+        }
+        Kind::Option(kind) => return vec![format!("\"anyOf\":[{},{{\"type\":\"null\"}}]", kind_schema(kind))],
+        Kind::Either(kinds) => return vec![format!("\"anyOf\":[{}]", schemas(kinds))],
+        Kind::Array(item, max) | Kind::Set(item, max) => {
+            let mut out = vec![r#""type":"array""#.to_string(), format!("\"items\":{}", kind_schema(item))];
+            if let Some(max) = max {
+                out.push(format!("\"maxItems\":{max}"));
+            }
+            if let Kind::Set(..) = kind {
+                out.push(r#""uniqueItems":true"#.to_string());
+            }
+            return out;
+        }
+        Kind::Literal(Literal::String(value)) => return vec![format!("\"const\":{}", string(value))],
+        Kind::Literal(Literal::Number(value)) => return vec![format!("\"const\":{value}")],
+        Kind::Literal(Literal::Bool(value)) => return vec![format!("\"const\":{value}")],
+        Kind::Literal(Literal::Array(kinds)) => {
+            return vec![r#""type":"array""#.to_string(), format!("\"prefixItems\":[{}]", schemas(kinds)), r#""items":false"#.to_string()]
+        }
+        Kind::Literal(Literal::Object(fields)) => return object_members(fields.iter().map(|(key, kind)| (key.as_str(), kind_schema(kind), kind))),
+    };
+    members.iter().map(|member| member.to_string()).collect()
+}
+
+/// The members of the JSON Schema of an object with a property for each of `fields`, required unless of an `option` kind.
+fn object_members<'a>(fields: impl Iterator<Item = (&'a str, String, &'a Kind)>) -> Vec<String> {
+    let mut properties = vec![];
+    let mut required = vec![];
+    for (key, schema, kind) in fields {
+        properties.push(format!("{}:{schema}", string(key)));
+        if !matches!(kind, Kind::Option(_)) {
+            required.push(string(key));
+        }
+    }
+    vec![
+        r#""type":"object""#.to_string(),
+        format!("\"properties\":{{{}}}", properties.join(",")),
+        format!("\"required\":[{}]", required.join(",")),
+        r#""additionalProperties":false"#.to_string(),
+    ]
+}
+
+fn schemas(kinds: &[Kind]) -> String {
+    kinds.iter().map(kind_schema).collect::<Vec<_>>().join(",")
+}
+
+/// `value` as a JSON string.
+fn string(value: &str) -> String {
+    let mut out = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,117 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+
+use crate::{parser::DefineFunctionStatement, IncludeFnArgs, Wrappers};
+
+/// Generates `FnService`, a `tower::Service<FnRequest>` calling the included functions through `dispatch`.
+pub(crate) fn service_type(
+    functions: &[DefineFunctionStatement],
+    args: &IncludeFnArgs,
+) -> TokenStream2 {
+    let krate = crate::krate();
+    let (name, _) = args.transform_fn_name("service");
+    let name = name.unwrap();
+    let (dispatch, _) = args.transform_fn_name("dispatch");
+    let dispatch = dispatch.unwrap();
+    let generics = args.engine.generics();
+    let connection = args.engine.connection();
+    // Futures of the WASM SDK aren't `Send`
+    let send = (!args.wasm).then(|| quote! { + ::std::marker::Send });
+
+    // Positions of the `@sensitive` arguments of each function
+    let sensitive = functions.iter().filter_map(|function| {
+        let sensitive = function.sensitive();
+        let positions = function
+            .args
+            .iter()
+            .enumerate()
+            .filter(|(_, (param, _))| sensitive.contains(&param.0))
+            .map(|(position, _)| position);
+        let key = function.name.join("::");
+        let cfg = function.cfg();
+        (!sensitive.is_empty()).then(|| quote! { #cfg #key => &[#(#positions),*], })
+    });
+
+    quote! {
+        #[doc = "A call to one of the included functions by name, handled by `FnService`."]
+        #[doc = ""]
+        #[doc = "Arguments of `@sensitive` parameters are redacted from its `Debug` output."]
+        #[derive(::std::clone::Clone)]
+        pub struct FnRequest {
+            pub name: ::std::string::String,
+            pub args: ::std::vec::Vec<#krate::sql::Value>,
+        }
+
+        impl ::std::fmt::Debug for FnRequest {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                struct Args<'a>(&'a [#krate::sql::Value], &'static [usize]);
+                impl<'a> ::std::fmt::Debug for Args<'a> {
+                    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                        let mut list = f.debug_list();
+                        for (position, value) in ::std::iter::Iterator::enumerate(self.0.iter()) {
+                            if self.1.contains(&position) {
+                                list.entry(&::std::format_args!("<redacted>"));
+                            } else {
+                                list.entry(value);
+                            }
+                        }
+                        list.finish()
+                    }
+                }
+                let sensitive: &'static [usize] = match self.name.strip_prefix("fn::").unwrap_or(&self.name) {
+                    #(#sensitive)*
+                    _ => &[],
+                };
+                f.debug_struct("FnRequest")
+                    .field("name", &self.name)
+                    .field("args", &Args(&self.args, sensitive))
+                    .finish()
+            }
+        }
+
+        impl FnRequest {
+            pub fn new(name: impl ::std::convert::Into<::std::string::String>, args: ::std::vec::Vec<#krate::sql::Value>) -> Self {
+                Self {
+                    name: ::std::convert::Into::into(name),
+                    args,
+                }
+            }
+        }
+
+        #[doc = "A `tower::Service` calling the included functions, so they can be wrapped in tower middleware (timeouts, rate limiting, ...)."]
+        #[doc = ""]
+        #[doc = "It is always ready, readiness is left to the middleware."]
+        #[derive(::std::fmt::Debug)]
+        pub struct FnService<C: #krate::Connection> {
+            db: #krate::Surreal<C>,
+        }
+
+        impl<C: #krate::Connection> ::std::clone::Clone for FnService<C> {
+            fn clone(&self) -> Self {
+                Self {
+                    db: ::std::clone::Clone::clone(&self.db),
+                }
+            }
+        }
+
+        impl #generics ::tower::Service<FnRequest> for FnService<#connection> {
+            type Response = #krate::Response;
+            type Error = #krate::Error;
+            type Future = ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = #krate::Result<#krate::Response>> #send>>;
+
+            fn poll_ready(&mut self, _: &mut ::std::task::Context<'_>) -> ::std::task::Poll<#krate::Result<()>> {
+                ::std::task::Poll::Ready(::std::result::Result::Ok(()))
+            }
+
+            fn call(&mut self, request: FnRequest) -> Self::Future {
+                let db = ::std::clone::Clone::clone(&self.db);
+                ::std::boxed::Box::pin(async move { #dispatch(&db, &request.name, request.args).await })
+            }
+        }
+
+        #[doc = "Wraps the connection in a `FnService`."]
+        pub fn #name #generics(db: #krate::Surreal<#connection>) -> FnService<#connection> {
+            FnService { db }
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use std::{
+    cell::RefCell,
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    error::Error,
+    fmt::{self, Display, Formatter},
+    hash::{Hash, Hasher},
+    ops::Range,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use surrealdb_functions_core::path::{find_workspace_root, fnv};
+
+use crate::{
+    parser::{self, DefineFunctionStatement, ParseError},
+    Duplicates, IncludeFnArgs, Wrappers,
+};
+
+/// The statements parsed from some contents, and the errors of the ones that failed to parse.
+type Parsed = (Vec<(Range<usize>, DefineFunctionStatement)>, Vec<ParseError>);
+
+/// Cached entries past which the caches start over, so a long-lived IDE process or the cache directory doesn't keep
+/// every version of the files.
+const CACHE_LIMIT: usize = 512;
+
+thread_local! {
+    /// Contents of the files read by earlier expansions, with the modification time and length they had.
+    static READ: RefCell<HashMap<PathBuf, (SystemTime, u64, String)>> = RefCell::default();
+    /// Statements parsed by earlier expansions, by the hash of the contents and whether parsing was lenient.
+    static PARSED: RefCell<HashMap<(u64, bool), (String, Parsed)>> = RefCell::default();
+}
+
+/// Reads the file at `path`, or returns the contents read by an earlier expansion if its modification time and length didn't change.
+fn read_file(path: &PathBuf) -> Result<String, Box<dyn Error>> {
+    let stamp = std::fs::metadata(path).ok().and_then(|metadata| Some((metadata.modified().ok()?, metadata.len())));
+    let cached = READ.with(|read| {
+        let read = read.borrow();
+        let (modified, len, contents) = read.get(path)?;
+        (stamp? == (*modified, *len)).then(|| contents.clone())
+    });
+    if let Some(contents) = cached {
+        return Ok(contents);
+    }
+    let contents = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    if let Some((modified, len)) = stamp {
+        READ.with(|read| {
+            let mut read = read.borrow_mut();
+            if read.len() >= CACHE_LIMIT {
+                read.clear();
+            }
+            read.insert(path.clone(), (modified, len, contents.clone()));
+        });
+    }
+    Ok(contents)
+}
+
+/// Directory keeping the parsed statements across builds, the `OUT_DIR` of the invoking crate when it has a build script,
+/// otherwise the target directory of its workspace. Named after the version and the sources of the parser, which may
+/// parse or serialize differently.
+fn cache_dir() -> Option<PathBuf> {
+    let var = |name: &str| std::env::var_os(name).map(PathBuf::from);
+    let base = var("OUT_DIR").or_else(|| var("CARGO_TARGET_DIR")).or_else(|| Some(find_workspace_root(&var("CARGO_MANIFEST_DIR")?).join("target")))?;
+    let name = format!("parsed-{}-{:016x}", env!("CARGO_PKG_VERSION"), parser::SOURCE_HASH);
+    Some(base.join("surrealdb_functions").join(name))
+}
+
+/// Suffixes of the files of the statements parsed strictly or leniently.
+const CACHE_SUFFIXES: [&str; 2] = ["-strict.json", "-lenient.json"];
+
+/// File of the statements parsed from `contents` in `dir`, by the FNV-1a hash and length of the contents.
+fn cache_file(dir: &Path, contents: &str, lenient: bool) -> PathBuf {
+    let suffix = CACHE_SUFFIXES[usize::from(lenient)];
+    dir.join(format!("{:016x}-{}{suffix}", fnv(contents), contents.len()))
+}
+
+/// The files `cache_file` named in `dir`, leaving out anything else.
+pub(crate) fn cache_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = dir.read_dir() else {
+        return vec![];
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+            CACHE_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+        })
+        .collect()
+}
+
+/// What an earlier build parsed from `contents`, if it's still on disk.
+pub(crate) fn read_cached(contents: &str, lenient: bool) -> Option<Parsed> {
+    let json = std::fs::read_to_string(cache_file(&cache_dir()?, contents, lenient)).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// Keeps `statements` on disk for later builds. Failing to is ignored, the contents are only parsed again.
+pub(crate) fn write_cached(contents: &str, lenient: bool, statements: &Parsed) {
+    let Some(dir) = cache_dir() else {
+        return;
+    };
+    // Started over past the limit, the files of every version of the contents would pile up otherwise.
+    // Only the files named by `cache_file` are removed, whatever else ended up in the directory is left alone
+    let cached = cache_files(&dir);
+    if cached.len() >= CACHE_LIMIT {
+        for path in cached {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+    let (Ok(()), Ok(json)) = (std::fs::create_dir_all(&dir), serde_json::to_string(statements)) else {
+        return;
+    };
+    // Renamed into place, so concurrent rustc processes never read a partly written file
+    let path = cache_file(&dir, contents, lenient);
+    let partial = path.with_extension(format!("{}.tmp", std::process::id()));
+    if std::fs::write(&partial, json).is_err() || std::fs::rename(&partial, &path).is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
+}
+
+/// Parses `contents`, or returns what an earlier expansion or build parsed from the same contents.
+///
+/// The macro is often invoked several times on the same files, and IDEs expand it again on every change,
+/// so only new contents go through the parser. What's parsed is also kept in [`cache_dir`], so unchanged files
+/// aren't parsed again by later builds either. A strict parse stops at its only error.
+fn parse_cached(contents: &str, lenient: bool) -> Parsed {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    let key = (hasher.finish(), lenient);
+    let cached = PARSED.with(|parsed| {
+        let parsed = parsed.borrow();
+        let (cached, statements) = parsed.get(&key)?;
+        (cached == contents).then(|| statements.clone())
+    });
+    if let Some(statements) = cached {
+        return statements;
+    }
+    let statements = read_cached(contents, lenient).unwrap_or_else(|| {
+        let statements = if lenient {
+            parser::statements_lenient(contents)
+        } else {
+            match parser::statements(contents) {
+                Ok(functions) => (functions, vec![]),
+                Err(e) => (vec![], vec![e]),
+            }
+        };
+        write_cached(contents, lenient, &statements);
+        statements
+    });
+    PARSED.with(|parsed| {
+        let mut parsed = parsed.borrow_mut();
+        if parsed.len() >= CACHE_LIMIT {
+            parsed.clear();
+        }
+        parsed.insert(key, (contents.to_string(), statements.clone()));
+    });
+    statements
+}
+
+/// Where the contents of an included file come from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Origin {
+    File(PathBuf),
+    /// Fetched during expansion, pinned by the SHA-256 of its contents.
+    Url { url: String, sha256: String },
+    /// Written in the macro input with `inline "..."`, numbered from 1 in the order they're included.
+    Inline { index: usize, sql: String },
+    /// Read from `INFO FOR DB` of the database at the endpoint, with the `database` option.
+    Database { endpoint: String },
+}
+
+impl Display for Origin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::File(path) => write!(f, "{}", path.display()),
+            Origin::Url { url, .. } => f.write_str(url),
+            Origin::Inline { index, .. } => write!(f, "inline #{index}"),
+            Origin::Database { endpoint } => write!(f, "database at {endpoint}"),
+        }
+    }
+}
+
+impl Origin {
+    fn read(&self) -> Result<String, Box<dyn Error>> {
+        match self {
+            Origin::File(path) => read_file(path),
+            #[cfg(feature = "remote")]
+            Origin::Url { url, sha256 } => crate::remote::fetch(url, sha256),
+            #[cfg(not(feature = "remote"))]
+            Origin::Url { url, .. } => Err(format!("{url}: the `remote` feature is disabled").into()),
+            Origin::Inline { sql, .. } => Ok(sql.clone()),
+            #[cfg(feature = "database")]
+            Origin::Database { endpoint } => crate::database::fetch(endpoint),
+            #[cfg(not(feature = "database"))]
+            Origin::Database { endpoint } => Err(format!("{endpoint}: the `database` feature is disabled").into()),
+        }
+    }
+}
+
+/// An included .surql file along with the functions parsed from it.
+pub(crate) struct SourceFile {
+    pub(crate) origin: Origin,
+    pub(crate) contents: String,
+    /// Functions that get wrappers, with the byte range of their statement.
+    pub(crate) functions: Vec<(Range<usize>, DefineFunctionStatement)>,
+    /// Definitions left without a wrapper by `duplicates as last`, still stored before the one that wins.
+    pub(crate) unwrapped: Vec<(Range<usize>, DefineFunctionStatement)>,
+}
+
+impl SourceFile {
+    /// Name of the module generated for the file with `modules as per_file`, like `user` for `user.surql`.
+    pub(crate) fn module_name(&self) -> String {
+        let path = match &self.origin {
+            Origin::File(path) => path.clone(),
+            Origin::Url { url, .. } => PathBuf::from(url.split(['?', '#']).next().unwrap_or_default()),
+            Origin::Inline { index, .. } => PathBuf::from(format!("inline_{index}")),
+            Origin::Database { .. } => PathBuf::from("database"),
+        };
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let mut name: String = stem.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' }).collect();
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            name.insert(0, '_');
+        }
+        // Keywords like `type.surql`
+        if syn::parse_str::<syn::Ident>(&name).is_err() {
+            name.push('_');
+        }
+        name
+    }
+
+    /// Parses the file, skipping the statements that fail to parse if `lenient`, along with a note for each of them.
+    fn parse(origin: &Origin, contents: String, lenient: bool) -> Result<(Self, Vec<String>), Box<dyn Error>> {
+        let (functions, errors) = parse_cached(&contents, lenient);
+        if let (false, Some(e)) = (lenient, errors.first()) {
+            return Err(format!("{origin}:{e}").into());
+        }
+        let skipped = errors
+            .iter()
+            .map(|e| format!("{origin}:{}:{} `{}`", e.line, e.column, e.snippet.trim()))
+            .collect();
+        let source = Self {
+            origin: origin.clone(),
+            contents,
+            functions,
+            unwrapped: vec![],
+        };
+        Ok((source, skipped))
+    }
+
+    /// The statements stored in the database in the order they're written, without the comments around them,
+    /// each along with its function and whether it gets wrappers.
+    pub(crate) fn stored_statements(&self) -> Vec<(&DefineFunctionStatement, String, bool)> {
+        let wrapped = self.functions.iter().map(|(range, function)| (range, function, true));
+        let unwrapped = self.unwrapped.iter().map(|(range, function)| (range, function, false));
+        let mut statements: Vec<_> = wrapped.chain(unwrapped).collect();
+        statements.sort_by_key(|(range, _, _)| range.start);
+        statements
+            .into_iter()
+            .map(|(range, function, wrapped)| (function, format!("{};", &self.contents[range.clone()]), wrapped))
+            .collect()
+    }
+}
+
+/// Prefixes `version` to the name of every function of `sources`, wherever they're defined or called, parsing the files again.
+fn versioned(sources: Vec<SourceFile>, version: &str, lenient: bool) -> Result<Vec<SourceFile>, Box<dyn Error>> {
+    let names: HashSet<String> = sources
+        .iter()
+        .flat_map(|source| source.functions.iter().map(|(_, function)| function.name.join("::")))
+        .collect();
+    let mut out = vec![];
+    for source in sources {
+        let mut contents = source.contents.clone();
+        // From the back so the offsets stay valid
+        for start in parser::scan::function_names(&source.contents).into_iter().rev() {
+            let name_start = start + "fn::".len();
+            let rest = &source.contents[name_start..];
+            let end = rest.find(|c: char| !parser::common::val_char(c) && c != ':').unwrap_or(rest.len());
+            if names.contains(rest[..end].trim_end_matches(':')) {
+                contents.insert_str(name_start, &format!("{version}::"));
+            }
+        }
+        let (mut source, _) = SourceFile::parse(&source.origin, contents, lenient)?;
+        for (_, function) in &mut source.functions {
+            function.version = Some(version.to_string());
+        }
+        out.push(source);
+    }
+    Ok(out)
+}
+
+/// Loads and parses every included file, applying the duplicates policy.
+///
+/// Returns the files and a message for every overridden function, plus one listing the statements skipped by `continue_on_error`.
+pub(crate) fn load_sources(
+    args: &IncludeFnArgs,
+) -> Result<(Vec<SourceFile>, Vec<String>), Box<dyn Error>> {
+    let mut contents = vec![];
+    for origin in args.includes.iter() {
+        contents.push((origin, origin.read()?));
+    }
+    let mut sources = vec![];
+    let mut skipped = vec![];
+    for (origin, contents) in contents {
+        let source = SourceFile::parse(origin, contents.clone(), args.continue_on_error);
+        let parsed: Option<Vec<_>> = source
+            .as_ref()
+            .ok()
+            .map(|(source, _)| source.functions.iter().map(|(_, v)| v.clone()).collect());
+        crate::validate_source(args, origin, &contents, parsed.as_deref());
+        let (source, errors) = source?;
+        sources.push(source);
+        skipped.extend(errors);
+    }
+    if args.validate {
+        proc_macro_error::abort_if_dirty();
+    }
+    if let Some(version) = &args.name_version {
+        sources = versioned(sources, version, args.continue_on_error)?;
+    }
+
+    // (file, function) indices of the definition currently used for each name
+    let mut kept: Vec<(usize, usize)> = vec![];
+    let mut dropped: Vec<(usize, usize)> = vec![];
+    let mut notes = vec![];
+    if !skipped.is_empty() {
+        notes.push(format!("skipped the statements that failed to parse: {}", skipped.join(", ")));
+    }
+    for (file, source) in sources.iter().enumerate() {
+        for (index, (_, function)) in source.functions.iter().enumerate() {
+            let existing = kept
+                .iter()
+                .position(|&(f, i)| sources[f].functions[i].1.name == function.name);
+            let Some(position) = existing else {
+                kept.push((file, index));
+                continue;
+            };
+            let first = &sources[kept[position].0].origin;
+            let name = function.name.join("::");
+            match args.duplicates {
+                Duplicates::Error => {
+                    return Err(if *first == source.origin {
+                        format!("`fn::{name}` is defined twice in {first}")
+                    } else {
+                        format!("`fn::{name}` is defined in both {first} and {}", source.origin)
+                    }
+                    .into());
+                }
+                Duplicates::First => dropped.push((file, index)),
+                Duplicates::Last => dropped.push(std::mem::replace(&mut kept[position], (file, index))),
+                Duplicates::Override => {
+                    notes.push(format!(
+                        "`fn::{name}` from {first} is overridden by {}",
+                        source.origin
+                    ));
+                    dropped.push(std::mem::replace(&mut kept[position], (file, index)));
+                }
+            }
+        }
+    }
+
+    // Remove from the back so the indices stay valid
+    dropped.sort_unstable();
+    for &(file, index) in dropped.iter().rev() {
+        let statement = sources[file].functions.remove(index);
+        // Stored earlier definitions are replaced by the kept one, later ones would replace it instead
+        if args.duplicates == Duplicates::Last {
+            sources[file].unwrapped.push(statement);
+        }
+    }
+
+    Ok((sources, notes))
+}
+
+/// Every function that gets a wrapper, including the `@alias` ones.
+pub(crate) fn functions_of(sources: &[SourceFile]) -> Vec<DefineFunctionStatement> {
+    let mut out: Vec<_> = sources
+        .iter()
+        .flat_map(|source| source.functions.iter().map(|(_, v)| v.clone()))
+        .collect();
+    let aliases: Vec<_> = out.iter().flat_map(DefineFunctionStatement::aliases).collect();
+    out.extend(aliases);
+    out
+}
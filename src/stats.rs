@@ -0,0 +1,52 @@
+use proc_macro2::{Ident, TokenStream as TokenStream2};
+use quote::quote;
+
+use crate::{parser::DefineFunctionStatement, IncludeFnArgs, Wrappers};
+
+/// Generates the `Timed` type returned by the `_with_stats` functions.
+pub(crate) fn timed_type() -> TokenStream2 {
+    quote! {
+        #[doc = "The result of a call from a `_with_stats` function, along with how long the database took to run it."]
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::cmp::PartialEq)]
+        pub struct Timed<T> {
+            pub value: T,
+            #[doc = "Execution time of the statement calling the function, as reported by the database."]
+            pub execution_time: ::std::option::Option<::std::time::Duration>,
+        }
+    }
+}
+
+/// Generates `name`, running the query of the driver function with the statistics of the response,
+/// and taking the result of the call like `_value` does.
+pub(crate) fn stats_function(function: &DefineFunctionStatement, name: &Ident, args: &IncludeFnArgs) -> TokenStream2 {
+    let krate = crate::krate();
+    let output = function.value_output(args);
+    let query = function.custom_function_query();
+    let comments = function.attributes(args);
+    let params = function.params_to_args(args);
+    let generics = function.generics(args.engine.param(), args);
+    let connection = args.engine.connection();
+    let bind = function.params_to_bindings(args);
+    let root = function.root_path(args);
+    let timed_out = quote! { #krate::Error::Db(#krate::error::Db::QueryTimedout) };
+    let send = args.awaited(
+        quote! {
+            db.query(#query)
+                #bind
+                .with_stats()
+        },
+        timed_out,
+    );
+    quote! {
+        #comments
+        #[doc = ""]
+        #[doc = "Takes the result of the call like `_value`, along with the execution time the database reported for it."]
+        pub async fn #name #generics(db: &#krate::Surreal<#connection>, #params) -> #krate::Result<#root Timed<#output>> {
+            let mut response = #send?;
+            let ::std::option::Option::Some((stats, value)) = response.take(0) else {
+                ::std::unreachable!("a single statement has a single result")
+            };
+            ::std::result::Result::Ok(#root Timed { value: value?, execution_time: stats.execution_time })
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use proc_macro2::{Ident, TokenStream as TokenStream2};
+use quote::quote;
+
+use crate::{parser::DefineFunctionStatement, IncludeFnArgs, Wrappers};
+
+/// Generates the `CallTarget` type taken by the `_in` functions.
+pub(crate) fn target_type() -> TokenStream2 {
+    quote! {
+        #[doc = "The namespace and database a single call from an `_in` function runs in, instead of the ones the client uses."]
+        #[derive(::std::fmt::Debug, ::std::clone::Clone, ::std::default::Default, ::std::cmp::PartialEq, ::std::cmp::Eq, ::std::hash::Hash)]
+        pub struct CallTarget {
+            pub namespace: ::std::option::Option<::std::string::String>,
+            pub database: ::std::option::Option<::std::string::String>,
+        }
+
+        impl CallTarget {
+            #[doc = "Targets `database` in the namespace `namespace`."]
+            pub fn new(namespace: impl ::std::convert::Into<::std::string::String>, database: impl ::std::convert::Into<::std::string::String>) -> Self {
+                Self {
+                    namespace: ::std::option::Option::Some(namespace.into()),
+                    database: ::std::option::Option::Some(database.into()),
+                }
+            }
+
+            #[doc = "Targets `database` in the namespace the client uses."]
+            pub fn database(database: impl ::std::convert::Into<::std::string::String>) -> Self {
+                Self {
+                    namespace: ::std::option::Option::None,
+                    database: ::std::option::Option::Some(database.into()),
+                }
+            }
+
+            #[doc = "The `USE` statement switching to the target, empty if it targets neither a namespace nor a database."]
+            pub fn statement(&self) -> ::std::string::String {
+                fn escape(name: &str) -> ::std::string::String {
+                    ::std::format!("`{}`", name.replace('\\', "\\\\").replace('`', "\\`"))
+                }
+                let mut statement = ::std::string::String::new();
+                if let ::std::option::Option::Some(namespace) = &self.namespace {
+                    statement.push_str(&::std::format!(" NS {}", escape(namespace)));
+                }
+                if let ::std::option::Option::Some(database) = &self.database {
+                    statement.push_str(&::std::format!(" DB {}", escape(database)));
+                }
+                match statement.is_empty() {
+                    true => statement,
+                    false => ::std::format!("USE{statement};\n"),
+                }
+            }
+        }
+    }
+}
+
+/// Generates `name`, calling `function` in the namespace and database of a `CallTarget`,
+/// with a `USE` statement ahead of the call in the same query, and taking the result like `_value` does.
+pub(crate) fn target_function(function: &DefineFunctionStatement, name: &Ident, args: &IncludeFnArgs) -> TokenStream2 {
+    let krate = crate::krate();
+    let output = function.value_output(args);
+    let query = function.custom_function_query();
+    let comments = function.attributes(args);
+    let params = function.params_to_args(args);
+    let generics = function.generics(args.engine.param(), args);
+    let connection = args.engine.connection();
+    let bind = function.params_to_bindings(args);
+    let root = function.root_path(args);
+    let timed_out = quote! { #krate::Error::Db(#krate::error::Db::QueryTimedout) };
+    let send = args.awaited(
+        quote! {
+            db.query(::std::format!("{use_statement}{}", #query))
+                #bind
+        },
+        timed_out,
+    );
+    quote! {
+        #comments
+        #[doc = ""]
+        #[doc = "Runs the call in the namespace and database of `target`, then checks the response for errors and takes the result of the call."]
+        pub async fn #name #generics(db: &#krate::Surreal<#connection>, target: &#root CallTarget, #params) -> #krate::Result<#output> {
+            let use_statement = target.statement();
+            // The result of the call follows the one of `USE`, if there is one
+            let index = usize::from(!use_statement.is_empty());
+            #send?.check()?.take(index)
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use proc_macro2::{Ident, TokenStream as TokenStream2};
+use quote::quote;
+
+use crate::{instrument, metrics, parser::DefineFunctionStatement, IncludeFnArgs, Wrappers};
+
+/// Generates a trait with a method for every function and implements it for `Surreal<C>`.
+///
+/// Methods are named like the derive ones, nested function names are joined with `_`.
+pub(crate) fn stored_functions_trait(
+    name: &Ident,
+    functions: &[DefineFunctionStatement],
+    args: &IncludeFnArgs,
+) -> TokenStream2 {
+    let krate = crate::krate();
+    let (define, _) = args.transform_fn_name("define_functions");
+    let define = define.unwrap();
+    let generics = args.engine.generics();
+    let connection = args.engine.connection();
+
+    let mut declarations = quote! {
+        #[doc = "Defines all the functions using the connection."]
+        async fn #define(&self) -> #krate::Result<#krate::Response>;
+    };
+    let mut implementations = quote! {
+        async fn #define(&self) -> #krate::Result<#krate::Response> {
+            #define(self).await
+        }
+    };
+
+    for function in functions {
+        let (method, _) = args.transform_fn_name(&function.method_base_name());
+        let method = method.unwrap();
+        let comments = function.attributes(args);
+        let signature = function.method_signature(&method, args);
+        let body = instrument::instrumented(args, function, function.method_body(quote! { self }, args));
+        let body = metrics::observed(args, function, &TokenStream2::new(), body);
+        declarations.extend(quote! {
+            #comments
+            #signature;
+        });
+        let cfg = function.cfg();
+        implementations.extend(quote! {
+            #cfg
+            #signature {
+                #body
+            }
+        });
+    }
+
+    quote! {
+        #[doc = "Every function from the included files, implemented for `Surreal<C>`."]
+        #[doc = ""]
+        #[doc = "Depend on this trait instead of a concrete connection to be able to mock the functions in tests."]
+        #[allow(async_fn_in_trait)]
+        pub trait #name {
+            #declarations
+        }
+
+        impl #generics #name for #krate::Surreal<#connection> {
+            #implementations
+        }
+    }
+}
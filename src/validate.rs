@@ -0,0 +1,117 @@
+use surrealdb::sql::{statements::DefineStatement, Statement};
+
+use crate::parser::DefineFunctionStatement;
+
+/// Runs the whole file through the official surrealdb parser.
+///
+/// Returns a message pointing at `name:line:char` when the parser rejects the file. Otherwise, when this crate's
+/// parser accepted the file as well, returns a message for every function the two parsers don't agree on.
+pub(crate) fn validate_file(
+    name: &str,
+    contents: &str,
+    parsed: Option<&[DefineFunctionStatement]>,
+) -> Vec<String> {
+    let query = match surrealdb::sql::parse(contents) {
+        Ok(query) => query,
+        Err(surrealdb::err::Error::InvalidQuery { line, char, sql }) => {
+            return vec![format!(
+                "{name}:{line}:{char}: surrealdb failed to parse `{}`",
+                sql.lines().next().unwrap_or_default().trim()
+            )]
+        }
+        Err(e) => return vec![format!("{name}: {e}")],
+    };
+    let Some(parsed) = parsed else {
+        return vec![format!("{name}: surrealdb parses this file, but this crate's parser doesn't")];
+    };
+
+    let official: Vec<_> = query
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::Define(DefineStatement::Function(function)) => Some((
+                function.name.0.clone(),
+                signature(function.args.iter().map(|(arg, kind)| (&arg.0, kind.to_string()))),
+            )),
+            _ => None,
+        })
+        .collect();
+    let ours: Vec<_> = parsed
+        .iter()
+        .map(|function| {
+            (
+                function.name.join("::"),
+                signature(function.args.iter().map(|(arg, kind)| (&arg.0, kind.to_string()))),
+            )
+        })
+        .collect();
+
+    let mut out = vec![];
+    for (function, args) in &official {
+        match ours.iter().find(|(other, _)| other == function) {
+            None => out.push(format!("{name}: surrealdb parsed `fn::{function}`, which this crate's parser missed")),
+            Some((_, other)) if other != args => out.push(format!(
+                "{name}: `fn::{function}` takes `{args}` according to surrealdb, but `{other}` according to this crate's parser"
+            )),
+            Some(_) => {}
+        }
+    }
+    for (function, _) in &ours {
+        if !official.iter().any(|(other, _)| other == function) {
+            out.push(format!("{name}: this crate's parser found `fn::{function}`, which surrealdb didn't"));
+        }
+    }
+    out
+}
+
+fn signature<'a>(args: impl Iterator<Item = (&'a String, String)>) -> String {
+    let args: Vec<_> = args.map(|(arg, kind)| format!("${arg}: {kind}")).collect();
+    format!("({})", args.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(sql: &str) -> Vec<DefineFunctionStatement> {
+        crate::parser::statements(sql).unwrap().into_iter().map(|(_, v)| v).collect()
+    }
+
+    #[test]
+    fn valid_file() {
+        let sql = "DEFINE FUNCTION fn::greet($name: string) { RETURN 'Hello, ' + $name; };";
+        assert!(validate_file("main.surql", sql, Some(&parsed(sql))).is_empty());
+    }
+
+    #[test]
+    fn invalid_body() {
+        let sql = "DEFINE FUNCTION fn::greet($name: string) {\n    RETRUN 'Hello, ' + $name;\n};";
+        let err = validate_file("main.surql", sql, Some(&parsed(sql)));
+        assert!(err[0].starts_with("main.surql:1:"), "{err:?}");
+    }
+
+    #[test]
+    fn agrees_on_kinds_and_names() {
+        let sql = "DEFINE FUNCTION fn::a::b($x: option<record<person>>, $y: array<string, 10>) { RETURN $x; };\n\
+                   DEFINE FUNCTION fn::c($z: geometry<point|polygon>) { RETURN $z; };";
+        assert_eq!(validate_file("main.surql", sql, Some(&parsed(sql))), Vec::<String>::new());
+    }
+
+    #[test]
+    fn reports_discrepancies() {
+        let sql = "DEFINE FUNCTION fn::greet($name: string) { RETURN $name; };";
+        let mut functions = parsed(sql);
+        functions[0].args[0].1 = crate::parser::kind::Kind::Int;
+        let err = validate_file("main.surql", sql, Some(&functions));
+        assert_eq!(
+            err,
+            vec!["main.surql: `fn::greet` takes `($name: string)` according to surrealdb, but `($name: int)` according to this crate's parser"]
+        );
+
+        functions[0].name = vec!["other".to_string()];
+        let err = validate_file("main.surql", sql, Some(&functions));
+        assert_eq!(err.len(), 2, "{err:?}");
+
+        let err = validate_file("main.surql", sql, None);
+        assert_eq!(err, vec!["main.surql: surrealdb parses this file, but this crate's parser doesn't"]);
+    }
+}
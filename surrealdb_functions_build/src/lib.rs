@@ -0,0 +1,127 @@
+//! Build script helper for crates using [`surrealdb_functions`](https://docs.rs/surrealdb_functions).
+//!
+//! On stable, rustc only learns about the files `include_fn!` includes, so a file added to an included directory
+//! doesn't trigger a rebuild. Calling [`rerun_if_changed`] from `build.rs` with the same paths has Cargo watch them instead,
+//! and hands `include_fn!` a hash of every directory it reads, which changes whenever a file is added:
+//!
+//! ```no_run
+//! // In `fn main()` of build.rs
+//! surrealdb_functions_build::rerun_if_changed(["$CARGO_MANIFEST_DIR/surql"]);
+//! ```
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+};
+
+// Shared with surrealdb_functions, so the variables are named the way the macro reads them
+use surrealdb_functions_core::path::{find_workspace_root, fnv, listing_var, resolve_path};
+
+/// Tells Cargo to rerun the build script, and so recompile the crate, when any of `paths` changes.
+///
+/// Paths are written like in `include_fn!`, with `$VAR` replaced by the environment variable and `$WORKSPACE_ROOT`
+/// defaulting to the root of the workspace. Directories are watched as a whole, including files added to them later,
+/// and the hash of their entries is set as an environment variable tracked by `include_fn!`.
+///
+/// # Panics
+///
+/// Panics if a path uses an environment variable that isn't set, failing the build script.
+pub fn rerun_if_changed<P: AsRef<str>>(paths: impl IntoIterator<Item = P>) {
+    for line in instructions(paths, |variable| std::env::var(variable).ok()) {
+        println!("{line}");
+    }
+}
+
+/// The `cargo:` lines for `paths`, with `get_env` looking up environment variables.
+fn instructions<P: AsRef<str>>(paths: impl IntoIterator<Item = P>, get_env: impl Fn(&str) -> Option<String>) -> Vec<String> {
+    let mut out = vec![];
+    for path in paths {
+        let path = path.as_ref();
+        let (resolved, variables) = resolve(path, &get_env);
+        for variable in variables {
+            let line = format!("cargo:rerun-if-env-changed={variable}");
+            if !out.contains(&line) {
+                out.push(line);
+            }
+        }
+        out.push(format!("cargo:rerun-if-changed={}", resolved.display()));
+        listings(&resolved, &mut out);
+    }
+    out
+}
+
+/// A `cargo:rustc-env` line for `dir` and every directory in it, holding the hash of its entries.
+fn listings(dir: &Path, out: &mut Vec<String>) {
+    let Ok(entries) = dir.read_dir() else {
+        return;
+    };
+    let mut entries: Vec<_> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect();
+    entries.sort();
+    let names: String = entries.iter().map(|entry| format!("{}\n", entry.display())).collect();
+    out.push(format!("cargo:rustc-env={}={:016x}", listing_var(dir), fnv(&names)));
+    for entry in entries.iter().filter(|entry| entry.is_dir()) {
+        listings(entry, out);
+    }
+}
+
+/// Replaces every `$VAR` in `raw`, returning the path and the variables it used.
+fn resolve(raw: &str, get_env: &impl Fn(&str) -> Option<String>) -> (PathBuf, Vec<String>) {
+    let variables = RefCell::new(vec![]);
+    let resolved = resolve_path(raw, |variable| {
+        variables.borrow_mut().push(variable.to_string());
+        get_env(variable).or_else(|| match variable {
+            "WORKSPACE_ROOT" => get_env("CARGO_MANIFEST_DIR").map(|dir| find_workspace_root(Path::new(&dir)).display().to_string()),
+            _ => None,
+        })
+    });
+    match resolved {
+        Ok(resolved) => (resolved, variables.into_inner()),
+        Err(e) => panic!("{e} in \"{raw}\""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_variables() {
+        let lines = instructions(["$DIR/surql", "$DIR/extra.surql", "plain.surql"], |name| {
+            assert_eq!(name, "DIR");
+            Some("/crate".to_string())
+        });
+        assert_eq!(
+            lines,
+            vec![
+                "cargo:rerun-if-env-changed=DIR",
+                "cargo:rerun-if-changed=/crate/surql",
+                "cargo:rerun-if-changed=/crate/extra.surql",
+                "cargo:rerun-if-changed=plain.surql",
+            ]
+        );
+    }
+
+    #[test]
+    fn directory_listing() {
+        let dir = std::env::temp_dir().join("surrealdb_functions_build_listing");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("a.surql"), "").unwrap();
+        let path = dir.display().to_string();
+
+        let before = instructions([&path], |_| None);
+        assert_eq!(before[0], format!("cargo:rerun-if-changed={path}"));
+        assert!(before[1].starts_with(&format!("cargo:rustc-env={}=", listing_var(&dir))));
+        assert!(before[2].starts_with(&format!("cargo:rustc-env={}=", listing_var(&dir.join("nested")))));
+
+        std::fs::write(dir.join("b.surql"), "").unwrap();
+        let after = instructions([&path], |_| None);
+        assert_ne!(before[1], after[1]);
+        assert_eq!(before[2], after[2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unable to resolve $MISSING in \"$MISSING/surql\"")]
+    fn missing_variable() {
+        instructions(["$MISSING/surql"], |_| None);
+    }
+}
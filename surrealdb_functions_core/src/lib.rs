@@ -0,0 +1,9 @@
+//! Parser of `DEFINE FUNCTION` statements, protobuf naming of the functions and resolution of included paths,
+//! shared by [`surrealdb_functions`](https://docs.rs/surrealdb_functions) and its companion crates
+//! [`surrealdb_functions_runtime`](https://docs.rs/surrealdb_functions_runtime) and
+//! [`surrealdb_functions_build`](https://docs.rs/surrealdb_functions_build), so they all read .surql files the same way.
+//!
+//! It's an implementation detail of those crates, use their API instead.
+pub mod parser;
+pub mod path;
+pub mod proto;
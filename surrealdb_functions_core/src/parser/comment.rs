@@ -0,0 +1,139 @@
+use nom::{
+    branch::alt,
+    bytes::complete::take_until,
+    character::complete::{char, line_ending, multispace0, multispace1, not_line_ending},
+    combinator::not,
+    multi::many1,
+    IResult,
+};
+
+pub fn mightbespace(i: &str) -> IResult<&str, ()> {
+    let (i, _) = alt((comment, blank))(i)?;
+    Ok((i, ()))
+}
+
+pub fn shouldbespace(i: &str) -> IResult<&str, ()> {
+    let (i, _) = alt((comment, space))(i)?;
+    Ok((i, ()))
+}
+
+pub fn comment(i: &str) -> IResult<&str, ()> {
+    let (i, _) = multispace0(i)?;
+    let (i, _) = many1(alt((block, slash, dash, hash)))(i)?;
+    let (i, _) = multispace0(i)?;
+    Ok((i, ()))
+}
+
+pub fn mightbecomment(i: &str) -> IResult<&str, Vec<&str>> {
+    let (i, comments) = alt((comment_wanted, blank_))(i)?;
+    Ok((i, comments))
+}
+
+pub fn comment_wanted(i: &str) -> IResult<&str, Vec<&str>> {
+    let (i, _) = multispace0(i)?;
+    let (i, comments) = many1(alt((block, slash, dash, hash)))(i)?;
+    let (i, _) = multispace0(i)?;
+    Ok((i, comments))
+}
+
+/// A single comment that starts on the current line, like one following a statement.
+pub fn trailing_comment(i: &str) -> IResult<&str, &str> {
+    let (i, _) = not(line_ending)(i)?;
+    alt((block, slash, dash, hash))(i)
+}
+
+pub fn block(i: &str) -> IResult<&str, &str> {
+    let (i, _) = multispace0(i)?;
+    let (i, _) = char('/')(i)?;
+    let (i, _) = char('*')(i)?;
+    let (i, comment) = take_until("*/")(i)?;
+    let (i, _) = char('*')(i)?;
+    let (i, _) = char('/')(i)?;
+    let (i, _) = multispace0(i)?;
+    Ok((i, comment.trim()))
+}
+
+/// The lines of a comment as written, without the indentation of block comments or the ` * ` in front of their lines.
+///
+/// Blank lines are kept, so paragraphs and markdown like code fences make it into the documentation.
+pub fn doc_lines(comment: &str) -> Vec<String> {
+    if !comment.contains('\n') {
+        return vec![comment.to_string()];
+    }
+    // `/**` leaves its second star in front
+    let comment = comment.strip_prefix('*').unwrap_or(comment);
+    let mut lines: Vec<&str> = comment.lines().collect();
+    let rest = &mut lines[1..];
+    let starred = rest.iter().filter(|line| !line.trim().is_empty()).all(|line| line.trim_start().starts_with('*'));
+    let indent = rest
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.chars().take_while(|c| c.is_whitespace()).count())
+        .min()
+        .unwrap_or(0);
+    for line in rest.iter_mut() {
+        *line = match starred {
+            true => {
+                let line = line.trim_start().strip_prefix('*').unwrap_or(line);
+                line.strip_prefix(' ').unwrap_or(line)
+            }
+            false => line.char_indices().nth(indent).map_or("", |(start, _)| &line[start..]),
+        };
+    }
+    let lines: Vec<String> = lines.iter().map(|line| line.trim_end().to_string()).collect();
+    let start = lines.iter().position(|line| !line.is_empty()).unwrap_or(lines.len());
+    let end = lines.iter().rposition(|line| !line.is_empty()).map_or(start, |end| end + 1);
+    lines[start..end].to_vec()
+}
+
+pub fn slash(i: &str) -> IResult<&str, &str> {
+    let (i, _) = multispace0(i)?;
+    let (i, _) = char('/')(i)?;
+    let (i, _) = char('/')(i)?;
+    let (i, comment) = not_line_ending(i)?;
+    Ok((i, comment.trim()))
+}
+
+pub fn dash(i: &str) -> IResult<&str, &str> {
+    let (i, _) = multispace0(i)?;
+    let (i, _) = char('-')(i)?;
+    let (i, _) = char('-')(i)?;
+    let (i, comment) = not_line_ending(i)?;
+    Ok((i, comment.trim()))
+}
+
+pub fn hash(i: &str) -> IResult<&str, &str> {
+    let (i, _) = multispace0(i)?;
+    let (i, _) = char('#')(i)?;
+    let (i, comment) = not_line_ending(i)?;
+    Ok((i, comment.trim()))
+}
+
+fn blank(i: &str) -> IResult<&str, ()> {
+    let (i, _) = multispace0(i)?;
+    Ok((i, ()))
+}
+
+fn space(i: &str) -> IResult<&str, ()> {
+    let (i, _) = multispace1(i)?;
+    Ok((i, ()))
+}
+
+fn blank_(i: &str) -> IResult<&str, Vec<&str>> {
+    let (i, _) = multispace0(i)?;
+    Ok((i, vec![]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_doc_lines() {
+        let (_, comment) = block("/* Returns a greeting.\n\n   ```surql\n   RETURN fn::greet('a');\n   ```\n */").unwrap();
+        assert_eq!(doc_lines(comment), ["Returns a greeting.", "", "```surql", "RETURN fn::greet('a');", "```"]);
+        let (_, comment) = block("/**\n * Starred\n *\n *     indented\n */").unwrap();
+        assert_eq!(doc_lines(comment), ["Starred", "", "    indented"]);
+        assert_eq!(doc_lines("* not a block"), ["* not a block"]);
+    }
+}
@@ -1,13 +1,19 @@
-use super::comment::mightbespace;
+use super::comment::{mightbespace, trailing_comment};
 use nom::{
-    character::{complete::{char, multispace0}, is_alphanumeric},
+    branch::alt,
+    character::{complete::{char, multispace0, space0}, is_alphanumeric},
+    combinator::opt,
+    error::{Error, ErrorKind},
     multi::many1,
+    sequence::terminated,
     IResult,
 };
 
 pub fn colons(i: &str) -> IResult<&str, ()> {
     let (i, _) = multispace0(i)?;
-    let (i, _) = many1(char(';'))(i)?;
+    let (i, _) = many1(terminated(char(';'), space0))(i)?;
+    // A comment on the same line belongs to the statement before it, not the next one
+    let (i, _) = opt(trailing_comment)(i)?;
     let (i, _) = multispace0(i)?;
     Ok((i, ()))
 }
@@ -50,6 +56,27 @@ pub fn closebraces(i: &str) -> IResult<&str, ()> {
     Ok((i, ()))
 }
 
+/// A string in single or double quotes, with its escapes resolved.
+pub fn strand(i: &str) -> IResult<&str, String> {
+    let (i, quote) = alt((char('\''), char('"')))(i)?;
+    let mut out = String::new();
+    let mut chars = i.char_indices();
+    while let Some((n, c)) = chars.next() {
+        match c {
+            c if c == quote => return Ok((&i[n + 1..], out)),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => out.push('\n'),
+                Some((_, 't')) => out.push('\t'),
+                Some((_, 'r')) => out.push('\r'),
+                Some((_, c)) => out.push(c),
+                None => break,
+            },
+            c => out.push(c),
+        }
+    }
+    Err(nom::Err::Error(Error::new(i, ErrorKind::Char)))
+}
+
 #[inline]
 pub fn val_u8(chr: u8) -> bool {
     is_alphanumeric(chr) || chr == b'_'
@@ -0,0 +1,86 @@
+use super::common::val_char;
+use nom::{
+    bytes::complete::take_while1,
+    character::complete::{char, space0},
+    IResult,
+};
+
+/// An annotation written as a comment line above a function, like `-- @alias old_name`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Directive {
+    pub name: String,
+    pub value: String,
+}
+
+pub fn directive(i: &str) -> IResult<&str, Directive> {
+    let (i, _) = char('@')(i)?;
+    let (i, name) = take_while1(val_char)(i)?;
+    let (i, _) = space0(i)?;
+    Ok((
+        "",
+        Directive {
+            name: name.to_string(),
+            value: i.trim().to_string(),
+        },
+    ))
+}
+
+/// Splits the comments above a function into documentation and directives.
+pub fn split_comments(comments: Vec<&str>) -> (Vec<String>, Vec<Directive>) {
+    let mut docs = vec![];
+    let mut directives = vec![];
+    for comment in comments {
+        match directive(comment) {
+            Ok((_, v)) => directives.push(v),
+            Err(_) => docs.push(comment.to_string()),
+        }
+    }
+    (docs, directives)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn directive_with_value() {
+        let res = directive("@alias old_name");
+        let out = res.unwrap().1;
+        assert_eq!(
+            out,
+            Directive {
+                name: "alias".to_string(),
+                value: "old_name".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn directive_without_value() {
+        let res = directive("@sensitive");
+        let out = res.unwrap().1;
+        assert_eq!(out.name, "sensitive");
+        assert_eq!(out.value, "");
+    }
+
+    #[test]
+    fn directive_not_a_directive() {
+        assert!(directive("Returns a greeting, @bob").is_err());
+        assert!(directive("@ nothing").is_err());
+    }
+
+    #[test]
+    fn split_docs_and_directives() {
+        let (docs, directives) = split_comments(vec!["Returns a greeting", "@alias greet_old"]);
+        assert_eq!(docs, vec!["Returns a greeting".to_string()]);
+        assert_eq!(
+            directives,
+            vec![Directive {
+                name: "alias".to_string(),
+                value: "greet_old".to_string(),
+            }]
+        );
+    }
+}
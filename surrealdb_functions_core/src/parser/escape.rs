@@ -11,6 +11,11 @@ pub fn escape_ident(s: &str) -> Cow<'_, str> {
     escape_numeric(s, BACKTICK, BACKTICK, BACKTICK_ESC)
 }
 
+/// The segments of a function name joined with `::`, escaping the ones that need it, like ``nested::`first name` ``.
+pub fn escape_path(name: &[String]) -> String {
+    name.iter().map(|segment| escape_ident(segment)).collect::<Vec<_>>().join("::")
+}
+
 #[inline]
 pub fn escape_numeric<'a>(s: &'a str, l: char, r: char, e: &str) -> Cow<'a, str> {
     // Presume this is numeric
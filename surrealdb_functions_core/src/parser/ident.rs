@@ -21,7 +21,9 @@ const BRACKET_END_NUL: &str = "⟩\0";
 const BACKTICK: char = '`';
 const BACKTICK_ESC_NUL: &str = "`\\\0";
 
+/// A parameter name without its `$`.
 #[derive(Clone, Debug, Default, Eq, Ord, PartialEq, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ident(pub String);
 
 impl From<String> for Ident {
@@ -61,8 +63,9 @@ pub fn ident(i: &str) -> IResult<&str, Ident> {
     Ok((i, Ident::from(v)))
 }
 
-pub fn multikeep(i: &str) -> IResult<&str, Vec<&str>> {
-    let (i, v) = separated_list1(tag("::"), take_while1(val_char))(i)?;
+/// The segments of a function name, each of which can be escaped like ``fn::nested::`first name` ``.
+pub fn multikeep(i: &str) -> IResult<&str, Vec<String>> {
+    let (i, v) = separated_list1(tag("::"), ident_raw)(i)?;
     Ok((i, v))
 }
 
@@ -1,21 +1,28 @@
 use super::{
     comment::mightbespace,
-    common::{closeparentheses, commas, openparentheses, verbar},
+    common::{closebraces, closeparentheses, commas, openbraces, openparentheses, val_char, verbar},
+    escape::escape_ident,
     fmt::Fmt,
+    ident::ident_raw,
     table::{table, Table},
 };
 use nom::{
     branch::alt,
-    bytes::complete::tag,
-    character::complete::{char, u64},
-    combinator::{map, opt},
-    multi::separated_list1,
+    bytes::complete::{tag, take_while1},
+    character::complete::{char, digit1, satisfy, u64},
+    combinator::{map, not, opt, peek, recognize, value},
+    error::{Error, ErrorKind},
+    multi::{separated_list0, separated_list1},
+    sequence::{pair, terminated, tuple},
     IResult,
 };
 use std::fmt::{self, Display, Formatter};
 
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// The type of a parameter or return value, like `option<record<user>>`, displayed the way SurrealDB writes it.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Kind {
+    #[default]
     Any,
     Bool,
     Bytes,
@@ -23,28 +30,42 @@ pub enum Kind {
     Decimal,
     Duration,
     Float,
+    Function,
     Int,
     Number,
     Object,
     Point,
+    Range,
+    Regex,
     String,
     Uuid,
     Record(Vec<Table>),
+    /// Records pointing at the one being read, from `table` and through `field` if given.
+    References(Option<Table>, Option<String>),
     Geometry(Vec<String>),
     Option(Box<Kind>),
     Either(Vec<Kind>),
     Set(Box<Kind>, Option<u64>),
     Array(Box<Kind>, Option<u64>),
+    Literal(Literal),
+    /// A kind this version doesn't know yet, kept as written, like `file<avatars>`.
+    Unknown(String),
 }
 
-impl Default for Kind {
-    fn default() -> Self {
-        Self::Any
-    }
+/// A single allowed value, or an array or object with a kind for each of its items, like `"asc"` or `{ id: int }`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Literal {
+    String(String),
+    /// Kept as written, floats would get in the way of `Eq`
+    Number(String),
+    Bool(bool),
+    Array(Vec<Kind>),
+    Object(Vec<(String, Kind)>),
 }
 
 impl Kind {
-    fn is_any(&self) -> bool {
+    pub fn is_any(&self) -> bool {
         matches!(self, Kind::Any)
     }
 }
@@ -66,10 +87,13 @@ impl Display for Kind {
             Kind::Decimal => f.write_str("decimal"),
             Kind::Duration => f.write_str("duration"),
             Kind::Float => f.write_str("float"),
+            Kind::Function => f.write_str("function"),
             Kind::Int => f.write_str("int"),
             Kind::Number => f.write_str("number"),
             Kind::Object => f.write_str("object"),
             Kind::Point => f.write_str("point"),
+            Kind::Range => f.write_str("range"),
+            Kind::Regex => f.write_str("regex"),
             Kind::String => f.write_str("string"),
             Kind::Uuid => f.write_str("uuid"),
             Kind::Option(k) => write!(f, "option<{k}>"),
@@ -77,6 +101,11 @@ impl Display for Kind {
                 k if k.is_empty() => write!(f, "record"),
                 k => write!(f, "record<{}>", Fmt::verbar_separated(k)),
             },
+            Kind::References(t, v) => match (t, v) {
+                (None, _) => write!(f, "references"),
+                (Some(t), None) => write!(f, "references<{t}>"),
+                (Some(t), Some(v)) => write!(f, "references<{t}, {v}>"),
+            },
             Kind::Geometry(k) => match k {
                 k if k.is_empty() => write!(f, "geometry"),
                 k => write!(f, "geometry<{}>", Fmt::verbar_separated(k)),
@@ -92,18 +121,74 @@ impl Display for Kind {
                 (k, Some(l)) => write!(f, "array<{k}, {l}>"),
             },
             Kind::Either(k) => write!(f, "{}", Fmt::verbar_separated(k)),
+            Kind::Literal(l) => Display::fmt(l, f),
+            Kind::Unknown(k) => f.write_str(k),
+        }
+    }
+}
+
+impl Display for Literal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::String(s) => write!(f, "\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")),
+            Literal::Number(n) => f.write_str(n),
+            Literal::Bool(b) => write!(f, "{b}"),
+            Literal::Array(k) => write!(f, "[{}]", Fmt::comma_separated(k)),
+            Literal::Object(k) if k.is_empty() => f.write_str("{}"),
+            Literal::Object(k) => {
+                f.write_str("{ ")?;
+                for (i, (key, kind)) in k.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    // Keys that would need backticks as idents are written as strings instead
+                    match escape_ident(key) == key.as_str() {
+                        true => write!(f, "{key}: {kind}")?,
+                        false => write!(f, "{}: {kind}", Literal::String(key.clone()))?,
+                    }
+                }
+                f.write_str(" }")
+            }
         }
     }
 }
 
 pub fn kind(i: &str) -> IResult<&str, Kind> {
-    alt((any, either, option))(i)
+    // `option<T>` first, it would be taken as an unknown kind otherwise
+    alt((whole(any), option, either))(i)
 }
 
 pub fn any(i: &str) -> IResult<&str, Kind> {
     map(tag("any"), |_| Kind::Any)(i)
 }
 
+/// Runs `parser` only if what it parsed isn't the start of a longer name, so `integer` isn't taken as `int`.
+fn whole<'a>(parser: impl FnMut(&'a str) -> IResult<&'a str, Kind>) -> impl FnMut(&'a str) -> IResult<&'a str, Kind> {
+    terminated(parser, not(peek(satisfy(val_char))))
+}
+
+/// Any other name, with its arguments in angle brackets if any, for kinds added after this parser was written.
+fn unknown(i: &str) -> IResult<&str, Kind> {
+    let (rest, _) = take_while1(val_char)(i)?;
+    let mut end = i.len() - rest.len();
+    if rest.starts_with('<') {
+        let mut depth = 0;
+        let close = rest.char_indices().find_map(|(at, c)| {
+            match c {
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                _ => {}
+            }
+            (depth == 0).then_some(at)
+        });
+        match close {
+            Some(at) => end += at + 1,
+            None => return Err(nom::Err::Error(Error::new(rest, ErrorKind::Char))),
+        }
+    }
+    Ok((&i[end..], Kind::Unknown(i[..end].to_string())))
+}
+
 pub fn simple(i: &str) -> IResult<&str, Kind> {
     alt((
         map(tag("bool"), |_| Kind::Bool),
@@ -112,17 +197,23 @@ pub fn simple(i: &str) -> IResult<&str, Kind> {
         map(tag("decimal"), |_| Kind::Decimal),
         map(tag("duration"), |_| Kind::Duration),
         map(tag("float"), |_| Kind::Float),
+        map(tag("function"), |_| Kind::Function),
         map(tag("int"), |_| Kind::Int),
         map(tag("number"), |_| Kind::Number),
         map(tag("object"), |_| Kind::Object),
         map(tag("point"), |_| Kind::Point),
+        map(tag("range"), |_| Kind::Range),
+        map(tag("regex"), |_| Kind::Regex),
         map(tag("string"), |_| Kind::String),
         map(tag("uuid"), |_| Kind::Uuid),
     ))(i)
 }
 
 fn either(i: &str) -> IResult<&str, Kind> {
-    let (i, mut v) = separated_list1(verbar, alt((simple, geometry, record, array, set)))(i)?;
+    let (i, mut v) = separated_list1(
+        verbar,
+        alt((whole(simple), whole(geometry), whole(record), whole(references), whole(array), whole(set), literal, unknown)),
+    )(i)?;
     match v.len() {
         1 => Ok((i, v.remove(0))),
         _ => Ok((i, Kind::Either(v))),
@@ -133,10 +224,7 @@ fn option(i: &str) -> IResult<&str, Kind> {
     let (i, _) = tag("option")(i)?;
     let (i, _) = mightbespace(i)?;
     let (i, _) = char('<')(i)?;
-    let (i, v) = map(
-        alt((either, simple, geometry, record, array, set)),
-        Box::new,
-    )(i)?;
+    let (i, v) = map(either, Box::new)(i)?;
     let (i, _) = char('>')(i)?;
     Ok((i, Kind::Option(v)))
 }
@@ -162,6 +250,31 @@ fn record(i: &str) -> IResult<&str, Kind> {
     Ok((i, Kind::Record(v.unwrap_or_default())))
 }
 
+fn references(i: &str) -> IResult<&str, Kind> {
+    let (i, _) = tag("references")(i)?;
+    let (i, v) = opt(|i| {
+        let (i, _) = mightbespace(i)?;
+        let (i, _) = char('<')(i)?;
+        let (i, _) = mightbespace(i)?;
+        let (i, t) = table(i)?;
+        let (i, v) = opt(|i| {
+            let (i, _) = commas(i)?;
+            let (i, v) = recognize(separated_list1(char('.'), ident_raw))(i)?;
+            Ok((i, v.to_string()))
+        })(i)?;
+        let (i, _) = mightbespace(i)?;
+        let (i, _) = char('>')(i)?;
+        Ok((i, (t, v)))
+    })(i)?;
+    Ok((
+        i,
+        match v {
+            Some((t, v)) => Kind::References(Some(t), v),
+            None => Kind::References(None, None),
+        },
+    ))
+}
+
 fn geometry(i: &str) -> IResult<&str, Kind> {
     let (i, _) = tag("geometry")(i)?;
     let (i, v) = opt(alt((
@@ -235,6 +348,62 @@ fn set(i: &str) -> IResult<&str, Kind> {
     ))
 }
 
+fn literal(i: &str) -> IResult<&str, Kind> {
+    let (i, v) = alt((
+        map(quoted, Literal::String),
+        map(recognize(tuple((opt(char('-')), digit1, opt(pair(char('.'), digit1))))), |v: &str| {
+            Literal::Number(v.to_string())
+        }),
+        value(Literal::Bool(true), tag("true")),
+        value(Literal::Bool(false), tag("false")),
+        |i| {
+            let (i, _) = char('[')(i)?;
+            let (i, _) = mightbespace(i)?;
+            let (i, v) = separated_list0(commas, kind)(i)?;
+            let (i, _) = mightbespace(i)?;
+            let (i, _) = char(']')(i)?;
+            Ok((i, Literal::Array(v)))
+        },
+        |i| {
+            let (i, _) = openbraces(i)?;
+            let (i, v) = separated_list0(commas, |i| {
+                let (i, k) = alt((quoted, ident_raw))(i)?;
+                let (i, _) = mightbespace(i)?;
+                let (i, _) = char(':')(i)?;
+                let (i, _) = mightbespace(i)?;
+                let (i, v) = kind(i)?;
+                Ok((i, (k, v)))
+            })(i)?;
+            let (i, _) = closebraces(i)?;
+            Ok((i, Literal::Object(v)))
+        },
+    ))(i)?;
+    Ok((i, Kind::Literal(v)))
+}
+
+/// A string in double or single quotes, with its escapes resolved.
+fn quoted(i: &str) -> IResult<&str, String> {
+    let quote = match i.chars().next() {
+        Some(c @ ('"' | '\'')) => c,
+        _ => return Err(nom::Err::Error(Error::new(i, ErrorKind::Char))),
+    };
+    let mut out = String::new();
+    let mut chars = i[1..].char_indices();
+    while let Some((at, c)) = chars.next() {
+        match c {
+            c if c == quote => return Ok((&i[at + 2..], out)),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => out.push('\n'),
+                Some((_, 't')) => out.push('\t'),
+                Some((_, c)) => out.push(c),
+                None => break,
+            },
+            c => out.push(c),
+        }
+    }
+    Err(nom::Err::Error(Error::new(i, ErrorKind::Char)))
+}
+
 fn geo(i: &str) -> IResult<&str, String> {
     map(
         alt((
@@ -535,4 +704,127 @@ mod tests {
         assert_eq!("set<float, 10>", format!("{}", out));
         assert_eq!(out, Kind::Set(Box::new(Kind::Float), Some(10)));
     }
+
+    #[test]
+    fn kind_literal_strings() {
+        let sql = r#""asc" | 'desc'"#;
+        let res = kind(sql);
+        assert!(res.is_ok());
+        let out = res.unwrap().1;
+        assert_eq!(r#""asc" | "desc""#, format!("{}", out));
+        assert_eq!(
+            out,
+            Kind::Either(vec![
+                Kind::Literal(Literal::String(String::from("asc"))),
+                Kind::Literal(Literal::String(String::from("desc")))
+            ])
+        );
+    }
+
+    #[test]
+    fn kind_literal_escaped_string() {
+        let sql = r#""say \"hi\"""#;
+        let res = kind(sql);
+        assert!(res.is_ok());
+        let out = res.unwrap().1;
+        assert_eq!(sql, format!("{}", out));
+        assert_eq!(out, Kind::Literal(Literal::String(String::from(r#"say "hi""#))));
+    }
+
+    #[test]
+    fn kind_literal_numbers() {
+        let sql = "1 | -2 | 0.5 | true";
+        let res = kind(sql);
+        assert!(res.is_ok());
+        let out = res.unwrap().1;
+        assert_eq!("1 | -2 | 0.5 | true", format!("{}", out));
+        assert_eq!(
+            out,
+            Kind::Either(vec![
+                Kind::Literal(Literal::Number(String::from("1"))),
+                Kind::Literal(Literal::Number(String::from("-2"))),
+                Kind::Literal(Literal::Number(String::from("0.5"))),
+                Kind::Literal(Literal::Bool(true))
+            ])
+        );
+    }
+
+    #[test]
+    fn kind_literal_array() {
+        let sql = "[string, int | float]";
+        let res = kind(sql);
+        assert!(res.is_ok());
+        let out = res.unwrap().1;
+        assert_eq!("[string, int | float]", format!("{}", out));
+        assert_eq!(
+            out,
+            Kind::Literal(Literal::Array(vec![Kind::String, Kind::Either(vec![Kind::Int, Kind::Float])]))
+        );
+    }
+
+    #[test]
+    fn kind_literal_object() {
+        let sql = r#"option<{ name: string, "first-seen": datetime, tags: array<string> }>"#;
+        let res = kind(sql);
+        assert!(res.is_ok());
+        let out = res.unwrap().1;
+        assert_eq!(sql, format!("{}", out));
+        assert_eq!(
+            out,
+            Kind::Option(Box::new(Kind::Literal(Literal::Object(vec![
+                (String::from("name"), Kind::String),
+                (String::from("first-seen"), Kind::Datetime),
+                (String::from("tags"), Kind::Array(Box::new(Kind::String), None)),
+            ]))))
+        );
+    }
+
+    #[test]
+    fn kind_range_regex_function() {
+        let sql = "range | regex | function";
+        let res = kind(sql);
+        assert!(res.is_ok());
+        let out = res.unwrap().1;
+        assert_eq!("range | regex | function", format!("{}", out));
+        assert_eq!(out, Kind::Either(vec![Kind::Range, Kind::Regex, Kind::Function]));
+    }
+
+    #[test]
+    fn kind_references_any() {
+        let sql = "references";
+        let res = kind(sql);
+        assert!(res.is_ok());
+        let out = res.unwrap().1;
+        assert_eq!("references", format!("{}", out));
+        assert_eq!(out, Kind::References(None, None));
+    }
+
+    #[test]
+    fn kind_references_field() {
+        let sql = "option<references<comment, author.id>>";
+        let res = kind(sql);
+        assert!(res.is_ok());
+        let out = res.unwrap().1;
+        assert_eq!("option<references<comment, author.id>>", format!("{}", out));
+        assert_eq!(
+            out,
+            Kind::Option(Box::new(Kind::References(Some(Table::from("comment")), Some(String::from("author.id")))))
+        );
+    }
+
+    #[test]
+    fn kind_unknown() {
+        let sql = "option<file<avatars> | string>";
+        let res = kind(sql);
+        assert!(res.is_ok());
+        let out = res.unwrap().1;
+        assert_eq!("option<file<avatars> | string>", format!("{}", out));
+        assert_eq!(
+            out,
+            Kind::Option(Box::new(Kind::Either(vec![Kind::Unknown(String::from("file<avatars>")), Kind::String])))
+        );
+        assert_eq!(kind("integer").unwrap(), ("", Kind::Unknown(String::from("integer"))));
+        assert_eq!(kind("array<int> | settings").unwrap().1, Kind::Either(vec![Kind::Array(Box::new(Kind::Int), None), Kind::Unknown(String::from("settings"))]));
+        assert!(kind("file<avatars").is_err());
+    }
 }
@@ -0,0 +1,850 @@
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, tag_no_case},
+    character::complete::{char, multispace0, multispace1, space0},
+    combinator::{all_consuming, cut, map, opt, recognize, value},
+    multi::{many0, separated_list1},
+    sequence::{delimited, tuple},
+    IResult, Offset,
+};
+use std::{
+    fmt::{Display, Formatter},
+    ops::Range,
+};
+
+pub mod comment;
+pub mod common;
+pub mod directive;
+pub mod escape;
+pub mod fmt;
+pub mod ident;
+pub mod kind;
+pub mod scan;
+pub mod table;
+
+use comment::{mightbecomment, mightbespace, shouldbespace, trailing_comment};
+use common::strand;
+use directive::{split_comments, Directive};
+use escape::escape_path;
+use ident::{ident, Ident};
+use kind::{kind, Kind};
+
+use self::common::colons;
+
+/// Hash of the sources of the parser, so what's cached from a parse is never read back by a parser that may
+/// parse or serialize differently, including unreleased versions sharing a version number.
+pub const SOURCE_HASH: u64 = {
+    let sources = [
+        include_str!("mod.rs"),
+        include_str!("comment.rs"),
+        include_str!("common.rs"),
+        include_str!("directive.rs"),
+        include_str!("escape.rs"),
+        include_str!("fmt.rs"),
+        include_str!("ident.rs"),
+        include_str!("kind.rs"),
+        include_str!("scan.rs"),
+        include_str!("table.rs"),
+    ];
+    let mut hash: u64 = 0;
+    let mut i = 0;
+    while i < sources.len() {
+        hash = crate::path::fnv(sources[i]) ^ hash.rotate_left(1);
+        i += 1;
+    }
+    hash
+};
+
+/// A `DEFINE FUNCTION` statement, with the comments written above it.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DefineFunctionStatement {
+    /// Comment lines above the function that aren't directives, without the leading `--`,
+    /// followed by the lines of its `COMMENT` clause.
+    pub comments: Vec<String>,
+    pub directives: Vec<Directive>,
+    /// The name split on `::`, without the `fn` prefix.
+    pub name: Vec<String>,
+    pub args: Vec<(Ident, Kind)>,
+    /// Comments following parameters on the same line, like `$name: string, -- display name`.
+    pub arg_comments: Vec<(Ident, String)>,
+    /// The kind after `->` or from a `@returns` directive, if the function declares what it returns.
+    pub returns: Option<Kind>,
+    /// For generated aliases, the name of the function actually called.
+    pub alias_of: Option<Vec<String>>,
+    /// The statement as written in the file, from `DEFINE` to its closing brace, when parsed from a whole file.
+    pub definition: Option<String>,
+    /// The segment the `version` option of `include_fn!` prefixed to the name, left out of the Rust names.
+    pub version: Option<String>,
+}
+
+impl std::hash::Hash for DefineFunctionStatement {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+    }
+}
+
+impl DefineFunctionStatement {
+    /// The function as `fn::name($arg: kind) -> kind`, comparing equal as long as calling it the same way works.
+    pub fn signature(&self) -> String {
+        let args: Vec<_> = self.args.iter().map(|(name, kind)| format!("${name}: {kind}")).collect();
+        let mut out = format!("fn::{}({})", escape_path(&self.name), args.join(", "));
+        if let Some(kind) = &self.returns {
+            out.push_str(&format!(" -> {kind}"));
+        }
+        out
+    }
+
+    /// The name without the segment prefixed by `version`, which the Rust names are made from.
+    pub fn unversioned_name(&self) -> &[String] {
+        &self.name[usize::from(self.version.is_some())..]
+    }
+}
+
+/// Where parsing a file stopped, with the offending line for context.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+    pub snippet: String,
+}
+
+impl ParseError {
+    fn new(input: &str, remaining: &str, message: impl Into<String>) -> Self {
+        let before = &input[..input.len() - remaining.len()];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            message: message.into(),
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            snippet: input[line_start..].lines().next().unwrap_or_default().to_string(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+        writeln!(f, "{}:{}: {}", self.line, self.column, self.message)?;
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{} | {}", self.line, self.snippet)?;
+        write!(f, "{gutter} | {}^", " ".repeat(self.column - 1))
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+const UNPARSABLE: &str = "unable to parse DEFINE FUNCTION statement";
+
+/// Parses a whole file, keeping the byte range of every statement from `DEFINE` to its closing brace.
+///
+/// Everything outside of those ranges has to be whitespace, comments or semicolons,
+/// so no part of the file can be skipped without an error.
+/// Files starting with `OPTION IMPORT;`, like the dumps of `surreal export`, are the exception:
+/// their other statements are skipped, and comments are only kept within functions.
+pub fn statements(
+    input: &str,
+) -> Result<Vec<(Range<usize>, DefineFunctionStatement)>, ParseError> {
+    let dump = is_dump(input);
+    let parsed = match dump {
+        true => all_consuming(dumped_functions)(input),
+        false => all_consuming(spanned_functions)(input),
+    };
+    let statements = match parsed {
+        Ok((_, v)) => v,
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+            return Err(ParseError::new(input, e.input, UNPARSABLE))
+        }
+        Err(nom::Err::Incomplete(_)) => return Err(ParseError::new(input, "", UNPARSABLE)),
+    };
+    let statements: Vec<_> = statements.into_iter().map(|(text, v)| spanned(input, text, v)).collect();
+    if !dump {
+        check_coverage(input, &statements)?;
+    }
+    Ok(statements)
+}
+
+/// Like [`statements`], but skips the statements that can't be parsed instead of failing, with an error for each of them.
+///
+/// A statement that isn't a function is skipped up to the `;` ending it, even in the middle of a broken function.
+pub fn statements_lenient(
+    input: &str,
+) -> (Vec<(Range<usize>, DefineFunctionStatement)>, Vec<ParseError>) {
+    let dump = is_dump(input);
+    let mut statements = vec![];
+    let mut errors = vec![];
+    let mut i = input;
+    loop {
+        if let Ok((rest, _)) = ignorable(i) {
+            i = rest;
+        }
+        if i.is_empty() {
+            return (statements, errors);
+        }
+        match spanned_function(i) {
+            Ok((rest, (text, v))) => {
+                statements.push(spanned(input, text, v));
+                i = rest;
+                continue;
+            }
+            Err(nom::Err::Error(_)) if dump => {}
+            Err(nom::Err::Failure(e)) => errors.push(ParseError::new(input, e.input, UNPARSABLE)),
+            // Not a function at all
+            Err(nom::Err::Error(_) | nom::Err::Incomplete(_)) => errors.push(ParseError::new(input, i, UNPARSABLE)),
+        }
+        i = match scan::statement(i) {
+            Ok((rest, _)) => rest,
+            // Nothing after an unclosed string or block can be told apart from its contents
+            Err(_) => "",
+        };
+    }
+}
+
+/// The byte range of a statement from its `text`, which has to be a part of `input`.
+fn spanned(input: &str, text: &str, mut v: DefineFunctionStatement) -> (Range<usize>, DefineFunctionStatement) {
+    let start = input.offset(text);
+    v.definition = Some(text.to_string());
+    (start..start + text.len(), v)
+}
+
+/// Whether the first statement is `OPTION IMPORT`, which `surreal export` starts its dumps with.
+fn is_dump(input: &str) -> bool {
+    tuple((ignorable, tag_no_case("OPTION"), multispace1, tag_no_case("IMPORT"), multispace0, char(';')))(input).is_ok()
+}
+
+/// The functions of a dump, skipping any other statement.
+fn dumped_functions(mut i: &str) -> IResult<&str, Vec<(&str, DefineFunctionStatement)>> {
+    let mut out = vec![];
+    loop {
+        (i, _) = ignorable(i)?;
+        if i.is_empty() {
+            return Ok((i, out));
+        }
+        match spanned_function(i) {
+            Ok((rest, v)) => {
+                out.push(v);
+                i = rest;
+            }
+            Err(nom::Err::Error(_)) => (i, _) = scan::statement(i)?,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Makes sure the statements and the ignorable gaps between them add up to the whole file.
+fn check_coverage(
+    input: &str,
+    statements: &[(Range<usize>, DefineFunctionStatement)],
+) -> Result<(), ParseError> {
+    let mut gaps = vec![];
+    let mut last = 0;
+    for (range, _) in statements {
+        gaps.push(last..range.start);
+        last = range.end;
+    }
+    gaps.push(last..input.len());
+
+    let covered: usize = statements.iter().map(|(range, _)| range.len()).sum();
+    let mut ignored = 0;
+    for gap in gaps {
+        let text = &input[gap];
+        match ignorable(text) {
+            Ok(("", _)) => ignored += text.len(),
+            Ok((rest, _)) | Err(nom::Err::Error(nom::error::Error { input: rest, .. })) => {
+                ignored += text.offset(rest);
+                let message = format!(
+                    "content outside of any DEFINE FUNCTION statement ({covered} bytes in statements, {ignored} ignored, {} unaccounted of {})",
+                    input.len() - covered - ignored,
+                    input.len()
+                );
+                return Err(ParseError::new(input, rest, message));
+            }
+            Err(_) => return Err(ParseError::new(input, text, UNPARSABLE)),
+        }
+    }
+    Ok(())
+}
+
+fn ignorable(i: &str) -> IResult<&str, ()> {
+    let (i, _) = many0(alt((
+        value((), multispace1),
+        value((), alt((comment::block, comment::slash, comment::dash, comment::hash))),
+        value((), char(';')),
+    )))(i)?;
+    Ok((i, ()))
+}
+
+fn spanned_functions(i: &str) -> IResult<&str, Vec<(&str, DefineFunctionStatement)>> {
+    let (i, _) = multispace0(i)?;
+    let (i, v) = separated_list1(colons, spanned_function)(i)?;
+    let (i, _) = colons(i)?;
+    let (i, _) = mightbespace(i)?;
+    Ok((i, v))
+}
+
+/// A function along with its text, starting from `DEFINE`.
+fn spanned_function(i: &str) -> IResult<&str, (&str, DefineFunctionStatement)> {
+    let (start, _) = mightbecomment(i)?;
+    let (start, _) = mightbespace(start)?;
+    let (rest, v) = function(i)?;
+    Ok((rest, (&start[..start.offset(rest)], v)))
+}
+
+fn function(i: &str) -> IResult<&str, DefineFunctionStatement> {
+    let (i, comments) = mightbecomment(i)?;
+    let (i, _) = mightbespace(i)?;
+    let (i, _) = tag_no_case("DEFINE")(i)?;
+    let (i, _) = shouldbespace(i)?;
+    let (i, _) = tag_no_case("FUNCTION")(i)?;
+    let (i, _) = shouldbespace(i)?;
+    let (i, _) = opt(tuple((
+        alt((tag_no_case("OVERWRITE"), recognize(tuple((tag_no_case("IF"), shouldbespace, tag_no_case("NOT"), shouldbespace, tag_no_case("EXISTS")))))),
+        shouldbespace,
+    )))(i)?;
+    let (i, _) = tag("fn::")(i)?;
+    // Past this point this can only be a function, so report errors where they happen
+    let (i, (name, args, mut returns)) = cut(signature)(i)?;
+    // Both orders, although `INFO FOR DB` writes the comment first
+    let (i, (clause, _)) = alt((
+        map(tuple((permissions_clause, comment_clause)), |(permissions, comment)| (Some(comment), Some(permissions))),
+        tuple((opt(comment_clause), opt(permissions_clause))),
+    ))(i)?;
+    for comment in &comments {
+        // `-- @returns kind` for SurrealDB versions without `-> kind`
+        let value = comment.trim().strip_prefix("@returns");
+        if let Some(value) = value.filter(|v| v.starts_with(char::is_whitespace)) {
+            let (_, kind) = cut(all_consuming(delimited(mightbespace, kind, mightbespace)))(value)?;
+            returns = returns.or(Some(kind));
+        }
+    }
+    let lines: Vec<String> = comments.into_iter().flat_map(comment::doc_lines).collect();
+    let (mut comments, directives) = split_comments(lines.iter().map(String::as_str).collect());
+    if let Some(clause) = clause {
+        if !comments.is_empty() {
+            comments.push(String::new());
+        }
+        comments.extend(clause.lines().map(|line| line.trim_end().to_string()));
+    }
+    let arg_comments = args.iter().filter_map(|(name, _, comment)| Some((name.clone(), comment.clone()?))).collect();
+    Ok((
+        i,
+        DefineFunctionStatement {
+            comments,
+            directives,
+            name,
+            args: args.into_iter().map(|(name, kind, _)| (name, kind)).collect(),
+            arg_comments,
+            returns,
+            alias_of: None,
+            definition: None,
+            version: None,
+        },
+    ))
+}
+
+#[allow(clippy::type_complexity)]
+fn signature(i: &str) -> IResult<&str, (Vec<String>, Vec<(Ident, Kind, Option<String>)>, Option<Kind>)> {
+    let (i, name) = ident::multikeep(i)?;
+    let (i, _) = mightbespace(i)?;
+    let (i, _) = char('(')(i)?;
+    let (i, args) = params(i)?;
+    let (i, _) = mightbespace(i)?;
+    let (i, _) = char(')')(i)?;
+    let (i, _) = mightbespace(i)?;
+    let (i, returns) = opt(|i| {
+        let (i, _) = tag("->")(i)?;
+        let (i, _) = mightbespace(i)?;
+        let (i, kind) = kind(i)?;
+        let (i, _) = mightbespace(i)?;
+        Ok((i, kind))
+    })(i)?;
+    let (i, _) = ignored_block(i)?;
+    Ok((i, (name, args, returns)))
+}
+
+/// The parameters of a function, each with the comment following it on the same line, like `$name: string, -- display name`.
+#[allow(clippy::type_complexity)]
+fn params(mut i: &str) -> IResult<&str, Vec<(Ident, Kind, Option<String>)>> {
+    let mut out = vec![];
+    loop {
+        let (rest, _) = mightbespace(i)?;
+        let param = |i| {
+            let (i, _) = char('$')(i)?;
+            let (i, name) = ident(i)?;
+            let (i, _) = mightbespace(i)?;
+            let (i, _) = char(':')(i)?;
+            let (i, _) = mightbespace(i)?;
+            let (i, kind) = cut(kind)(i)?;
+            Ok((i, (name, kind)))
+        };
+        // After a comma, another parameter has to follow
+        let (rest, (name, kind)) = match out.is_empty() {
+            true => match opt(param)(rest)? {
+                (rest, Some(param)) => (rest, param),
+                (_, None) => return Ok((i, out)),
+            },
+            false => param(rest)?,
+        };
+        let (rest, _) = space0(rest)?;
+        let (rest, comma) = opt(char(','))(rest)?;
+        let (rest, _) = space0(rest)?;
+        let (rest, comment) = opt(trailing_comment)(rest)?;
+        out.push((name, kind, comment.filter(|c| !c.is_empty()).map(str::to_string)));
+        i = rest;
+        if comma.is_none() {
+            return Ok((i, out));
+        }
+    }
+}
+
+/// The `COMMENT "..."` following the body, like in the definitions of `INFO FOR DB`.
+fn comment_clause(i: &str) -> IResult<&str, String> {
+    let (i, _) = mightbespace(i)?;
+    let (i, _) = tag_no_case("COMMENT")(i)?;
+    let (i, _) = mightbespace(i)?;
+    cut(strand)(i)
+}
+
+/// The `PERMISSIONS` following the body, `FULL`, `NONE` or `WHERE` and a condition running up to the end of the statement.
+fn permissions_clause(i: &str) -> IResult<&str, ()> {
+    let (i, _) = mightbespace(i)?;
+    let (i, _) = tag_no_case("PERMISSIONS")(i)?;
+    let (i, _) = shouldbespace(i)?;
+    cut(alt((
+        value((), tag_no_case("FULL")),
+        value((), tag_no_case("NONE")),
+        value((), tuple((tag_no_case("WHERE"), shouldbespace, scan::statement))),
+    )))(i)
+}
+
+pub fn ignored_block(i: &str) -> IResult<&str, ()> {
+    let (i, _) = scan::block(i)?;
+    Ok((i, ()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec;
+
+    use super::table::Table;
+    use nom::combinator::map;
+
+    use super::*;
+
+    fn functions(i: &str) -> IResult<&str, Vec<DefineFunctionStatement>> {
+        map(spanned_functions, |v| v.into_iter().map(|(_, v)| v).collect())(i)
+    }
+
+    #[test]
+    fn function_normal() {
+        let sql = r#"DEFINE FUNCTION fn::greet($name: string) {
+	RETURN "Hello, " + $name + "!";
+}"#;
+        let res = function(sql);
+        assert!(res.is_ok());
+        let out = res.unwrap().1;
+        assert_eq!(
+            out,
+            DefineFunctionStatement {
+                comments: vec![],
+                name: vec!["greet".to_string()],
+                args: vec![(Ident::from("name"), Kind::String)],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn function_with_comments() {
+        let sql = r#"
+-- It is necessary to prefix the name of your function with "fn::"
+-- This indicates that it's a custom function
+DEFINE FUNCTION fn::greet($name: string) {
+	RETURN "Hello, " + $name + "!";
+}"#;
+        let res = function(sql);
+        assert!(res.is_ok());
+        let out = res.unwrap().1;
+        assert_eq!(
+            out,
+            DefineFunctionStatement {
+                comments: vec![
+                    "It is necessary to prefix the name of your function with \"fn::\"".to_string(),
+                    "This indicates that it's a custom function".to_string()
+                ],
+                name: vec!["greet".to_string()],
+                args: vec![(Ident::from("name"), Kind::String)],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn function_complex() {
+        let sql = r#"
+DEFINE FUNCTION fn::relation_exists::nested(
+    $in: record<some>,
+    $tb: string,
+    $out: record<other>
+) {};
+"#;
+        let res = function(sql);
+        let out = res.unwrap().1;
+        assert_eq!(
+            out,
+            DefineFunctionStatement {
+                comments: vec![],
+                name: vec!["relation_exists".to_string(), "nested".to_string()],
+                args: vec![
+                    (
+                        Ident::from("in"),
+                        Kind::Record(vec![Table("some".to_string())])
+                    ),
+                    (Ident::from("tb"), Kind::String),
+                    (
+                        Ident::from("out"),
+                        Kind::Record(vec![Table("other".to_string())])
+                    )
+                ],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn functions_basic() {
+        let sql = r#"
+-- It is necessary to prefix the name of your function with "fn::"
+-- This indicates that it's a custom function
+DEFINE FUNCTION fn::greet($name: string) {
+    RETURN "Hello, " + $name + "!";
+};
+
+// It is necessary to prefix the name of your function with "fn::"
+// This indicates that it's a custom function
+DEFINE FUNCTION fn::greet($name: string) {
+    RETURN "Hello, " + $name + "!";
+};
+
+# A different comment style
+DEFINE FUNCTION fn::relation_exists::nested(
+    $in: record<some>,
+    $tb: string,
+    $out: record<other>
+) {};
+"#;
+        let res = functions(sql);
+        let out = res.unwrap().1;
+        assert_eq!(
+            out,
+            vec![
+                DefineFunctionStatement {
+                    comments: vec![
+                        "It is necessary to prefix the name of your function with \"fn::\""
+                            .to_string(),
+                        "This indicates that it's a custom function".to_string()
+                    ],
+                    name: vec!["greet".to_string()],
+                    args: vec![(Ident::from("name"), Kind::String)],
+                    ..Default::default()
+                },
+                DefineFunctionStatement {
+                    comments: vec![
+                        "It is necessary to prefix the name of your function with \"fn::\""
+                            .to_string(),
+                        "This indicates that it's a custom function".to_string()
+                    ],
+                    name: vec!["greet".to_string()],
+                    args: vec![(Ident::from("name"), Kind::String)],
+                    ..Default::default()
+                },
+                DefineFunctionStatement {
+                    comments: vec!["A different comment style".to_string(),],
+                    name: vec!["relation_exists".to_string(), "nested".to_string()],
+                    args: vec![
+                        (
+                            Ident::from("in"),
+                            Kind::Record(vec![Table("some".to_string())])
+                        ),
+                        (Ident::from("tb"), Kind::String),
+                        (
+                            Ident::from("out"),
+                            Kind::Record(vec![Table("other".to_string())])
+                        )
+                    ],
+                    ..Default::default()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn functions_same_line() {
+        // As written by `surreal export`
+        let sql = "DEFINE FUNCTION fn::greet($name: string) { RETURN 'Hello, ' + $name + '!'; }; DEFINE FUNCTION fn::nested::greet($name: string) { RETURN 'Hello, ' + $name + '!'; };\n";
+        let out = functions(sql).unwrap();
+        assert_eq!(out.0, "");
+        assert_eq!(
+            out.1.iter().map(|f| f.name.clone()).collect::<Vec<_>>(),
+            vec![vec!["greet".to_string()], vec!["nested".to_string(), "greet".to_string()]]
+        );
+    }
+
+    #[test]
+    fn functions_same_line_spaces_and_comments() {
+        let sql = r#"
+-- ------------------------------
+-- FUNCTIONS
+-- ------------------------------
+
+DEFINE FUNCTION fn::a() { RETURN 1; } ;  ; DEFINE FUNCTION fn::b($x: int) { RETURN $x; };	-- trailing comment
+-- Docs for c
+DEFINE FUNCTION fn::c() { RETURN 3; }; /* block */ DEFINE FUNCTION fn::d() { RETURN 4; }; # the end
+"#;
+        let out = functions(sql).unwrap();
+        assert_eq!(out.0, "");
+        assert_eq!(
+            out.1
+                .iter()
+                .map(|f| (f.name.join("::"), f.comments.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "a".to_string(),
+                    vec![
+                        "------------------------------".to_string(),
+                        "FUNCTIONS".to_string(),
+                        "------------------------------".to_string()
+                    ]
+                ),
+                ("b".to_string(), vec![]),
+                ("c".to_string(), vec!["Docs for c".to_string()]),
+                ("d".to_string(), vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn functions_trailing_comments_at_end() {
+        let sql = "DEFINE FUNCTION fn::a() { RETURN 1; };\n\n-- nothing else\n";
+        let out = functions(sql).unwrap();
+        assert_eq!(out.0, "");
+        assert_eq!(out.1.len(), 1);
+    }
+
+    #[test]
+    fn function_with_directives() {
+        let sql = r#"
+-- Returns a greeting
+-- @alias greet_old
+DEFINE FUNCTION fn::greet($name: string) {
+	RETURN "Hello, " + $name + "!";
+}"#;
+        let res = function(sql);
+        let out = res.unwrap().1;
+        assert_eq!(
+            out,
+            DefineFunctionStatement {
+                comments: vec!["Returns a greeting".to_string()],
+                directives: vec![Directive {
+                    name: "alias".to_string(),
+                    value: "greet_old".to_string(),
+                }],
+                name: vec!["greet".to_string()],
+                args: vec![(Ident::from("name"), Kind::String)],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn parse_error_position() {
+        let sql = "DEFINE FUNCTION fn::a() { RETURN 1; };\n\nDEFINE FUNCTION fn::b($x: <string>) { RETURN $x; };\n";
+        let err = statements(sql).unwrap_err();
+        assert_eq!(
+            err,
+            ParseError {
+                message: UNPARSABLE.to_string(),
+                line: 3,
+                column: 27,
+                snippet: "DEFINE FUNCTION fn::b($x: <string>) { RETURN $x; };".to_string(),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "3:27: unable to parse DEFINE FUNCTION statement\n  |\n3 | DEFINE FUNCTION fn::b($x: <string>) { RETURN $x; };\n  |                           ^"
+        );
+    }
+
+    #[test]
+    fn parse_error_other_statement() {
+        let sql = "DEFINE FUNCTION fn::a() { RETURN 1; };\nDEFINE TABLE person;\n";
+        let err = statements(sql).unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+    }
+
+    #[test]
+    fn statements_spans() {
+        let sql = "-- docs\nDEFINE FUNCTION fn::a() { RETURN 1; }; DEFINE FUNCTION fn::b() { RETURN 2; };\n";
+        let out = statements(sql).unwrap();
+        assert_eq!(
+            out.iter().map(|(_, v)| v.definition.as_deref().unwrap()).collect::<Vec<_>>(),
+            vec![
+                "DEFINE FUNCTION fn::a() { RETURN 1; }",
+                "DEFINE FUNCTION fn::b() { RETURN 2; }"
+            ]
+        );
+        assert_eq!(
+            out.iter().map(|(range, _)| &sql[range.clone()]).collect::<Vec<_>>(),
+            vec![
+                "DEFINE FUNCTION fn::a() { RETURN 1; }",
+                "DEFINE FUNCTION fn::b() { RETURN 2; }"
+            ]
+        );
+    }
+
+    #[test]
+    fn coverage_gap() {
+        let sql = "DEFINE FUNCTION fn::a() { RETURN 1; };\n-- fine\nDEFINE FUNCTION fn::b() { RETURN 2; };\n";
+        let out = statements(sql).unwrap();
+        assert!(check_coverage(sql, &out).is_ok());
+        // Pretend the second statement was skipped
+        let err = check_coverage(sql, &out[..1]).unwrap_err();
+        assert_eq!((err.line, err.column), (3, 1));
+        assert!(
+            err.message.contains("37 bytes in statements, 10 ignored, 39 unaccounted of 86"),
+            "{}",
+            err.message
+        );
+    }
+
+    #[test]
+    fn function_arg_comments() {
+        let sql = "DEFINE FUNCTION fn::a(
+    $name: string, -- display name
+    -- not about $age
+    $age: int, /* in years */ $tags: array<string> # labels
+) { RETURN 1; }";
+        let out = function(sql).unwrap().1;
+        assert_eq!(out.args.len(), 3);
+        assert_eq!(
+            out.arg_comments,
+            vec![
+                (Ident::from("name"), "display name".to_string()),
+                (Ident::from("age"), "in years".to_string()),
+                (Ident::from("tags"), "labels".to_string()),
+            ]
+        );
+        assert!(function("DEFINE FUNCTION fn::a($name: string,) { RETURN 1; }").is_err());
+        assert!(function("DEFINE FUNCTION fn::a() { RETURN 1; }").unwrap().1.args.is_empty());
+    }
+
+    #[test]
+    fn function_returns() {
+        let sql = "DEFINE FUNCTION fn::names($limit: int) -> array<string> { RETURN []; }";
+        let out = function(sql).unwrap().1;
+        assert_eq!(out.returns, Some(Kind::Array(Box::new(Kind::String), None)));
+        let sql = "DEFINE FUNCTION fn::names() { RETURN []; }";
+        assert_eq!(function(sql).unwrap().1.returns, None);
+        let sql = "-- @returns option<int>\nDEFINE FUNCTION fn::count() { RETURN 1; }";
+        assert_eq!(
+            function(sql).unwrap().1.returns,
+            Some(Kind::Option(Box::new(Kind::Int)))
+        );
+        let sql = "-- @returns file<avatars>\nDEFINE FUNCTION fn::count() { RETURN 1; }";
+        assert_eq!(function(sql).unwrap().1.returns, Some(Kind::Unknown("file<avatars>".to_string())));
+        let sql = "-- @returns <nope>\nDEFINE FUNCTION fn::count() { RETURN 1; }";
+        assert!(matches!(function(sql), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn function_comment_clause() {
+        let sql = "-- Greets someone\nDEFINE FUNCTION fn::greet($name: string) { RETURN $name; } COMMENT 'Says \\'hi\\'\\nto $name'";
+        let (rest, out) = function(sql).unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(out.comments, vec!["Greets someone", "", "Says 'hi'", "to $name"]);
+        let sql = "DEFINE FUNCTION fn::greet() { RETURN 1; } comment \"Greets\";";
+        let out = statements(sql).unwrap();
+        assert_eq!(out[0].1.comments, vec!["Greets"]);
+        assert_eq!(out[0].1.definition.as_deref(), Some("DEFINE FUNCTION fn::greet() { RETURN 1; } comment \"Greets\""));
+        assert!(matches!(function("DEFINE FUNCTION fn::greet() { RETURN 1; } COMMENT 'open"), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn export_dump() {
+        let sql = include_str!("../../tests/export.surql");
+        let out = statements(sql).unwrap();
+        let names: Vec<_> = out.iter().map(|(_, v)| v.name.join("::")).collect();
+        assert_eq!(names, vec!["greet", "users::active", "users::rename"]);
+        // Section headers aren't documentation
+        assert_eq!(out[0].1.comments, vec!["Greets someone"]);
+        assert_eq!(out[0].1.returns, Some(Kind::String));
+        assert!(out[1].1.comments.is_empty());
+        assert!(out[1].1.definition.as_deref().unwrap().ends_with("} PERMISSIONS WHERE $auth.admin = true"));
+        assert!(out[2].1.definition.as_deref().unwrap().ends_with("} PERMISSIONS NONE"));
+
+        // Without `OPTION IMPORT` other statements are still an error
+        let sql = sql.replacen("OPTION IMPORT;", "", 1);
+        assert!(statements(&sql).is_err());
+    }
+
+    #[test]
+    fn info_for_db_definitions() {
+        // As returned by `INFO FOR DB`, and what `DEFINE FUNCTION` accepts around it
+        for sql in [
+            "DEFINE FUNCTION fn::a($x: int) { IF $x > 0 { RETURN { ok: true }; } ELSE { RETURN { ok: false }; }; } PERMISSIONS FULL",
+            "DEFINE FUNCTION fn::a($x: int) { RETURN $x; } COMMENT 'docs' PERMISSIONS WHERE $auth.id = user:admin",
+            "DEFINE FUNCTION fn::a($x: int) { RETURN $x; } PERMISSIONS NONE COMMENT 'docs'",
+            "DEFINE FUNCTION OVERWRITE fn::a($x: int) { RETURN $x; }",
+            "DEFINE FUNCTION IF NOT EXISTS fn::a($x: int) { RETURN $x; }",
+        ] {
+            let (rest, out) = function(sql).unwrap_or_else(|e| panic!("{sql}\n{e}"));
+            assert_eq!((rest, out.name), ("", vec!["a".to_string()]), "{sql}");
+        }
+        assert!(matches!(function("DEFINE FUNCTION fn::a() { RETURN 1; } PERMISSIONS SOME"), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn lenient_skips_broken() {
+        let sql = "DEFINE FUNCTION fn::a() { RETURN 1; };\nDEFINE FUNCTION fn::b($x: <string>) { RETURN $x; };\nDEFINE TABLE person;\nDEFINE FUNCTION fn::c() { RETURN 'c;}'; };\n";
+        let (out, errors) = statements_lenient(sql);
+        assert_eq!(out.iter().map(|(_, v)| v.name.join("::")).collect::<Vec<_>>(), vec!["a", "c"]);
+        assert_eq!(&sql[out[1].0.clone()], "DEFINE FUNCTION fn::c() { RETURN 'c;}'; }");
+        assert_eq!(errors.iter().map(|e| (e.line, e.column)).collect::<Vec<_>>(), vec![(2, 27), (3, 1)]);
+
+        let (out, errors) = statements_lenient("DEFINE FUNCTION fn::a() { RETURN 1; };\nDEFINE FUNCTION fn::b() { RETURN '1; };");
+        assert_eq!((out.len(), errors.len()), (1, 1));
+    }
+
+    #[test]
+    fn body_strings() {
+        let sql = r#"DEFINE FUNCTION fn::a() { RETURN "a;}"; };
+DEFINE FUNCTION fn::b() { RETURN 'it\'s {' + "\";}" + ⟨c};⟩ + `d;{`; };
+DEFINE FUNCTION fn::c() { -- } not the end;
+    /* neither is }; */ RETURN { a: ["}", '{'] };
+};
+"#;
+        let out = statements(sql).unwrap();
+        let definitions: Vec<_> = out.iter().map(|(range, _)| &sql[range.clone()]).collect();
+        assert_eq!(definitions[0], r#"DEFINE FUNCTION fn::a() { RETURN "a;}"; }"#);
+        assert_eq!(definitions[1], r#"DEFINE FUNCTION fn::b() { RETURN 'it\'s {' + "\";}" + ⟨c};⟩ + `d;{`; }"#);
+        assert!(definitions[2].ends_with("RETURN { a: [\"}\", '{'] };\n}"));
+    }
+
+    #[test]
+    fn lenient_skips_closures() {
+        let sql = r#"DEFINE FIELD score ON user VALUE |$x: int| -> int { LET $y = $x * 2; RETURN $y; }($value);
+DEFINE FIELD tags ON user VALUE $value.map(|$tag| string::concat($tag, ";")) DEFAULT [];
+DEFINE FIELD label ON user DEFAULT "a;b}" + '{' + ⟨c;⟩; -- trailing; comment }
+DEFINE EVENT log ON user WHEN true THEN { IF $after { CREATE log SET at = time::now(); }; };
+DEFINE FUNCTION fn::double($x: int) -> int { RETURN (|$y: int| $y * 2)($x); };
+"#;
+        let (out, errors) = statements_lenient(sql);
+        assert_eq!(out.iter().map(|(_, v)| v.name.join("::")).collect::<Vec<_>>(), vec!["double"]);
+        assert_eq!(&sql[out[0].0.clone()], "DEFINE FUNCTION fn::double($x: int) -> int { RETURN (|$y: int| $y * 2)($x); }");
+        assert_eq!(errors.iter().map(|e| e.line).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        assert!(errors.iter().all(|e| e.column == 1));
+    }
+}
@@ -0,0 +1,179 @@
+use std::{iter::Peekable, ops::Range, str::CharIndices};
+
+use nom::{
+    character::complete::char,
+    error::{Error, ErrorKind},
+    IResult,
+};
+
+/// A block in braces, like the body of a function, returning what's between them.
+pub fn block(i: &str) -> IResult<&str, &str> {
+    let (i, _) = char('{')(i)?;
+    let end = skip_to(i, '}')?;
+    Ok((&i[end + 1..], &i[..end]))
+}
+
+/// Any statement, up to the `;` ending it or the end of the input, returning its text.
+pub fn statement(i: &str) -> IResult<&str, &str> {
+    let end = match skip_to(i, ';') {
+        Ok(end) => end,
+        Err(nom::Err::Error(e)) if e.input.is_empty() => i.len(),
+        Err(e) => return Err(e),
+    };
+    let text = i[..end].trim_end();
+    if text.is_empty() {
+        return Err(nom::Err::Error(Error::new(i, ErrorKind::TakeUntil)));
+    }
+    Ok((&i[text.len()..], text))
+}
+
+/// Byte offset of the first `stop` outside of strings, comments and brackets.
+///
+/// Fails at the end of the input if there's none, or where a bracket is closed without being opened.
+fn skip_to(i: &str, stop: char) -> Result<usize, nom::Err<Error<&str>>> {
+    let error = |at: usize| nom::Err::Error(Error::new(&i[at..], ErrorKind::TakeUntil));
+    let mut closers = vec![];
+    let mut chars = i.char_indices().peekable();
+    while let Some((n, c)) = chars.next() {
+        let next = chars.peek().map(|(_, c)| *c);
+        match c {
+            c if c == stop && closers.is_empty() => return Ok(n),
+            '{' => closers.push('}'),
+            '[' => closers.push(']'),
+            '(' => closers.push(')'),
+            '}' | ']' | ')' => match closers.pop() {
+                Some(closer) if closer == c => {}
+                _ => return Err(error(n)),
+            },
+            c if !skip_literal(&mut chars, c, next) => return Err(error(n)),
+            _ => {}
+        }
+    }
+    Err(error(i.len()))
+}
+
+/// Byte offset of every `fn::` outside of strings and comments, where a custom function is named.
+pub fn function_names(i: &str) -> Vec<usize> {
+    let mut out = vec![];
+    let mut chars = i.char_indices().peekable();
+    while let Some((n, c)) = chars.next() {
+        let next = chars.peek().map(|(_, c)| *c);
+        match c {
+            'f' if i[n..].starts_with("fn::") && !i[..n].ends_with(super::common::val_char) => out.push(n),
+            // An unterminated string or comment has no names left in it
+            c if !skip_literal(&mut chars, c, next) => break,
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Byte range of every `$param` outside of strings and comments, with the name of the parameter, unescaped.
+pub fn params(i: &str) -> Vec<(Range<usize>, String)> {
+    let mut out = vec![];
+    let mut chars = i.char_indices().peekable();
+    while let Some((n, c)) = chars.next() {
+        let next = chars.peek().map(|(_, c)| *c);
+        match c {
+            '$' => {
+                let Ok((rest, name)) = super::ident::ident_raw(&i[n + 1..]) else { continue };
+                let end = i.len() - rest.len();
+                while chars.next_if(|&(n, _)| n < end).is_some() {}
+                out.push((n..end, name));
+            }
+            // An unterminated string or comment has no parameters left in it
+            c if !skip_literal(&mut chars, c, next) => break,
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Skips the string or comment opened by `c`, if it opens one, returning `false` if it's never closed.
+fn skip_literal(chars: &mut Peekable<CharIndices<'_>>, c: char, next: Option<char>) -> bool {
+    match c {
+        '\'' | '"' | '`' | '⟨' => {
+            let close = if c == '⟨' { '⟩' } else { c };
+            loop {
+                match chars.next() {
+                    Some((_, '\\')) => {
+                        chars.next();
+                    }
+                    Some((_, c)) if c == close => return true,
+                    Some(_) => {}
+                    None => return false,
+                }
+            }
+        }
+        '-' if next == Some('-') => skip_line(chars),
+        '/' if next == Some('/') => skip_line(chars),
+        '#' => skip_line(chars),
+        '/' if next == Some('*') => {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some((_, '*')) if chars.peek().is_some_and(|(_, c)| *c == '/') => {
+                        chars.next();
+                        return true;
+                    }
+                    Some(_) => {}
+                    None => return false,
+                }
+            }
+        }
+        _ => {}
+    }
+    true
+}
+
+fn skip_line(chars: &mut impl Iterator<Item = (usize, char)>) {
+    for (_, c) in chars {
+        if c == '\n' {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_nested() {
+        let sql = "{ IF $a { RETURN { b: [1, (2)] }; }; RETURN 'x}' + \"{\" + ⟨a}⟩; -- }\n /* } */ } rest";
+        let (rest, body) = block(sql).unwrap();
+        assert_eq!(rest, " rest");
+        assert!(body.ends_with("/* } */ "));
+        assert!(block("{ RETURN [1); }").is_err());
+        assert!(block("{ RETURN '}; }").is_err());
+    }
+
+    #[test]
+    fn statement_until_semicolon() {
+        assert_eq!(statement("INSERT [{ a: ';' }] ;\nNEXT"), Ok((" ;\nNEXT", "INSERT [{ a: ';' }]")));
+        assert_eq!(statement("OPTION IMPORT"), Ok(("", "OPTION IMPORT")));
+        assert!(statement("; x").is_err());
+    }
+
+    #[test]
+    fn names_outside_strings() {
+        let sql = "DEFINE FUNCTION fn::a() { RETURN fn::b() + 'fn::c' + myfn::d; -- fn::e\n };";
+        let names: Vec<_> = function_names(sql).into_iter().map(|n| &sql[n..n + 5]).collect();
+        assert_eq!(names, vec!["fn::a", "fn::b"]);
+    }
+
+    #[test]
+    fn params_outside_strings() {
+        let sql = "$team + $⟨first name⟩ + '$quoted' + $`a\\`b` -- $comment\n + $teams";
+        let params: Vec<_> = params(sql).into_iter().map(|(range, name)| (&sql[range], name)).collect();
+        assert_eq!(
+            params,
+            vec![
+                ("$team", "team".to_string()),
+                ("$⟨first name⟩", "first name".to_string()),
+                ("$`a\\`b`", "a`b".to_string()),
+                ("$teams", "teams".to_string())
+            ]
+        );
+    }
+}
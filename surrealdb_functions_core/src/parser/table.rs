@@ -32,7 +32,9 @@ impl Display for Tables {
     }
 }
 
+/// A table name, as in `record<user>`.
 #[derive(Clone, Debug, Default, Eq, PartialEq, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Table(pub String);
 
 impl From<String> for Table {
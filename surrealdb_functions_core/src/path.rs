@@ -0,0 +1,268 @@
+//! Paths given to `include_fn!`, shared with `surrealdb_functions_build` so both resolve them and name their variables the same way.
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+};
+
+/// Replaces every `$VAR` in `raw` by the value `get_env` returns for it.
+///
+/// Variables are made of ASCII letters, digits and `_`, and don't start with a digit. Values aren't resolved again.
+pub fn resolve_path(
+    raw: &str,
+    get_env: impl Fn(&str) -> Option<String>,
+) -> Result<PathBuf, Box<dyn Error>> {
+    let mut unprocessed = raw;
+    let mut resolved = String::new();
+
+    while let Some(dollar_sign) = unprocessed.find('$') {
+        let (head, tail) = unprocessed.split_at(dollar_sign);
+        resolved.push_str(head);
+
+        match parse_identifier(&tail[1..]) {
+            Some((variable, rest)) => {
+                let value = get_env(variable).ok_or_else(|| MissingVariable {
+                    variable: variable.to_string(),
+                })?;
+                resolved.push_str(&value);
+                unprocessed = rest;
+            }
+            None => {
+                return Err(UnableToParseVariable { rest: tail.into() }.into());
+            }
+        }
+    }
+    resolved.push_str(unprocessed);
+
+    Ok(PathBuf::from(resolved))
+}
+
+/// A variable of the path that `get_env` has no value for.
+#[derive(Debug, PartialEq)]
+pub struct MissingVariable {
+    pub variable: String,
+}
+
+impl Error for MissingVariable {}
+
+impl Display for MissingVariable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Unable to resolve ${}", self.variable)
+    }
+}
+
+/// A `$` that isn't followed by a variable name, with the rest of the path.
+#[derive(Debug, PartialEq)]
+pub struct UnableToParseVariable {
+    pub rest: String,
+}
+
+impl Error for UnableToParseVariable {}
+
+impl Display for UnableToParseVariable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Unable to parse a variable from \"{}\"", self.rest)
+    }
+}
+
+fn parse_identifier(text: &str) -> Option<(&str, &str)> {
+    let mut calls = 0;
+
+    let (head, tail) = take_while(text, |c| {
+        calls += 1;
+
+        match c {
+            '_' => true,
+            letter if letter.is_ascii_alphabetic() => true,
+            digit if digit.is_ascii_digit() && calls > 1 => true,
+            _ => false,
+        }
+    });
+
+    if head.is_empty() {
+        None
+    } else {
+        Some((head, tail))
+    }
+}
+
+fn take_while(s: &str, mut predicate: impl FnMut(char) -> bool) -> (&str, &str) {
+    let mut index = 0;
+
+    for c in s.chars() {
+        if predicate(c) {
+            index += c.len_utf8();
+        } else {
+            break;
+        }
+    }
+
+    s.split_at(index)
+}
+
+/// Name of the variable holding the hash of the entries of the directory at `path`.
+///
+/// Set by `surrealdb_functions_build::rerun_if_changed` and read by the macro, on stable.
+pub fn listing_var(path: &Path) -> String {
+    format!("SURREALDB_FUNCTIONS_DIR_{:016X}", fnv(&path.to_string_lossy()))
+}
+
+/// FNV-1a hash of `text`, which unlike `DefaultHasher` won't change with the compiler.
+pub const fn fnv(text: &str) -> u64 {
+    let bytes = text.as_bytes();
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    // `for` and `u64::from` aren't allowed in a `const fn`
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        i += 1;
+    }
+    hash
+}
+
+/// The directory named by `package.workspace` in the manifest of `manifest_dir`, like cargo does, otherwise the closest directory
+/// above it with a `[workspace]` manifest, or `manifest_dir` itself.
+pub fn find_workspace_root(manifest_dir: &Path) -> PathBuf {
+    if let Some(root) = explicit_workspace(manifest_dir) {
+        return root;
+    }
+    manifest_dir
+        .ancestors()
+        .find(|dir| {
+            std::fs::read_to_string(dir.join("Cargo.toml")).is_ok_and(|manifest| {
+                manifest.lines().any(|line| line.split('#').next().unwrap_or_default().trim() == "[workspace]")
+            })
+        })
+        .unwrap_or(manifest_dir)
+        .to_path_buf()
+}
+
+/// The `workspace = "../.."` key of the `[package]` table, for members outside of the directory of their workspace.
+fn explicit_workspace(manifest_dir: &Path) -> Option<PathBuf> {
+    let manifest = std::fs::read_to_string(manifest_dir.join("Cargo.toml")).ok()?;
+    let mut package = false;
+    for line in manifest.lines().map(|line| line.split('#').next().unwrap_or_default().trim()) {
+        if line.starts_with('[') {
+            package = line == "[package]";
+        } else if let Some(("workspace", value)) = line.split_once('=').map(|(key, value)| (key.trim(), value.trim())) {
+            if package {
+                return Some(manifest_dir.join(value.trim_matches(['"', '\''])));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_path_with_no_environment_variables() {
+        let path = "./file.txt";
+
+        let resolved = resolve_path(path, |_| unreachable!()).unwrap();
+
+        assert_eq!(resolved.to_str().unwrap(), path);
+    }
+
+    #[test]
+    fn simple_environment_variable() {
+        let path = "./$VAR";
+
+        let resolved = resolve_path(path, |name| {
+            assert_eq!(name, "VAR");
+            Some("file.txt".to_string())
+        })
+        .unwrap();
+
+        assert_eq!(resolved.to_str().unwrap(), "./file.txt");
+    }
+
+    #[test]
+    fn dont_resolve_recursively() {
+        let path = "./$TOP_LEVEL.txt";
+
+        let resolved = resolve_path(path, |name| match name {
+            "TOP_LEVEL" => Some("$NESTED".to_string()),
+            "$NESTED" => unreachable!("Shouldn't resolve recursively"),
+            _ => unreachable!(),
+        })
+        .unwrap();
+
+        assert_eq!(resolved.to_str().unwrap(), "./$NESTED.txt");
+    }
+
+    #[test]
+    fn parse_valid_identifiers() {
+        let inputs = vec![
+            ("a", "a"),
+            ("a_", "a_"),
+            ("_asf", "_asf"),
+            ("a1", "a1"),
+            ("a1_#sd", "a1_"),
+        ];
+
+        for (src, expected) in inputs {
+            let (got, rest) = parse_identifier(src).unwrap();
+            assert_eq!(got.len() + rest.len(), src.len());
+            assert_eq!(got, expected);
+        }
+    }
+
+    #[test]
+    fn unknown_environment_variable() {
+        let path = "$UNKNOWN";
+
+        let err = resolve_path(path, |_| None).unwrap_err();
+
+        let missing_variable = err.downcast::<MissingVariable>().unwrap();
+        assert_eq!(
+            *missing_variable,
+            MissingVariable {
+                variable: String::from("UNKNOWN"),
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_variables() {
+        let inputs = &["$1", "$"];
+
+        for input in inputs {
+            let err = resolve_path(input, |_| unreachable!()).unwrap_err();
+
+            let err = err.downcast::<UnableToParseVariable>().unwrap();
+            assert_eq!(
+                *err,
+                UnableToParseVariable {
+                    rest: input.to_string(),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn listing_var_hash() {
+        assert_eq!(listing_var(Path::new("/crate/surql")), "SURREALDB_FUNCTIONS_DIR_AB6B6EF1D8BAE969");
+    }
+
+    #[test]
+    fn workspace_root() {
+        let dir = std::env::temp_dir().join("surrealdb_functions_workspace");
+        let member = dir.join("crates/member");
+        std::fs::create_dir_all(&member).unwrap();
+        std::fs::write(dir.join("Cargo.toml"), "[workspace]\nmembers = [\"crates/*\"]\n").unwrap();
+        std::fs::write(member.join("Cargo.toml"), "[package]\nname = \"member\"\n").unwrap();
+
+        assert_eq!(find_workspace_root(&member), dir);
+        std::fs::write(dir.join("Cargo.toml"), "[workspace] # shared schema lives here\n").unwrap();
+        assert_eq!(find_workspace_root(&member), dir);
+        std::fs::remove_file(dir.join("Cargo.toml")).unwrap();
+        assert_eq!(find_workspace_root(&member), member);
+
+        std::fs::write(member.join("Cargo.toml"), "[package]\nname = \"member\"\nworkspace = \"../../root\" # elsewhere\n").unwrap();
+        assert_eq!(find_workspace_root(&member), member.join("../../root"));
+    }
+}
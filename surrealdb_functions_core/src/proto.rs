@@ -0,0 +1,108 @@
+//! Protobuf description of the functions, for serving them over gRPC.
+//!
+//! The .proto file has to exist before `include_fn!` expands, for tonic to generate the messages the `grpc` option implements
+//! the service with, so `surrealdb_functions_runtime` writes it from build scripts and the macro follows the same naming.
+
+use crate::parser::{kind::Kind, DefineFunctionStatement};
+
+/// How a parameter or result of some kind is sent in a message.
+pub struct Field {
+    /// Protobuf scalar type of the field, or `None` for values sent as JSON in a `string`.
+    pub scalar: Option<&'static str>,
+    /// Whether the field is `optional`, for `option` kinds.
+    pub optional: bool,
+}
+
+impl Field {
+    pub fn of(kind: &Kind) -> Self {
+        let (kind, optional) = match kind {
+            Kind::Option(kind) => (&**kind, true),
+            kind => (kind, false),
+        };
+        let scalar = match kind {
+            Kind::Bool => Some("bool"),
+            Kind::Int => Some("int64"),
+            Kind::Float | Kind::Number => Some("double"),
+            Kind::String => Some("string"),
+            _ => None,
+        };
+        Self { scalar, optional }
+    }
+
+    /// The field as written in the message, like `optional int64 limit = 2;`.
+    fn declaration(&self, name: &str, number: usize) -> String {
+        let optional = if self.optional { "optional " } else { "" };
+        format!("{optional}{} {name} = {number};", self.scalar.unwrap_or("string"))
+    }
+}
+
+/// Name of the rpc calling `function` and prefix of its messages, like `NestedGreet` for `fn::nested::greet`.
+pub fn rpc_name(function: &DefineFunctionStatement) -> String {
+    function
+        .unversioned_name()
+        .iter()
+        .flat_map(|part| part.split('_'))
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first).chain(chars).collect::<String>()
+        })
+        .collect()
+}
+
+/// `name` in snake case, the way prost and tonic name the fields and methods they generate.
+pub fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.char_indices() {
+        if c.is_ascii_uppercase() && i > 0 && !out.ends_with('_') {
+            out.push('_');
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    out
+}
+
+/// A .proto file with a `Functions` service in `package`, with an rpc for every function.
+///
+/// The parameters of a function are the fields of its `<Rpc>Request` message, what it returns the `value` of its `<Rpc>Response`.
+/// Booleans, integers, floats and strings are sent as the matching protobuf types, anything else as JSON in a `string`.
+pub fn proto_file(package: &str, functions: &[DefineFunctionStatement]) -> String {
+    let mut service = String::new();
+    let mut messages = String::new();
+    for function in functions {
+        let rpc = rpc_name(function);
+        service.push_str(&format!("  // {}\n", function.signature()));
+        service.push_str(&format!("  rpc {rpc}({rpc}Request) returns ({rpc}Response);\n"));
+
+        messages.push_str(&format!("\nmessage {rpc}Request {{\n"));
+        for (number, (param, kind)) in function.args.iter().enumerate() {
+            messages.push_str(&format!("  {}\n", Field::of(kind).declaration(&param.to_raw(), number + 1)));
+        }
+        messages.push_str("}\n");
+
+        let returns = Field::of(function.returns.as_ref().unwrap_or(&Kind::Any));
+        messages.push_str(&format!("\nmessage {rpc}Response {{\n  {}\n}}\n", returns.declaration("value", 1)));
+    }
+    format!("// Generated from the DEFINE FUNCTION statements, don't edit.\nsyntax = \"proto3\";\n\npackage {package};\n\nservice Functions {{\n{service}}}\n{messages}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::statements;
+
+    #[test]
+    fn proto_messages() {
+        let sql = "DEFINE FUNCTION fn::nested::greet_user($name: string, $limit: option<int>, $tags: array<string>) -> option<float> { RETURN 1.0; };";
+        let functions: Vec<_> = statements(sql).unwrap().into_iter().map(|(_, function)| function).collect();
+        assert_eq!(rpc_name(&functions[0]), "NestedGreetUser");
+        assert_eq!(snake_case("NestedGreetUser"), "nested_greet_user");
+        assert_eq!(snake_case("userId"), "user_id");
+        let proto = proto_file("functions", &functions);
+        assert!(proto.contains("package functions;\n"), "{proto}");
+        assert!(proto.contains("  rpc NestedGreetUser(NestedGreetUserRequest) returns (NestedGreetUserResponse);\n"), "{proto}");
+        assert!(proto.contains("message NestedGreetUserRequest {\n  string name = 1;\n  optional int64 limit = 2;\n  string tags = 3;\n}\n"), "{proto}");
+        assert!(proto.contains("message NestedGreetUserResponse {\n  optional double value = 1;\n}\n"), "{proto}");
+    }
+}
@@ -0,0 +1,183 @@
+//! `surrealdb-functions`, the counterpart of `define_functions` for deploy pipelines, reading the .surql files at runtime.
+//!
+//! Lists, validates, diffs and applies function files with the parser of `include_fn!`, without compiling the application.
+use std::{collections::BTreeMap, process::ExitCode};
+
+use surrealdb::{
+    engine::any::{connect, Any},
+    opt::auth::Root,
+    sql::Value,
+    Surreal,
+};
+use surrealdb_functions_runtime::{function_definitions, parse_function_files, DefineFunctionStatement};
+
+const USAGE: &str = "\
+usage: surrealdb-functions <list|validate|diff|apply> [options] <path>...
+
+commands:
+  list       print the signature of every function
+  validate   check that the files only hold functions, like include_fn! does
+  diff       compare the functions with the ones of the database, exiting with 1 if they differ
+  apply      define the functions that are missing or differ in the database, in a transaction
+
+options:
+  --endpoint <url>   database to connect to, like ws://localhost:8000, defaults to $SURREAL_ENDPOINT
+  --ns <name>        namespace, defaults to $SURREAL_NS
+  --db <name>        database, defaults to $SURREAL_DB
+  --user <name>      root user to sign in as, defaults to $SURREAL_USER
+  --pass <password>  password of the user, defaults to $SURREAL_PASS
+  --prune            with apply, also remove the functions of the database missing from the files
+
+Paths are .surql files or directories, read recursively in sorted order.";
+
+struct Options {
+    command: String,
+    paths: Vec<String>,
+    endpoint: Option<String>,
+    ns: Option<String>,
+    db: Option<String>,
+    user: Option<String>,
+    pass: Option<String>,
+    prune: bool,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let command = args.next().ok_or("missing command")?;
+        let env = |name: &str| std::env::var(name).ok();
+        let mut options = Options {
+            command,
+            paths: vec![],
+            endpoint: env("SURREAL_ENDPOINT"),
+            ns: env("SURREAL_NS"),
+            db: env("SURREAL_DB"),
+            user: env("SURREAL_USER"),
+            pass: env("SURREAL_PASS"),
+            prune: false,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing value of {arg}"));
+            match arg.as_str() {
+                "--endpoint" => options.endpoint = Some(value()?),
+                "--ns" => options.ns = Some(value()?),
+                "--db" => options.db = Some(value()?),
+                "--user" => options.user = Some(value()?),
+                "--pass" => options.pass = Some(value()?),
+                "--prune" => options.prune = true,
+                flag if flag.starts_with("--") => return Err(format!("unknown option {flag}")),
+                _ => options.paths.push(arg),
+            }
+        }
+        if options.paths.is_empty() {
+            return Err("missing paths".to_string());
+        }
+        Ok(options)
+    }
+
+    async fn connect(&self) -> Result<Surreal<Any>, String> {
+        let endpoint = self.endpoint.as_deref().ok_or("missing --endpoint")?;
+        let db = connect(endpoint).await.map_err(|e| e.to_string())?;
+        if let (Some(username), Some(password)) = (&self.user, &self.pass) {
+            db.signin(Root { username, password }).await.map_err(|e| e.to_string())?;
+        }
+        let (Some(ns), Some(name)) = (&self.ns, &self.db) else {
+            return Err("missing --ns or --db".to_string());
+        };
+        db.use_ns(ns).use_db(name).await.map_err(|e| e.to_string())?;
+        Ok(db)
+    }
+}
+
+/// The functions of every file at `paths`, failing on the first file that doesn't parse.
+fn read(paths: &[String]) -> Result<Vec<DefineFunctionStatement>, String> {
+    let mut out = vec![];
+    for path in paths {
+        out.extend(parse_function_files(path).map_err(|e| e.to_string())?);
+    }
+    Ok(out)
+}
+
+/// The definition of every function of the database, by name without the `fn::` prefix.
+async fn defined(db: &Surreal<Any>) -> Result<BTreeMap<String, String>, String> {
+    let mut response = db.query("INFO FOR DB").await.and_then(|response| response.check()).map_err(|e| e.to_string())?;
+    let info: Value = response.take(0).map_err(|e| e.to_string())?;
+    function_definitions(info).map_err(|e| e.to_string())
+}
+
+/// What `apply` would run, as `(marker, name, statement)`, `+` for new functions, `~` for changed ones and `-` for removed ones.
+async fn plan(db: &Surreal<Any>, functions: &[DefineFunctionStatement]) -> Result<Vec<(char, String, String)>, String> {
+    let mut existing = defined(db).await?;
+    let mut out = vec![];
+    for function in functions {
+        let name = function.name.join("::");
+        let statement = function.definition.clone().unwrap_or_default();
+        let marker = match existing.remove(&name) {
+            None => '+',
+            // Compared once parsed, so formatting doesn't matter
+            Some(current) if matches!((surrealdb::sql::parse(&current), surrealdb::sql::parse(&statement)), (Ok(a), Ok(b)) if a == b) => continue,
+            Some(_) => '~',
+        };
+        out.push((marker, name, statement));
+    }
+    for name in existing.into_keys() {
+        let statement = format!("REMOVE FUNCTION fn::{name}");
+        out.push(('-', name, statement));
+    }
+    Ok(out)
+}
+
+async fn run(options: Options) -> Result<ExitCode, String> {
+    let functions = read(&options.paths)?;
+    match options.command.as_str() {
+        "list" => {
+            for function in &functions {
+                println!("{}", function.signature());
+            }
+        }
+        "validate" => println!("{} functions", functions.len()),
+        "diff" => {
+            let plan = plan(&options.connect().await?, &functions).await?;
+            for (marker, name, _) in &plan {
+                println!("{marker} fn::{name}");
+            }
+            if !plan.is_empty() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        "apply" => {
+            let db = options.connect().await?;
+            let plan = plan(&db, &functions).await?;
+            let mut query = "BEGIN TRANSACTION;\n".to_string();
+            for (marker, name, statement) in &plan {
+                if *marker == '-' && !options.prune {
+                    continue;
+                }
+                println!("{marker} fn::{name}");
+                query.push_str(statement);
+                query.push_str(";\n");
+            }
+            query.push_str("COMMIT TRANSACTION;");
+            db.query(query).await.and_then(|response| response.check()).map_err(|e| e.to_string())?;
+        }
+        command => return Err(format!("unknown command {command}")),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match run(options).await {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! Runtime companion of [`surrealdb_functions`](https://docs.rs/surrealdb_functions).
+//!
+//! `include_fn!` embeds the .surql files into the binary, so changing a function means recompiling.
+//! With the `runtime-reload` feature of both crates, the generated `load_functions_from(path, db)` reads them at runtime instead,
+//! using the same parser as the macro, so a dev server can define edited functions again while it runs.
+//!
+//! Functions the binary has wrappers for are checked first: if one went missing or its parameters changed,
+//! nothing is defined, calling it through the compiled wrapper would fail.
+//!
+//! Build scripts and tooling can use [`parse_functions`] to read the signatures of a .surql file the way the macro does,
+//! without depending on a proc-macro crate:
+//!
+//! ```
+//! let functions = surrealdb_functions_runtime::parse_functions(
+//!     "DEFINE FUNCTION fn::greet($name: string) -> string { RETURN 'Hello ' + $name; };",
+//! )
+//! .unwrap();
+//! assert_eq!(functions[0].name, ["greet"]);
+//! assert_eq!(functions[0].args[0].1.to_string(), "string");
+//! assert_eq!(functions[0].signature(), "fn::greet($name: string) -> string");
+//! ```
+//!
+//! [`write_proto`] writes a .proto file with an rpc for every function for tonic to compile, which the `grpc` option
+//! of `include_fn!` implements the service of:
+//!
+//! ```no_run
+//! // In `fn main()` of build.rs
+//! let functions = surrealdb_functions_runtime::parse_functions(&std::fs::read_to_string("surql/main.surql").unwrap()).unwrap();
+//! let proto = surrealdb_functions_runtime::write_proto("functions", &functions).unwrap();
+//! // tonic_build::compile_protos(proto).unwrap();
+//! ```
+
+mod reload;
+
+// Shared with the macro, so both read the files and name the messages the same way
+use surrealdb_functions_core::{parser, proto};
+
+#[cfg(feature = "migrations")]
+pub use reload::function_definitions;
+#[cfg(feature = "runtime-reload")]
+pub use reload::load_functions_from;
+pub use parser::{
+    directive::Directive,
+    ident::Ident,
+    kind::{Kind, Literal},
+    table::Table,
+    DefineFunctionStatement, ParseError,
+};
+pub use proto::proto_file;
+pub use reload::{parse_function_files, read_functions, LoadError};
+
+/// Parses the `DEFINE FUNCTION` statements of a .surql file, in the order they're written.
+///
+/// Fails the same way `include_fn!` does, on anything that isn't a function, a comment or whitespace.
+pub fn parse_functions(text: &str) -> Result<Vec<DefineFunctionStatement>, ParseError> {
+    Ok(parser::statements(text)?.into_iter().map(|(_, function)| function).collect())
+}
+
+/// Writes the [`proto_file`] of `functions` to `$OUT_DIR/<package>.proto`, returning its path.
+///
+/// Meant for build scripts, for tonic to compile it before `include_fn!` implements the service with `grpc as <module>`.
+pub fn write_proto(package: &str, functions: &[DefineFunctionStatement]) -> std::io::Result<std::path::PathBuf> {
+    let dir = std::env::var_os("OUT_DIR").ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "OUT_DIR isn't set, call it from a build script"))?;
+    let path = std::path::Path::new(&dir).join(format!("{package}.proto"));
+    std::fs::write(&path, proto_file(package, functions))?;
+    Ok(path)
+}
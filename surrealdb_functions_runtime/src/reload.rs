@@ -0,0 +1,200 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::DefineFunctionStatement;
+
+/// Why the functions couldn't be reloaded.
+#[derive(Debug)]
+pub enum LoadError {
+    Io(PathBuf, io::Error),
+    /// A file isn't made of `DEFINE FUNCTION` statements, with the position and line it stopped at.
+    Parse(PathBuf, String),
+    /// Functions the compiled wrappers call that are missing or changed, one `fn::name(...)` line each.
+    Incompatible(Vec<String>),
+    #[cfg(feature = "runtime-reload")]
+    Db(surrealdb::Error),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(path, e) => write!(f, "{}: {e}", path.display()),
+            LoadError::Parse(path, e) => write!(f, "{}:{e}", path.display()),
+            LoadError::Incompatible(changes) => {
+                write!(f, "functions changed since the binary was compiled: {}", changes.join(", "))
+            }
+            #[cfg(feature = "runtime-reload")]
+            LoadError::Db(e) => Display::fmt(e, f),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+/// Reads the .surql files at `path`, a file or a directory walked recursively in sorted order, returning their text.
+///
+/// `signatures` are the functions the binary has wrappers for, like `fn::greet($name: string)`,
+/// each one has to be defined again the same way.
+pub fn read_functions(path: impl AsRef<Path>, signatures: &[&str]) -> Result<String, LoadError> {
+    let mut out = String::new();
+    let mut found = BTreeMap::new();
+    for (text, functions) in read_files(path.as_ref())? {
+        for function in functions {
+            found.insert(function.name.join("::"), function.signature());
+        }
+        out.push_str(&text);
+        out.push('\n');
+    }
+
+    let mut changes = vec![];
+    for signature in signatures {
+        let name = signature.trim_start_matches("fn::").split('(').next().unwrap_or_default();
+        match found.get(name) {
+            None => changes.push(format!("missing {signature}")),
+            Some(now) if now != signature => changes.push(format!("changed {signature} => {now}")),
+            Some(_) => {}
+        }
+    }
+    if !changes.is_empty() {
+        return Err(LoadError::Incompatible(changes));
+    }
+    Ok(out)
+}
+
+/// Parses the .surql files at `path`, walked like [`read_functions`], returning their functions in order.
+///
+/// Every file is parsed on its own, so a parse error points into the file that has it.
+pub fn parse_function_files(path: impl AsRef<Path>) -> Result<Vec<DefineFunctionStatement>, LoadError> {
+    Ok(read_files(path.as_ref())?.into_iter().flat_map(|(_, functions)| functions).collect())
+}
+
+/// Reads the .surql files at `path` like [`read_functions`] and defines them using `db`.
+///
+/// Nothing is defined if a file can't be parsed or a function the wrappers call changed.
+#[cfg(feature = "runtime-reload")]
+pub async fn load_functions_from<C: surrealdb::Connection>(
+    path: impl AsRef<Path>,
+    db: &surrealdb::Surreal<C>,
+    signatures: &[&str],
+) -> Result<surrealdb::Response, LoadError> {
+    let text = read_functions(path, signatures)?;
+    db.query(text).await.map_err(LoadError::Db)
+}
+
+/// The definition of every function in `info`, the result of `INFO FOR DB`, by name without the `fn::` prefix.
+///
+/// Used by the `defined_functions(db)` generated for `migrations` and `verify`, and by the `surrealdb-functions` tool.
+#[cfg(feature = "migrations")]
+pub fn function_definitions(info: surrealdb::sql::Value) -> surrealdb::Result<BTreeMap<String, String>> {
+    use surrealdb::{error::Api, sql::Value};
+
+    let unexpected = |info: &Value| surrealdb::Error::Api(Api::InvalidRequest(format!("unexpected result of INFO FOR DB: {info}")));
+    let Value::Array(infos) = info else {
+        return Err(unexpected(&info));
+    };
+    let mut out = BTreeMap::new();
+    for info in infos.0 {
+        let Value::Object(mut info) = info else {
+            return Err(unexpected(&info));
+        };
+        let Some(Value::Object(functions)) = info.0.remove("functions") else { continue };
+        for (name, definition) in functions.0 {
+            if let Value::Strand(definition) = definition {
+                // Nested names are escaped, like `nested::greet`, which doesn't parse back
+                let unescaped = name.trim_start_matches(['`', '⟨']).trim_end_matches(['`', '⟩']);
+                let definition = definition.0.replacen(&format!("fn::{name}"), &format!("fn::{unescaped}"), 1);
+                out.insert(unescaped.to_string(), definition);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// The text and functions of every .surql file at `path`, in the order they're read.
+fn read_files(path: &Path) -> Result<Vec<(String, Vec<DefineFunctionStatement>)>, LoadError> {
+    let mut files = vec![];
+    expand_path(path, &mut files)?;
+    files
+        .into_iter()
+        .map(|file| {
+            let text = std::fs::read_to_string(&file).map_err(|e| LoadError::Io(file.clone(), e))?;
+            let functions = crate::parse_functions(&text).map_err(|e| LoadError::Parse(file, e.to_string()))?;
+            Ok((text, functions))
+        })
+        .collect()
+}
+
+fn expand_path(path: &Path, out: &mut Vec<PathBuf>) -> Result<(), LoadError> {
+    if !path.is_dir() {
+        if path.extension().unwrap_or_default() == "surql" {
+            out.push(path.to_path_buf());
+        }
+        return Ok(());
+    }
+    // Sorted like the macro walks directories, so later files override earlier ones the same way
+    let mut entries = path
+        .read_dir()
+        .and_then(|entries| entries.map(|entry| entry.map(|entry| entry.path())).collect::<Result<Vec<_>, _>>())
+        .map_err(|e| LoadError::Io(path.to_path_buf(), e))?;
+    entries.sort();
+    for entry in entries {
+        expand_path(&entry, out)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_compatible_functions() {
+        let dir = std::env::temp_dir().join("surrealdb_functions_runtime_reload");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("a.surql"), "DEFINE FUNCTION fn::a($name: string) { RETURN $name; };").unwrap();
+        std::fs::write(dir.join("nested/b.surql"), "DEFINE FUNCTION fn::b() -> int { RETURN 1; };").unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a function").unwrap();
+
+        let text = read_functions(&dir, &["fn::a($name: string)", "fn::b() -> int"]).unwrap();
+        assert!(text.contains("fn::a") && text.contains("fn::b") && !text.contains("not a function"));
+
+        let err = read_functions(&dir, &["fn::a($name: int)", "fn::c()"]).unwrap_err();
+        match err {
+            LoadError::Incompatible(changes) => assert_eq!(
+                changes,
+                vec!["changed fn::a($name: int) => fn::a($name: string)", "missing fn::c()"]
+            ),
+            other => panic!("{other}"),
+        }
+
+        std::fs::write(dir.join("broken.surql"), "DEFINE FUNCTION fn::broken(").unwrap();
+        assert!(matches!(read_functions(&dir, &[]), Err(LoadError::Parse(..))));
+    }
+
+    #[test]
+    fn parses_every_file_on_its_own() {
+        let dir = std::env::temp_dir().join("surrealdb_functions_runtime_files");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.surql"), "OPTION IMPORT;\n\nDEFINE FUNCTION fn::a() { RETURN 1; };").unwrap();
+        std::fs::write(dir.join("b.surql"), "OPTION IMPORT;\n\nDEFINE FUNCTION fn::b() { RETURN 2; };").unwrap();
+
+        let functions = parse_function_files(&dir).unwrap();
+        assert_eq!(functions.iter().map(|function| function.name.join("::")).collect::<Vec<_>>(), ["a", "b"]);
+
+        std::fs::write(dir.join("c.surql"), "\n\nDEFINE FUNCTION fn::c(").unwrap();
+        match parse_function_files(&dir).unwrap_err() {
+            LoadError::Parse(path, e) => {
+                assert_eq!(path, dir.join("c.surql"));
+                assert!(e.starts_with('3'), "{e}");
+            }
+            other => panic!("{other}"),
+        }
+    }
+}
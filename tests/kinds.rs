@@ -0,0 +1,142 @@
+//! Every kind through both the driver and the datastore wrappers, checking the value comes back unchanged.
+
+use std::{collections::HashMap, str::FromStr};
+
+use surrealdb::{
+    dbs::Session,
+    engine::local::{Db, Mem},
+    kvs::Datastore,
+    sql::{thing, value, Array, Bytes, Geometry, Uuid, Value},
+    Surreal,
+};
+use surrealdb_functions::include_fn;
+
+include_fn! {
+    driver as drv_$;
+    datastore as ds_$;
+    tests;
+    "$CARGO_MANIFEST_DIR/tests/kinds.surql"
+}
+
+/// Runs a function through both wrappers, recording a failure for each one not returning `$expected`.
+macro_rules! case {
+    ($failures:ident, $db:ident, $ds:ident, $ses:ident, $name:literal, $driver:path, $datastore:path, ($($arg:expr),*), $expected:expr) => {{
+        let expected: Value = $expected;
+        let driver = driver_result($driver(&$db, $($arg),*).await);
+        if driver != expected {
+            $failures.push(format!("{} (driver): expected {expected:?}, got {driver:?}", $name));
+        }
+        let datastore = $datastore(&$ds, &$ses, $($arg),*).await.unwrap_or_else(|e| Value::from(format!("error: {e}")));
+        if datastore != expected {
+            $failures.push(format!("{} (datastore): expected {expected:?}, got {datastore:?}", $name));
+        }
+    }};
+}
+
+/// The result of the call, `NONE` coming back as an empty array.
+fn driver_result(response: surrealdb::Result<surrealdb::Response>) -> Value {
+    let result = response.and_then(|response| response.check()).and_then(|mut response| response.take::<Value>(0));
+    match result {
+        Ok(Value::Array(mut results)) if results.len() == 1 => results.0.remove(0),
+        Ok(Value::Array(results)) if results.is_empty() => Value::None,
+        Ok(other) => other,
+        Err(e) => Value::from(format!("error: {e}")),
+    }
+}
+
+fn parse(input: &str) -> Value {
+    value(input).unwrap()
+}
+
+async fn setup() -> (Surreal<Db>, Datastore, Session) {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    drv_define_functions(&db).await.unwrap().check().unwrap();
+
+    let ds = Datastore::new("memory").await.unwrap();
+    let ses = Session::for_kv().with_ns("test").with_db("test");
+    for response in ds_define_functions(&ds, &ses).await.unwrap() {
+        response.result.unwrap();
+    }
+    (db, ds, ses)
+}
+
+#[tokio::test]
+async fn every_kind() {
+    let (db, ds, ses) = setup().await;
+    let mut failures = vec![];
+    let uuid = || Uuid::from_str("e72bee20-f49b-11ec-b939-0242ac120002").unwrap();
+    let datetime = || chrono::DateTime::<chrono::Utc>::default();
+    let range = || match parse("1..3") {
+        Value::Range(range) => *range,
+        _ => unreachable!(),
+    };
+
+    case!(failures, db, ds, ses, "any", drv_any, ds_any, ("hello"), parse("'hello'"));
+    case!(failures, db, ds, ses, "bool", drv_bool, ds_bool, (true), Value::Bool(true));
+    case!(failures, db, ds, ses, "bytes", drv_bytes, ds_bytes, (vec![1u8, 2, 3]), Value::Bytes(Bytes::from(vec![1u8, 2, 3])));
+    case!(failures, db, ds, ses, "datetime", drv_datetime, ds_datetime, (datetime()), Value::from(datetime()));
+    case!(failures, db, ds, ses, "decimal", drv_decimal, ds_decimal, (10.69), parse("10.69"));
+    case!(failures, db, ds, ses, "duration", drv_duration, ds_duration, (std::time::Duration::from_secs(10)), parse("10s"));
+    case!(failures, db, ds, ses, "float", drv_float, ds_float, (10.5), parse("10.5"));
+    case!(failures, db, ds, ses, "int", drv_int, ds_int, (10), parse("10"));
+    case!(failures, db, ds, ses, "number", drv_number, ds_number, (10), parse("10"));
+    case!(failures, db, ds, ses, "object", drv_object, ds_object, (HashMap::from([("hello", parse("10"))])), parse("{ hello: 10 }"));
+    case!(failures, db, ds, ses, "point", drv_point, ds_point, ((10.0, 15.0)), parse("(10.0, 15.0)"));
+    case!(failures, db, ds, ses, "string", drv_string, ds_string, ("hello"), parse("'hello'"));
+    case!(failures, db, ds, ses, "regex", drv_regex, ds_regex, ("a.b"), parse("/a.b/"));
+    case!(failures, db, ds, ses, "range", drv_range, ds_range, (range()), parse("1..3"));
+    case!(failures, db, ds, ses, "uuid", drv_uuid, ds_uuid, (uuid()), Value::Uuid(uuid()));
+    case!(failures, db, ds, ses, "record", drv_record, ds_record, (thing("table:id").unwrap()), parse("table:id"));
+    case!(failures, db, ds, ses, "record<table>", drv_record_table, ds_record_table, (thing("table:id").unwrap()), parse("table:id"));
+    case!(failures, db, ds, ses, "record<a | b>", drv_record_tables, ds_record_tables, (thing("b:id").unwrap()), parse("b:id"));
+    case!(failures, db, ds, ses, "geometry", drv_geometry, ds_geometry, ((10.0, 15.0)), parse("(10.0, 15.0)"));
+    case!(failures, db, ds, ses, "geometry<point | polygon>", drv_geometry_types, ds_geometry_types, (Geometry::from((10.0, 15.0))), parse("(10.0, 15.0)"));
+    case!(failures, db, ds, ses, "bool | int (bool)", drv_either, ds_either, (true), Value::Bool(true));
+    case!(failures, db, ds, ses, "bool | int (int)", drv_either, ds_either, (10), parse("10"));
+    case!(failures, db, ds, ses, "int | array<int> (int)", drv_either_array, ds_either_array, (10), parse("10"));
+    case!(failures, db, ds, ses, "int | array<int> (array)", drv_either_array, ds_either_array, (vec![1, 2]), parse("[1, 2]"));
+    case!(failures, db, ds, ses, "set", drv_set, ds_set, (vec!["a", "b", "b"]), parse("['a', 'b']"));
+    case!(failures, db, ds, ses, "set<int>", drv_set_int, ds_set_int, (vec![2, 1, 2]), parse("[2, 1]"));
+    case!(failures, db, ds, ses, "array", drv_array, ds_array, (vec!["a", "b"]), parse("['a', 'b']"));
+    case!(failures, db, ds, ses, "array<string>", drv_array_string, ds_array_string, (vec!["a", "b"]), parse("['a', 'b']"));
+    case!(failures, db, ds, ses, "array<int, 2>", drv_array_max, ds_array_max, ([1, 2]), parse("[1, 2]"));
+
+    case!(failures, db, ds, ses, "option<bool> (some)", option::drv_bool, option::ds_bool, (Some(true)), Value::Bool(true));
+    case!(failures, db, ds, ses, "option<bytes> (some)", option::drv_bytes, option::ds_bytes, (Some(Bytes::from(vec![1u8]))), Value::Bytes(Bytes::from(vec![1u8])));
+    case!(failures, db, ds, ses, "option<datetime> (some)", option::drv_datetime, option::ds_datetime, (Some(datetime().into())), Value::from(datetime()));
+    case!(failures, db, ds, ses, "option<decimal> (some)", option::drv_decimal, option::ds_decimal, (Some(10.69.into())), parse("10.69"));
+    case!(failures, db, ds, ses, "option<duration> (some)", option::drv_duration, option::ds_duration, (Some(std::time::Duration::from_secs(10).into())), parse("10s"));
+    case!(failures, db, ds, ses, "option<float> (some)", option::drv_float, option::ds_float, (Some(10.5)), parse("10.5"));
+    case!(failures, db, ds, ses, "option<int> (some)", option::drv_int, option::ds_int, (Some(10)), parse("10"));
+    case!(failures, db, ds, ses, "option<number> (some)", option::drv_number, option::ds_number, (Some(10.into())), parse("10"));
+    case!(failures, db, ds, ses, "option<object> (some)", option::drv_object, option::ds_object, (Some(HashMap::from([("hello", parse("10"))]).into())), parse("{ hello: 10 }"));
+    case!(failures, db, ds, ses, "option<point> (some)", option::drv_point, option::ds_point, (Some((10.0, 15.0).into())), parse("(10.0, 15.0)"));
+    case!(failures, db, ds, ses, "option<string> (some)", option::drv_string, option::ds_string, (Some("hello".to_string())), parse("'hello'"));
+    case!(failures, db, ds, ses, "option<uuid> (some)", option::drv_uuid, option::ds_uuid, (Some(uuid())), Value::Uuid(uuid()));
+    case!(failures, db, ds, ses, "option<record<table>> (some)", option::drv_record, option::ds_record, (Some(thing("table:id").unwrap())), parse("table:id"));
+    case!(failures, db, ds, ses, "option<geometry<point>> (some)", option::drv_geometry, option::ds_geometry, (Some((10.0, 15.0).into())), parse("(10.0, 15.0)"));
+    case!(failures, db, ds, ses, "option<bool | int> (some)", option::drv_either, option::ds_either, (Some(Value::from(10))), parse("10"));
+    case!(failures, db, ds, ses, "option<set<int>> (some)", option::drv_set, option::ds_set, (Some(Array::from(vec![2, 1, 2]))), parse("[2, 1]"));
+    case!(failures, db, ds, ses, "option<array<int>> (some)", option::drv_array, option::ds_array, (Some(Array::from(vec![1, 2]))), parse("[1, 2]"));
+
+    case!(failures, db, ds, ses, "option<bool> (none)", option::drv_bool, option::ds_bool, (None), Value::None);
+    case!(failures, db, ds, ses, "option<bytes> (none)", option::drv_bytes, option::ds_bytes, (None), Value::None);
+    case!(failures, db, ds, ses, "option<datetime> (none)", option::drv_datetime, option::ds_datetime, (None), Value::None);
+    case!(failures, db, ds, ses, "option<decimal> (none)", option::drv_decimal, option::ds_decimal, (None), Value::None);
+    case!(failures, db, ds, ses, "option<duration> (none)", option::drv_duration, option::ds_duration, (None), Value::None);
+    case!(failures, db, ds, ses, "option<float> (none)", option::drv_float, option::ds_float, (None), Value::None);
+    case!(failures, db, ds, ses, "option<int> (none)", option::drv_int, option::ds_int, (None), Value::None);
+    case!(failures, db, ds, ses, "option<number> (none)", option::drv_number, option::ds_number, (None), Value::None);
+    case!(failures, db, ds, ses, "option<object> (none)", option::drv_object, option::ds_object, (None), Value::None);
+    case!(failures, db, ds, ses, "option<point> (none)", option::drv_point, option::ds_point, (None), Value::None);
+    case!(failures, db, ds, ses, "option<string> (none)", option::drv_string, option::ds_string, (None), Value::None);
+    case!(failures, db, ds, ses, "option<uuid> (none)", option::drv_uuid, option::ds_uuid, (None), Value::None);
+    case!(failures, db, ds, ses, "option<record<table>> (none)", option::drv_record, option::ds_record, (None), Value::None);
+    case!(failures, db, ds, ses, "option<geometry<point>> (none)", option::drv_geometry, option::ds_geometry, (None), Value::None);
+    case!(failures, db, ds, ses, "option<bool | int> (none)", option::drv_either, option::ds_either, (None), Value::None);
+    case!(failures, db, ds, ses, "option<set<int>> (none)", option::drv_set, option::ds_set, (None), Value::None);
+    case!(failures, db, ds, ses, "option<array<int>> (none)", option::drv_array, option::ds_array, (None), Value::None);
+
+    assert!(failures.is_empty(), "{} kinds failed:\n{}", failures.len(), failures.join("\n"));
+}